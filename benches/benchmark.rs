@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use lctree::LinkCutTree;
+use lctree::{FindMax, IndexType, LinkCutTree, Path, TreeGenerator};
 use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use rand_derive2::RandGen;
 use std::collections::{HashMap, HashSet};
@@ -17,7 +17,7 @@ fn benchmark(criterion: &mut Criterion) {
 
         group.bench_function("lctree", |bencher| {
             bencher.iter(|| {
-                lctree(
+                lctree::<usize>(
                     black_box(num_nodes[i]),
                     black_box(num_operations[i]),
                     black_box(seeds[i]),
@@ -37,7 +37,217 @@ fn benchmark(criterion: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, benchmark);
+// Smaller index types shrink the nodes (and the cache misses during access):
+fn index_types(criterion: &mut Criterion) {
+    // u16 ids address at most 65535 nodes (the largest id is reserved as "no node"):
+    let (num_nodes, num_operations, seed) = (60_000, 1_000_000, 0);
+    let mut group = criterion.benchmark_group(format!("index_types_{num_nodes}").as_str());
+    group.sample_size(10);
+    group.bench_function("usize", |bencher| {
+        bencher.iter(|| lctree::<usize>(black_box(num_nodes), black_box(num_operations), seed));
+    });
+    group.bench_function("u32", |bencher| {
+        bencher.iter(|| lctree::<u32>(black_box(num_nodes), black_box(num_operations), seed));
+    });
+    group.bench_function("u16", |bencher| {
+        bencher.iter(|| lctree::<u16>(black_box(num_nodes), black_box(num_operations), seed));
+    });
+}
+
+type Generate = fn(&mut TreeGenerator, &mut LinkCutTree<FindMax>, &[f64]);
+
+// Path queries on the shapes of trees that are the best and the worst cases for the splay trees:
+fn tree_shapes(criterion: &mut Criterion) {
+    let (num_nodes, num_queries, seed) = (10_000, 100_000, 0);
+    let weights = (0..num_nodes).map(|i| i as f64).collect::<Vec<_>>();
+    let mut group = criterion.benchmark_group(format!("tree_shapes_{num_nodes}").as_str());
+    group.sample_size(10);
+    let shapes: [(&str, Generate); 4] = [
+        ("uniform", |gen, lctree, weights| {
+            gen.uniform_tree(lctree, weights);
+        }),
+        ("path", |gen, lctree, weights| {
+            gen.path(lctree, weights);
+        }),
+        ("star", |gen, lctree, weights| {
+            gen.star(lctree, weights);
+        }),
+        ("caterpillar", |gen, lctree, weights| {
+            gen.caterpillar(lctree, weights, weights.len() / 10);
+        }),
+    ];
+    for (name, generate) in shapes {
+        group.bench_function(name, |bencher| {
+            bencher.iter(|| {
+                let mut generator = TreeGenerator::new(seed);
+                let mut lctree = LinkCutTree::new();
+                generate(&mut generator, &mut lctree, &weights);
+                let mut rng = StdRng::seed_from_u64(seed);
+                for _ in 0..num_queries {
+                    let v = rng.gen_range(0..num_nodes);
+                    let w = rng.gen_range(0..num_nodes);
+                    black_box(lctree.path(v, w));
+                }
+            });
+        });
+    }
+}
+
+// The 8 largest weights on a path, an aggregate that is expensive to recompute:
+#[derive(Clone)]
+struct TopK {
+    weights: Vec<f64>,
+}
+
+impl Path for TopK {
+    fn default(weight: f64, _: usize) -> Self {
+        TopK {
+            weights: vec![weight],
+        }
+    }
+
+    fn aggregate(&mut self, other: Self) {
+        self.weights.extend(other.weights);
+        self.weights.sort_by(|a, b| b.total_cmp(a));
+        self.weights.truncate(8);
+    }
+}
+
+// The same aggregate, reusing its vector when it is reset and merging the children in place:
+#[derive(Clone)]
+struct TopKInPlace {
+    weights: Vec<f64>,
+}
+
+impl Path for TopKInPlace {
+    fn default(weight: f64, _: usize) -> Self {
+        TopKInPlace {
+            weights: vec![weight],
+        }
+    }
+
+    fn aggregate(&mut self, other: Self) {
+        self.aggregate_from(&other);
+    }
+
+    fn reset(&mut self, weight: f64, _: usize) {
+        self.weights.clear();
+        self.weights.push(weight);
+    }
+
+    fn aggregate_from(&mut self, other: &Self) {
+        self.weights.extend_from_slice(&other.weights);
+        self.weights.sort_by(|a, b| b.total_cmp(a));
+        self.weights.truncate(8);
+    }
+}
+
+// Path queries with an aggregate-heavy workload, where the running time is dominated by
+// the number of aggregate recomputations during the splay operations:
+fn heavy_aggregate(criterion: &mut Criterion) {
+    let (num_nodes, num_queries, seed) = (10_000, 100_000, 0);
+    let weights = (0..num_nodes).map(|i| i as f64).collect::<Vec<_>>();
+    let mut group = criterion.benchmark_group(format!("heavy_aggregate_{num_nodes}").as_str());
+    group.sample_size(10);
+    group.bench_function("top_k", |bencher| {
+        bencher.iter(|| heavy_queries::<TopK>(num_nodes, num_queries, seed, &weights));
+    });
+    group.bench_function("top_k_in_place", |bencher| {
+        bencher.iter(|| heavy_queries::<TopKInPlace>(num_nodes, num_queries, seed, &weights));
+    });
+}
+
+fn heavy_queries<P: Path>(num_nodes: usize, num_queries: usize, seed: u64, weights: &[f64]) {
+    let mut lctree: LinkCutTree<P> = LinkCutTree::new();
+    TreeGenerator::new(seed).uniform_tree(&mut lctree, weights);
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..num_queries {
+        let v = rng.gen_range(0..num_nodes);
+        let w = rng.gen_range(0..num_nodes);
+        black_box(lctree.path(v, w));
+    }
+}
+
+// Workload profiles that stress different parts of the link-cut tree, with the percentages
+// of links, cuts (of existing edges) and path queries (the rest of the operations):
+fn workloads(criterion: &mut Criterion) {
+    let (num_nodes, num_operations, seed) = (10_000, 200_000, 0);
+    let mut group = criterion.benchmark_group(format!("workloads_{num_nodes}").as_str());
+    group.sample_size(10);
+    let profiles = [("path_heavy", 10, 5), ("cut_heavy", 45, 45)];
+    for (name, links, cuts) in profiles {
+        group.bench_function(name, |bencher| {
+            bencher.iter(|| workload(num_nodes, num_operations, seed, links, cuts));
+        });
+    }
+
+    // Links and connectivity queries only, where a union-find is the natural baseline:
+    group.bench_function("link_only", |bencher| {
+        bencher.iter(|| {
+            let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+            let _ = lctree.extend_forest(vec![0.0; num_nodes]);
+            let mut rng = StdRng::seed_from_u64(seed);
+            for _ in 0..num_operations {
+                let v = rng.gen_range(0..num_nodes);
+                let w = rng.gen_range(0..num_nodes);
+                if rng.gen_bool(0.5) {
+                    black_box(lctree.link(v, w));
+                } else {
+                    black_box(lctree.connected(v, w));
+                }
+            }
+        });
+    });
+    group.bench_function("link_only_union_find", |bencher| {
+        bencher.iter(|| {
+            let mut union_find = UnionFind::new(num_nodes);
+            let mut rng = StdRng::seed_from_u64(seed);
+            for _ in 0..num_operations {
+                let v = rng.gen_range(0..num_nodes);
+                let w = rng.gen_range(0..num_nodes);
+                if rng.gen_bool(0.5) {
+                    union_find.union(v, w);
+                } else {
+                    black_box(union_find.find(v) == union_find.find(w));
+                }
+            }
+        });
+    });
+}
+
+fn workload(num_nodes: usize, num_operations: usize, seed: u64, links: u32, cuts: u32) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let weights = (0..num_nodes).map(|i| i as f64).collect::<Vec<_>>();
+    let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    let _ = lctree.extend_forest(weights);
+    let mut edges = Vec::new();
+    for _ in 0..num_operations {
+        let v = rng.gen_range(0..num_nodes);
+        let w = rng.gen_range(0..num_nodes);
+        let operation = rng.gen_range(0..100);
+        if operation < links {
+            if lctree.link(v, w).is_linked() {
+                edges.push((v, w));
+            }
+        } else if operation < links + cuts {
+            if !edges.is_empty() {
+                let (v, w) = edges.swap_remove(rng.gen_range(0..edges.len()));
+                black_box(lctree.cut(v, w));
+            }
+        } else {
+            black_box(lctree.path(v, w));
+        }
+    }
+}
+
+criterion_group!(
+    benches,
+    benchmark,
+    index_types,
+    tree_shapes,
+    heavy_aggregate,
+    workloads
+);
 criterion_main!(benches);
 
 #[derive(RandGen)]
@@ -48,7 +258,7 @@ enum Operation {
     Path,
 }
 
-fn lctree(num_nodes: usize, num_operations: usize, seed: u64) {
+fn lctree<Ix: IndexType>(num_nodes: usize, num_operations: usize, seed: u64) {
     let mut rng = StdRng::seed_from_u64(seed);
     // Generate distinct random weights:
     let mut weights = (0..num_nodes).map(|i| i as f64).collect::<Vec<_>>();
@@ -56,7 +266,7 @@ fn lctree(num_nodes: usize, num_operations: usize, seed: u64) {
 
     // Initialize link-cut tree, we start with a forest of single nodes
     // (edges are not added yet):
-    let mut lctree = LinkCutTree::default();
+    let mut lctree: LinkCutTree<FindMax, Ix> = LinkCutTree::new();
     for w in 0..num_nodes {
         lctree.make_tree(weights[w]);
     }
@@ -216,3 +426,38 @@ impl BruteForce {
         usize::MAX
     }
 }
+
+// Union by size with path halving:
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(num_nodes: usize) -> Self {
+        Self {
+            parent: (0..num_nodes).collect(),
+            size: vec![1; num_nodes],
+        }
+    }
+
+    pub fn find(&mut self, mut v: usize) -> usize {
+        while self.parent[v] != v {
+            self.parent[v] = self.parent[self.parent[v]];
+            v = self.parent[v];
+        }
+        v
+    }
+
+    pub fn union(&mut self, v: usize, w: usize) {
+        let (mut v, mut w) = (self.find(v), self.find(w));
+        if v == w {
+            return;
+        }
+        if self.size[v] < self.size[w] {
+            std::mem::swap(&mut v, &mut w);
+        }
+        self.parent[w] = v;
+        self.size[v] += self.size[w];
+    }
+}
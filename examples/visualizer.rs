@@ -0,0 +1,139 @@
+//! An interactive visualizer of the link-cut tree.
+//!
+//! Run it with `cargo run --example visualizer` and type the operations, one per line
+//! (e.g. `make_tree 5`, `link 0 1`, `cut 0 1`, `path 0 1`, `connected 0 1`, `findroot 0`).
+//! After every operation, both the represented forest and its preferred paths
+//! (i.e. the auxiliary splay trees) are printed, which shows how the preferred paths evolve.
+//! The lazy updates (`subtree_add`, `path_add` and `path_assign`) need `--features lazy-updates`.
+use lctree::{FindMax, LinkCutTree, Operation};
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    println!("Type an operation (or `help`), an empty line quits.");
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 || line.trim().is_empty() {
+            break;
+        }
+        if line.trim() == "help" {
+            println!("Operations: make_tree <weight>, remove_tree <v>, link <v> <w>, cut <v> <w>,");
+            println!("            linked <v> <w>, connected <v> <w>, path <v> <w>, findroot <v>,");
+            println!(
+                "            subtree_add <v> <delta>, replace_weight <v> <weight>, reroot <v>,"
+            );
+            println!("            path_add <v> <w> <delta>, path_assign <v> <w> <weight>");
+            continue;
+        }
+        match line.parse::<Operation>() {
+            Ok(operation) => {
+                if is_valid(&lctree, operation) {
+                    apply(&mut lctree, operation);
+                    print_forest(&lctree);
+                } else {
+                    println!("Unknown node (or the tree contains more than one node).");
+                }
+            }
+            Err(err) => println!("Invalid operation: {err}"),
+        }
+    }
+}
+
+// Checks that the operation refers to existing nodes (the link-cut tree panics otherwise).
+fn is_valid(lctree: &LinkCutTree<FindMax>, operation: Operation) -> bool {
+    let exists = |v: usize| lctree.node_ids().any(|idx| idx == v);
+    match operation {
+        Operation::MakeTree(_) | Operation::IdReuse(_) | Operation::IdReusePolicy(_) => true,
+        Operation::RemoveTree(v)
+        | Operation::FindRoot(v)
+        | Operation::Reroot(v)
+        | Operation::SubtreeAdd(v, _)
+        | Operation::ReplaceWeight(v, _) => exists(v),
+        Operation::Link(v, w)
+        | Operation::Cut(v, w)
+        | Operation::Linked(v, w)
+        | Operation::Connected(v, w)
+        | Operation::Path(v, w)
+        | Operation::PathAdd(v, w, _)
+        | Operation::PathAssign(v, w, _) => exists(v) && exists(w),
+    }
+}
+
+fn apply(lctree: &mut LinkCutTree<FindMax>, operation: Operation) {
+    match operation {
+        Operation::MakeTree(weight) => println!("created node {}", lctree.make_tree(weight)),
+        Operation::RemoveTree(v) => match lctree.remove_tree(v) {
+            Ok(()) => println!("removed node {v}"),
+            Err(err) => println!("{err}"),
+        },
+        Operation::Link(v, w) => println!("{:?}", lctree.link(v, w)),
+        Operation::Cut(v, w) => println!("{:?}", lctree.cut(v, w)),
+        Operation::Linked(v, w) => println!("linked: {}", lctree.linked(v, w)),
+        Operation::Connected(v, w) => println!("connected: {}", lctree.connected(v, w)),
+        Operation::Path(v, w) => {
+            let path = lctree.path(v, w);
+            if path.idx == usize::MAX {
+                println!("not connected");
+            } else {
+                println!("max weight {} at node {}", path.weight, path.idx);
+            }
+        }
+        Operation::FindRoot(v) => println!("root: {}", lctree.findroot(v)),
+        #[cfg(feature = "lazy-updates")]
+        Operation::SubtreeAdd(v, delta) => lctree.subtree_add(v, delta),
+        #[cfg(feature = "lazy-updates")]
+        Operation::PathAdd(v, w, delta) => {
+            if !lctree.path_add(v, w, delta) {
+                println!("not connected");
+            }
+        }
+        #[cfg(feature = "lazy-updates")]
+        Operation::PathAssign(v, w, weight) => {
+            if !lctree.path_assign(v, w, weight) {
+                println!("not connected");
+            }
+        }
+        #[cfg(not(feature = "lazy-updates"))]
+        Operation::SubtreeAdd(..) | Operation::PathAdd(..) | Operation::PathAssign(..) => {
+            println!("the lazy updates require the `lazy-updates` feature");
+        }
+        Operation::ReplaceWeight(v, weight) => {
+            println!("old weight: {}", lctree.replace_weight(v, weight));
+        }
+        Operation::Reroot(v) => {
+            lctree.reroot(v);
+            println!("root: {v}");
+        }
+        Operation::IdReuse(enabled) => lctree.set_id_reuse(enabled),
+        Operation::IdReusePolicy(policy) => lctree.set_id_reuse_policy(policy),
+    }
+}
+
+// Prints every represented tree below its current root, followed by the preferred paths.
+fn print_forest(lctree: &LinkCutTree<FindMax>) {
+    let len = lctree.node_ids().last().map_or(0, |idx| idx + 1);
+    let mut children = vec![Vec::new(); len];
+    let mut has_parent = vec![false; len];
+    for (child, parent) in lctree.edges() {
+        children[parent].push(child);
+        has_parent[child] = true;
+    }
+    println!("Represented forest:");
+    for root in lctree.node_ids().filter(|&idx| !has_parent[idx]) {
+        let mut stack = vec![(root, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            println!("  {}{node}", "  ".repeat(depth));
+            for &child in children[node].iter().rev() {
+                stack.push((child, depth + 1));
+            }
+        }
+    }
+    println!("Preferred paths (auxiliary splay trees):");
+    for path in lctree.preferred_paths() {
+        let path = path.iter().map(ToString::to_string).collect::<Vec<_>>();
+        println!("  {}", path.join(" - "));
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::VecDeque;
+
+/// An event of the Euler tour of a tree (see [`LinkCutTree::euler_tour`](crate::LinkCutTree::euler_tour)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TourEvent {
+    /// The tour enters the subtree of the node.
+    Enter(usize),
+    /// The tour leaves the subtree of the node.
+    Exit(usize),
+}
+
+// Tracks the neighbors of every node in the represented forest,
+// so that the trees can be traversed without touching the splay trees.
+#[derive(Clone, Default)]
+pub struct Adjacency {
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl Adjacency {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_edges(edges: impl Iterator<Item = (usize, usize)>) -> Self {
+        let mut adjacency = Self::new();
+        for (v, w) in edges {
+            adjacency.link(v, w);
+        }
+        adjacency
+    }
+
+    pub fn link(&mut self, v: usize, w: usize) {
+        let len = v.max(w) + 1;
+        if self.neighbors.len() < len {
+            self.neighbors.resize_with(len, Vec::new);
+        }
+        self.neighbors[v].push(w);
+        self.neighbors[w].push(v);
+    }
+
+    pub fn cut(&mut self, v: usize, w: usize) {
+        self.remove_neighbor(v, w);
+        self.remove_neighbor(w, v);
+    }
+
+    fn remove_neighbor(&mut self, v: usize, w: usize) {
+        let neighbors = &mut self.neighbors[v];
+        if let Some(pos) = neighbors.iter().position(|&x| x == w) {
+            neighbors.swap_remove(pos);
+        }
+    }
+
+    pub fn neighbors(&self, v: usize) -> &[usize] {
+        self.neighbors.get(v).map_or(&[], Vec::as_slice)
+    }
+
+    // Iterates over the tree containing `v` in depth-first (pre-)order, starting from `v`.
+    pub fn dfs(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut stack = vec![(v, usize::MAX)];
+        std::iter::from_fn(move || {
+            let (node, parent) = stack.pop()?;
+            // push the neighbors in reverse, so that they are visited in order:
+            for &next in self.neighbors(node).iter().rev() {
+                if next != parent {
+                    stack.push((next, node));
+                }
+            }
+            Some(node)
+        })
+    }
+
+    // Iterates over the tree containing `root` in breadth-first order,
+    // yielding each node together with its depth.
+    pub fn bfs(&self, root: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut queue = VecDeque::from([(root, usize::MAX, 0)]);
+        std::iter::from_fn(move || {
+            let (node, parent, depth) = queue.pop_front()?;
+            for &next in self.neighbors(node) {
+                if next != parent {
+                    queue.push_back((next, node, depth + 1));
+                }
+            }
+            Some((node, depth))
+        })
+    }
+
+    // Lists the enter/exit events of the tree containing `root`, visited in depth-first order.
+    pub fn euler_tour(&self, root: usize) -> Vec<TourEvent> {
+        let mut tour = Vec::new();
+        let mut stack = vec![(root, usize::MAX, false)];
+        while let Some((node, parent, visited)) = stack.pop() {
+            if visited {
+                tour.push(TourEvent::Exit(node));
+                continue;
+            }
+            tour.push(TourEvent::Enter(node));
+            stack.push((node, parent, true));
+            for &next in self.neighbors(node).iter().rev() {
+                if next != parent {
+                    stack.push((next, node, false));
+                }
+            }
+        }
+        tour
+    }
+}
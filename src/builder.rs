@@ -0,0 +1,183 @@
+use std::marker::PhantomData;
+
+use crate::{
+    index::{IdReusePolicy, IndexType},
+    path::Path,
+    splay::Forest,
+    LinkCutTree,
+};
+
+/// Configures and constructs a [`LinkCutTree`].
+///
+/// # Examples
+/// ```
+/// use lctree::{FindSum, LinkCutTree, LinkCutTreeBuilder};
+///
+/// let mut lctree: LinkCutTree<FindSum> = LinkCutTreeBuilder::new()
+///     .capacity(1000)
+///     .instrumentation(true)
+///     .build();
+/// let alice = lctree.make_tree(1.0);
+/// let bob = lctree.make_tree(2.0);
+/// lctree.link(alice, bob);
+/// assert_eq!(lctree.path(alice, bob).sum, 3.0);
+/// assert!(lctree.cost_counters().is_some());
+/// ```
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct LinkCutTreeBuilder<P: Path, Ix: IndexType = usize> {
+    capacity: usize,
+    instrumentation: bool,
+    weight_validation: bool,
+    adjacency_tracking: bool,
+    #[cfg(feature = "component-sizes")]
+    component_tracking: bool,
+    query_cache: bool,
+    lazy_aggregates: bool,
+    id_reuse: bool,
+    id_reuse_policy: IdReusePolicy,
+    _path: PhantomData<(P, Ix)>,
+}
+
+impl<P: Path, Ix: IndexType> LinkCutTreeBuilder<P, Ix> {
+    /// Creates a builder with the default configuration
+    /// (which is the same as the one used by [`LinkCutTree::new`]).
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            instrumentation: false,
+            weight_validation: false,
+            adjacency_tracking: false,
+            #[cfg(feature = "component-sizes")]
+            component_tracking: false,
+            query_cache: false,
+            lazy_aggregates: false,
+            id_reuse: true,
+            id_reuse_policy: IdReusePolicy::Lifo,
+            _path: PhantomData,
+        }
+    }
+
+    /// Reserves space for at least `capacity` nodes, so that creating them does not reallocate.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Changes the type used to store node ids internally (see [`IndexType`](crate::IndexType)).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree, LinkCutTreeBuilder};
+    ///
+    /// let lctree: LinkCutTree<FindMax, u32> = LinkCutTreeBuilder::<FindMax>::new()
+    ///     .index_type::<u32>()
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn index_type<Jx: IndexType>(self) -> LinkCutTreeBuilder<P, Jx> {
+        LinkCutTreeBuilder {
+            capacity: self.capacity,
+            instrumentation: self.instrumentation,
+            weight_validation: self.weight_validation,
+            adjacency_tracking: self.adjacency_tracking,
+            #[cfg(feature = "component-sizes")]
+            component_tracking: self.component_tracking,
+            query_cache: self.query_cache,
+            lazy_aggregates: self.lazy_aggregates,
+            id_reuse: self.id_reuse,
+            id_reuse_policy: self.id_reuse_policy,
+            _path: PhantomData,
+        }
+    }
+
+    /// Enables collecting the cost counters of the operations
+    /// (see [`LinkCutTree::set_instrumentation`]).
+    #[must_use]
+    pub fn instrumentation(mut self, enabled: bool) -> Self {
+        self.instrumentation = enabled;
+        self
+    }
+
+    /// Enables rejecting NaN and infinite weights
+    /// (see [`LinkCutTree::set_weight_validation`]).
+    #[must_use]
+    pub fn weight_validation(mut self, enabled: bool) -> Self {
+        self.weight_validation = enabled;
+        self
+    }
+
+    /// Enables tracking the neighbors of every node to traverse the represented trees
+    /// (see [`LinkCutTree::set_adjacency_tracking`]).
+    #[must_use]
+    pub fn adjacency_tracking(mut self, enabled: bool) -> Self {
+        self.adjacency_tracking = enabled;
+        self
+    }
+
+    /// Enables tracking the number of trees of every size to find the largest tree
+    /// (see [`LinkCutTree::set_component_tracking`], requires the `component-sizes` feature).
+    #[must_use]
+    #[cfg(feature = "component-sizes")]
+    pub fn component_tracking(mut self, enabled: bool) -> Self {
+        self.component_tracking = enabled;
+        self
+    }
+
+    /// Enables memoizing the results of `path` and `connected` between the changes of the forest
+    /// (see [`LinkCutTree::set_query_cache`]).
+    #[must_use]
+    pub fn query_cache(mut self, enabled: bool) -> Self {
+        self.query_cache = enabled;
+        self
+    }
+
+    /// Enables recomputing the path aggregates only when they are queried
+    /// (see [`LinkCutTree::set_lazy_aggregates`]).
+    #[must_use]
+    pub fn lazy_aggregates(mut self, enabled: bool) -> Self {
+        self.lazy_aggregates = enabled;
+        self
+    }
+
+    /// Enables (or disables) reusing the ids of removed nodes
+    /// (see [`LinkCutTree::set_id_reuse`]).
+    #[must_use]
+    pub fn id_reuse(mut self, enabled: bool) -> Self {
+        self.id_reuse = enabled;
+        self
+    }
+
+    /// Changes the order in which the ids of removed nodes are reused
+    /// (see [`LinkCutTree::set_id_reuse_policy`]).
+    #[must_use]
+    pub fn id_reuse_policy(mut self, policy: IdReusePolicy) -> Self {
+        self.id_reuse_policy = policy;
+        self
+    }
+
+    /// Constructs an empty link-cut tree with the given configuration.
+    #[must_use]
+    pub fn build(self) -> LinkCutTree<P, Ix> {
+        let mut forest = Forest::with_capacity(self.capacity);
+        forest.set_instrumentation(self.instrumentation);
+        forest.set_id_reuse(self.id_reuse);
+        forest.set_id_reuse_policy(self.id_reuse_policy);
+        forest.set_lazy_aggregates(self.lazy_aggregates);
+        let mut lctree = LinkCutTree::from_forest(forest);
+        lctree.set_weight_validation(self.weight_validation);
+        lctree.set_adjacency_tracking(self.adjacency_tracking);
+        #[cfg(feature = "component-sizes")]
+        lctree.set_component_tracking(self.component_tracking);
+        lctree.set_query_cache(self.query_cache);
+        lctree
+    }
+}
+
+impl<P: Path, Ix: IndexType> Default for LinkCutTreeBuilder<P, Ix> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+// The maximum number of memoized results (of each kind), beyond which the cache starts over:
+const MAX_ENTRIES: usize = 1 << 16;
+
+// The memoized results of `path` and `connected` queries, which are valid as long as
+// the version of the forest does not change (see `LinkCutTree::set_query_cache`).
+pub struct QueryCache<P> {
+    version: u64, // the version of the forest the results were computed for
+    paths: HashMap<(usize, usize), P>,
+    connected: HashMap<(usize, usize), bool>, // keyed by the smaller id first
+}
+
+impl<P: Clone> QueryCache<P> {
+    pub fn new() -> Self {
+        Self {
+            version: 0,
+            paths: HashMap::new(),
+            connected: HashMap::new(),
+        }
+    }
+
+    // Drops the results of older versions of the forest.
+    fn sync(&mut self, version: u64) {
+        if self.version != version {
+            self.version = version;
+            self.paths.clear();
+            self.connected.clear();
+        }
+    }
+
+    pub fn path(&mut self, version: u64, v: usize, w: usize) -> Option<P> {
+        self.sync(version);
+        self.paths.get(&(v, w)).cloned()
+    }
+
+    pub fn insert_path(&mut self, version: u64, v: usize, w: usize, path: P) {
+        self.sync(version);
+        if self.paths.len() >= MAX_ENTRIES {
+            self.paths.clear();
+        }
+        self.paths.insert((v, w), path);
+    }
+
+    pub fn connected(&mut self, version: u64, v: usize, w: usize) -> Option<bool> {
+        self.sync(version);
+        self.connected.get(&(v.min(w), v.max(w))).copied()
+    }
+
+    pub fn insert_connected(&mut self, version: u64, v: usize, w: usize, connected: bool) {
+        self.sync(version);
+        if self.connected.len() >= MAX_ENTRIES {
+            self.connected.clear();
+        }
+        self.connected.insert((v.min(w), v.max(w)), connected);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryCache;
+
+    #[test]
+    pub fn versions() {
+        let mut cache: QueryCache<f64> = QueryCache::new();
+        cache.insert_path(1, 0, 1, 2.0);
+        cache.insert_connected(1, 1, 0, true);
+        assert_eq!(cache.path(1, 0, 1), Some(2.0));
+        assert_eq!(cache.path(1, 1, 0), None); // the paths are directed
+        assert_eq!(cache.connected(1, 0, 1), Some(true));
+        assert_eq!(cache.paths.len() + cache.connected.len(), 2);
+
+        // a new version of the forest drops the results:
+        assert_eq!(cache.connected(2, 0, 1), None);
+        assert_eq!(cache.path(1, 0, 1), None);
+        assert!(cache.paths.is_empty() && cache.connected.is_empty());
+    }
+
+    #[test]
+    pub fn bounded() {
+        let mut cache: QueryCache<f64> = QueryCache::new();
+        for v in 0..super::MAX_ENTRIES {
+            cache.insert_path(1, v, 0, 0.0);
+        }
+        assert_eq!(cache.paths.len(), super::MAX_ENTRIES);
+
+        // a full cache starts over:
+        cache.insert_path(1, 0, 1, 2.0);
+        assert_eq!(cache.paths.len(), 1);
+        assert_eq!(cache.path(1, 0, 1), Some(2.0));
+    }
+}
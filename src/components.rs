@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+// The number of trees of every size in the forest, maintained by `link`, `cut`,
+// `make_tree` and `remove_tree` (see `LinkCutTree::set_component_tracking`).
+pub struct ComponentSizes {
+    counts: BTreeMap<usize, usize>, // the number of trees of each size
+}
+
+impl ComponentSizes {
+    pub fn from_sizes(sizes: impl IntoIterator<Item = usize>) -> Self {
+        let mut components = Self {
+            counts: BTreeMap::new(),
+        };
+        for size in sizes {
+            components.insert(size);
+        }
+        components
+    }
+
+    pub fn insert(&mut self, size: usize) {
+        *self.counts.entry(size).or_insert(0) += 1;
+    }
+
+    pub fn remove(&mut self, size: usize) {
+        if let Some(count) = self.counts.get_mut(&size) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&size);
+            }
+        }
+    }
+
+    // Two trees of the given sizes were linked.
+    pub fn merge(&mut self, lhs: usize, rhs: usize) {
+        self.remove(lhs);
+        self.remove(rhs);
+        self.insert(lhs + rhs);
+    }
+
+    // A tree was cut into two trees of the given sizes.
+    pub fn split(&mut self, lhs: usize, rhs: usize) {
+        self.remove(lhs + rhs);
+        self.insert(lhs);
+        self.insert(rhs);
+    }
+
+    pub fn max(&self) -> usize {
+        self.counts.keys().next_back().copied().unwrap_or(0)
+    }
+
+    // Lists the sizes of the trees in increasing order, along with the number of trees of each size.
+    pub fn histogram(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.counts.iter().map(|(&size, &count)| (size, count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ComponentSizes;
+
+    #[test]
+    pub fn merge_and_split() {
+        let mut components = ComponentSizes::from_sizes([1, 1, 1, 2]);
+        assert_eq!(components.max(), 2);
+        components.merge(1, 2);
+        assert_eq!(
+            components.histogram().collect::<Vec<_>>(),
+            vec![(1, 2), (3, 1)]
+        );
+        components.split(2, 1);
+        assert_eq!(
+            components.histogram().collect::<Vec<_>>(),
+            vec![(1, 3), (2, 1)]
+        );
+        components.remove(2);
+        components.merge(1, 1);
+        components.remove(1);
+        components.remove(2);
+        assert_eq!(components.max(), 0);
+        assert_eq!(components.histogram().count(), 0);
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{atomic::Ordering, Arc},
+};
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+
+use crate::{index::IndexType, path::Path, LinkCutTree};
+
+/// A link-cut tree whose published versions can be read concurrently without locks
+/// (available with the `concurrent` feature).
+///
+/// The writer owns the link-cut tree and mutates it as usual (it dereferences to [`LinkCutTree`]),
+/// then calls [`ConcurrentLinkCutTree::publish`] to make the current version of the forest
+/// visible to the readers. Readers ([`ForestReader`]) answer `connected` and `path` queries
+/// on the most recently published version without blocking the writer or each other,
+/// and old versions are reclaimed with epoch-based reclamation once no reader uses them.
+///
+/// Publishing takes `O(n)` time, so it is meant to be called after batches of mutations.
+/// A published version is a plain rooted forest (not splay trees): it answers `connected` in `O(1)`,
+/// but `path` walks up from both nodes to their common ancestor in `O(depth)` time,
+/// which is up to `O(n)` per query on deep trees (e.g. long paths).
+///
+/// # Examples
+/// ```
+/// use lctree::{ConcurrentLinkCutTree, FindSum};
+///
+/// let mut lctree: ConcurrentLinkCutTree<FindSum> = ConcurrentLinkCutTree::new();
+/// let alice = lctree.make_tree(1.0);
+/// let bob = lctree.make_tree(2.0);
+/// let reader = lctree.reader();
+///
+/// lctree.link(alice, bob);
+/// assert!(!reader.connected(alice, bob)); // not published yet
+///
+/// lctree.publish();
+/// let handle = std::thread::spawn(move || reader.path(alice, bob).map(|path| path.sum));
+/// assert_eq!(handle.join().unwrap(), Some(3.0));
+/// ```
+pub struct ConcurrentLinkCutTree<P: Path, Ix: IndexType = usize> {
+    lctree: LinkCutTree<P, Ix>,
+    published: Arc<Published<P>>,
+}
+
+impl<P: Path + Send + Sync, Ix: IndexType> ConcurrentLinkCutTree<P, Ix> {
+    /// Creates a new empty link-cut tree (with an empty published version).
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_lctree(LinkCutTree::new())
+    }
+
+    /// Wraps an existing link-cut tree and publishes its current version.
+    #[must_use]
+    pub fn from_lctree(mut lctree: LinkCutTree<P, Ix>) -> Self {
+        let snapshot = Snapshot::new(&mut lctree, 0);
+        Self {
+            lctree,
+            published: Arc::new(Published {
+                current: Atomic::new(snapshot),
+            }),
+        }
+    }
+
+    /// Publishes the current version of the forest to the readers in `O(n)`
+    /// and returns its version number (which starts from 0 and increases by 1 on every call).
+    pub fn publish(&mut self) -> u64 {
+        let guard = epoch::pin();
+        let current = self.published.current.load(Ordering::Acquire, &guard);
+        // SAFETY: the current version is only replaced by the writer (i.e. here), so it is valid.
+        let version = unsafe { current.deref() }.version + 1;
+        let snapshot = Owned::new(Snapshot::new(&mut self.lctree, version));
+        let old = self
+            .published
+            .current
+            .swap(snapshot, Ordering::AcqRel, &guard);
+        // SAFETY: the old version is unreachable for new readers, and the readers that still
+        // hold it are pinned, so it is destroyed after they are done.
+        unsafe { guard.defer_destroy(old) };
+        version
+    }
+
+    /// Creates a reader of the published versions, which can be sent to other threads.
+    #[must_use]
+    pub fn reader(&self) -> ForestReader<P> {
+        ForestReader {
+            published: Arc::clone(&self.published),
+        }
+    }
+
+    /// Unwraps the link-cut tree (the readers keep reading the last published version).
+    #[must_use]
+    pub fn into_inner(self) -> LinkCutTree<P, Ix> {
+        self.lctree
+    }
+}
+
+impl<P: Path + Send + Sync, Ix: IndexType> Default for ConcurrentLinkCutTree<P, Ix> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Path, Ix: IndexType> Deref for ConcurrentLinkCutTree<P, Ix> {
+    type Target = LinkCutTree<P, Ix>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lctree
+    }
+}
+
+impl<P: Path, Ix: IndexType> DerefMut for ConcurrentLinkCutTree<P, Ix> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lctree
+    }
+}
+
+/// Answers queries on the most recently published version of a [`ConcurrentLinkCutTree`]
+/// without locks (available with the `concurrent` feature).
+///
+/// `connected` takes `O(1)` time and `path` takes `O(depth)` time (up to `O(n)` on deep trees,
+/// see [`ConcurrentLinkCutTree`]). Node ids that did not exist in the published version
+/// are not connected to any node (not even to themselves) and have no paths.
+#[derive(Clone)]
+pub struct ForestReader<P: Path> {
+    published: Arc<Published<P>>,
+}
+
+impl<P: Path + Send + Sync> ForestReader<P> {
+    fn read<T>(&self, query: impl FnOnce(&Snapshot<P>) -> T) -> T {
+        let guard = epoch::pin();
+        let current = self.published.current.load(Ordering::Acquire, &guard);
+        // SAFETY: the versions are never null, and the loaded version
+        // is not destroyed while the guard is pinned.
+        query(unsafe { current.deref() })
+    }
+
+    /// Returns the number of the published version that the next query will read.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.read(|snapshot| snapshot.version)
+    }
+
+    /// Checks if two nodes are connected in the published version.
+    #[must_use]
+    pub fn connected(&self, v: usize, w: usize) -> bool {
+        self.read(|snapshot| snapshot.connected(v, w))
+    }
+
+    /// Performs path aggregation on the path between two nodes in the published version
+    /// (or returns `None` if they are not connected).
+    #[must_use]
+    pub fn path(&self, v: usize, w: usize) -> Option<P> {
+        self.read(|snapshot| snapshot.path(v, w))
+    }
+}
+
+struct Published<P: Path> {
+    current: Atomic<Snapshot<P>>,
+}
+
+impl<P: Path> Drop for Published<P> {
+    fn drop(&mut self) {
+        // SAFETY: the last reference is being dropped, so no reader can access the version.
+        unsafe {
+            let current = self.current.load(Ordering::Relaxed, epoch::unprotected());
+            drop(current.into_owned());
+        }
+    }
+}
+
+// An immutable version of the forest, where every tree is rooted at its root at publication time.
+struct Snapshot<P: Path> {
+    version: u64,
+    parent: Vec<usize>, // usize::MAX for the roots and the missing nodes
+    root: Vec<usize>,
+    depth: Vec<usize>,
+    path: Vec<Option<P>>, // the aggregate of each node alone (None for the missing nodes)
+}
+
+impl<P: Path> Snapshot<P> {
+    fn new<Ix: IndexType>(lctree: &mut LinkCutTree<P, Ix>, version: u64) -> Self {
+        let weights = lctree.weights();
+        let len = weights.last().map_or(0, |&(idx, _)| idx + 1);
+        let mut path = vec![None; len];
+        for (idx, weight) in weights {
+            path[idx] = Some(P::default(weight, idx));
+        }
+        let mut parent = vec![usize::MAX; len];
+        let mut children = vec![Vec::new(); len];
+        for (child, parent_idx) in lctree.edges() {
+            parent[child] = parent_idx;
+            children[parent_idx].push(child);
+        }
+        let mut root = (0..len).collect::<Vec<_>>();
+        let mut depth = vec![0; len];
+        let mut stack = (0..len)
+            .filter(|&idx| path[idx].is_some() && parent[idx] == usize::MAX)
+            .collect::<Vec<_>>();
+        while let Some(node) = stack.pop() {
+            for &child in &children[node] {
+                root[child] = root[node];
+                depth[child] = depth[node] + 1;
+                stack.push(child);
+            }
+        }
+        Self {
+            version,
+            parent,
+            root,
+            depth,
+            path,
+        }
+    }
+
+    fn exists(&self, v: usize) -> bool {
+        v < self.path.len() && self.path[v].is_some()
+    }
+
+    fn connected(&self, v: usize, w: usize) -> bool {
+        self.exists(v) && self.exists(w) && self.root[v] == self.root[w]
+    }
+
+    fn path(&self, mut v: usize, mut w: usize) -> Option<P> {
+        if !self.exists(v) || !self.exists(w) || !self.connected(v, w) {
+            return None;
+        }
+        // climb from the deeper node until both nodes meet at their lowest common ancestor:
+        let mut result = self.path[v].clone()?;
+        if v != w {
+            result.aggregate(self.path[w].clone()?);
+        }
+        while v != w {
+            if self.depth[v] >= self.depth[w] {
+                v = self.parent[v];
+                if v != w {
+                    result.aggregate(self.path[v].clone()?);
+                }
+            } else {
+                w = self.parent[w];
+                if v != w {
+                    result.aggregate(self.path[w].clone()?);
+                }
+            }
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConcurrentLinkCutTree;
+    use crate::{FindMax, FindSum};
+
+    #[test]
+    pub fn published_versions() {
+        // We form a link-cut tree from the following rooted tree
+        // (the numbers in parentheses are the weights of the nodes):
+        //         a(0)
+        //        /    \
+        //     b(10)   e(7)
+        //     /   \     \
+        //   c(3)  d(11)  f(2)
+        let mut lctree: ConcurrentLinkCutTree<FindMax> = ConcurrentLinkCutTree::new();
+        let reader = lctree.reader();
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(10.);
+        let c = lctree.make_tree(3.);
+        let d = lctree.make_tree(11.);
+        let e = lctree.make_tree(7.);
+        let f = lctree.make_tree(2.);
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+        assert_eq!(reader.version(), 0);
+        assert!(!reader.connected(c, f)); // the nodes do not exist in version 0
+        assert!(!reader.connected(c, c));
+        assert!(reader.path(c, c).is_none());
+
+        assert_eq!(lctree.publish(), 1);
+        assert_eq!(reader.version(), 1);
+        assert!(reader.connected(c, f));
+        assert_eq!(reader.path(c, f).unwrap().idx, b);
+        assert_eq!(reader.path(c, d).unwrap().idx, d);
+        assert_eq!(reader.path(e, e).unwrap().idx, e);
+        assert_eq!(reader.path(f, a).unwrap().weight, 7.);
+
+        lctree.cut(b, a);
+        assert!(reader.connected(c, f)); // still reading version 1
+        lctree.publish();
+        assert!(!reader.connected(c, f));
+        assert!(reader.path(c, f).is_none());
+        assert_eq!(reader.path(c, d).unwrap().idx, d);
+
+        // the readers outlive the writer:
+        let lctree = lctree.into_inner();
+        assert_eq!(lctree.len(), 6);
+        assert!(reader.connected(a, f));
+    }
+
+    #[test]
+    pub fn concurrent_readers() {
+        let mut lctree: ConcurrentLinkCutTree<FindSum> = ConcurrentLinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0; 100]);
+        lctree.publish();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let reader = lctree.reader();
+                scope.spawn(move || {
+                    let mut last_version = 0;
+                    while last_version < 99 {
+                        let version = reader.version();
+                        assert!(version >= last_version);
+                        last_version = version;
+                        // every published version is a consistent path 0 - 1 - ... - version:
+                        if let Some(path) = reader.path(0, 99) {
+                            assert_eq!(path.sum, 100.0);
+                        }
+                        assert!(reader.connected(0, 1) || version < 2); // version 1 has no edges yet
+                    }
+                });
+            }
+            for pair in nodes.windows(2) {
+                lctree.link(pair[0], pair[1]);
+                lctree.publish();
+            }
+        });
+    }
+}
@@ -0,0 +1,218 @@
+use std::cmp::Ordering;
+
+use crate::{index::IndexType, node::NodeSlot, path::Path, storage::NodeStorage, LinkCutTree};
+
+/// The nodes (with their weights) and the edges of a forest at some point in time,
+/// taken with [`LinkCutTree::snapshot`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForestSnapshot {
+    weights: Vec<Option<f64>>, // indexed by node id, `None` for the removed nodes
+    edges: Vec<(usize, usize)>, // sorted, with the smaller id first
+}
+
+impl ForestSnapshot {
+    /// Returns the weight of node `v` (or `None` if there was no such node).
+    #[must_use]
+    pub fn weight(&self, v: usize) -> Option<f64> {
+        self.weights.get(v).copied().flatten()
+    }
+
+    /// Returns the edges of the forest, sorted and with the smaller id first.
+    #[must_use]
+    pub fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+
+    /// Returns the number of nodes in the forest.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.weights.iter().flatten().count()
+    }
+
+    /// Returns `true` if the forest contains no nodes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Lists the changes that turn this snapshot into `other`.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let saved = lctree.snapshot();
+    ///
+    /// lctree.link(alice, bob);
+    /// let diff = saved.diff(&lctree.snapshot());
+    /// assert_eq!(diff.added_edges, vec![(alice, bob)]);
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &ForestSnapshot) -> ForestDiff {
+        let mut diff = ForestDiff::default();
+        for v in 0..self.weights.len().max(other.weights.len()) {
+            match (self.weight(v), other.weight(v)) {
+                (None, Some(_)) => diff.added_nodes.push(v),
+                (Some(_), None) => diff.removed_nodes.push(v),
+                (Some(old), Some(new)) if old.total_cmp(&new) != Ordering::Equal => {
+                    diff.changed_weights.push((v, old, new));
+                }
+                _ => {}
+            }
+        }
+        // merge the sorted edge lists:
+        let (mut i, mut j) = (0, 0);
+        while i < self.edges.len() || j < other.edges.len() {
+            match (self.edges.get(i), other.edges.get(j)) {
+                (Some(old), Some(new)) if old == new => {
+                    i += 1;
+                    j += 1;
+                }
+                (Some(&old), new) if new.map_or(true, |&new| old < new) => {
+                    diff.removed_edges.push(old);
+                    i += 1;
+                }
+                (_, Some(&new)) => {
+                    diff.added_edges.push(new);
+                    j += 1;
+                }
+                (_, None) => unreachable!(),
+            }
+        }
+        diff
+    }
+}
+
+/// The changes between two forests (see [`LinkCutTree::diff`]).
+///
+/// The node ids are listed in increasing order, and the edges are sorted with the smaller id first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForestDiff {
+    /// The nodes that are only in the second forest.
+    pub added_nodes: Vec<usize>,
+    /// The nodes that are only in the first forest.
+    pub removed_nodes: Vec<usize>,
+    /// The nodes whose weights changed, along with the old and the new weight.
+    pub changed_weights: Vec<(usize, f64, f64)>,
+    /// The edges that are only in the second forest.
+    pub added_edges: Vec<(usize, usize)>,
+    /// The edges that are only in the first forest.
+    pub removed_edges: Vec<(usize, usize)>,
+}
+
+impl ForestDiff {
+    /// Returns `true` if the forests are the same.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_weights.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+}
+
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+    /// Takes a snapshot of the nodes, weights and edges of the forest in `O(n)`,
+    /// e.g. to [`diff`](ForestSnapshot::diff) it against the forest later on.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// lctree.link(bob, alice);
+    ///
+    /// let snapshot = lctree.snapshot();
+    /// assert_eq!(snapshot.len(), 2);
+    /// assert_eq!(snapshot.weight(bob), Some(1.0));
+    /// assert_eq!(snapshot.edges(), &[(alice, bob)]);
+    /// ```
+    #[must_use]
+    pub fn snapshot(&self) -> ForestSnapshot {
+        let effective_weights = self.effective_weights();
+        let mut weights = vec![None; effective_weights.len()];
+        for v in self.node_ids() {
+            weights[v] = Some(effective_weights[v]);
+        }
+        let mut edges: Vec<(usize, usize)> = self
+            .edges()
+            .map(|(v, w)| if v < w { (v, w) } else { (w, v) })
+            .collect();
+        edges.sort_unstable();
+        ForestSnapshot { weights, edges }
+    }
+
+    /// Lists the changes that turn this forest into `other` in `O(n)`
+    /// (the forests are compared by node ids).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let clay = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    ///
+    /// let mut replica = LinkCutTree::default();
+    /// replica.extend_forest(&[0.0, 1.0]);
+    ///
+    /// let diff = replica.diff(&lctree);
+    /// assert_eq!(diff.added_nodes, vec![clay]);
+    /// assert_eq!(diff.added_edges, vec![(alice, bob)]);
+    /// assert!(diff.removed_edges.is_empty());
+    /// ```
+    #[must_use]
+    pub fn diff<Q: Path, Jx: IndexType>(&self, other: &LinkCutTree<Q, Jx>) -> ForestDiff {
+        self.snapshot().diff(&other.snapshot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FindSum, LinkCutTree};
+
+    #[test]
+    pub fn diff() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0, 4.0]);
+        lctree.link(nodes[0], nodes[1]);
+        lctree.link(nodes[1], nodes[2]);
+        let saved = lctree.snapshot();
+        assert!(saved.diff(&lctree.snapshot()).is_empty());
+
+        lctree.cut(nodes[0], nodes[1]);
+        lctree.link(nodes[3], nodes[2]);
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(nodes[1], 10.0); // the cut rerooted the tree of nodes 1, 2 and 3 at 1
+        #[cfg(not(feature = "lazy-updates"))]
+        lctree.set_weights(&[(nodes[1], 12.0), (nodes[2], 13.0), (nodes[3], 14.0)]);
+        lctree.remove_tree(nodes[0]).unwrap();
+
+        let diff = saved.diff(&lctree.snapshot());
+        assert_eq!(diff.added_nodes, vec![]);
+        assert_eq!(diff.removed_nodes, vec![nodes[0]]);
+        assert_eq!(
+            diff.changed_weights,
+            vec![
+                (nodes[1], 2.0, 12.0),
+                (nodes[2], 3.0, 13.0),
+                (nodes[3], 4.0, 14.0)
+            ]
+        );
+        assert_eq!(diff.added_edges, vec![(nodes[2], nodes[3])]);
+        assert_eq!(diff.removed_edges, vec![(nodes[0], nodes[1])]);
+
+        // the snapshot reads the pending additions without pushing them down:
+        let before = lctree.snapshot();
+        lctree.map_weights(|_, weight| weight);
+        assert_eq!(lctree.snapshot(), before);
+    }
+}
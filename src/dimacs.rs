@@ -0,0 +1,216 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    error::ImportError, index::IndexType, node::NodeSlot, path::Path, storage::NodeStorage,
+    LinkCutTree,
+};
+
+/// # DIMACS edge lists
+/// The forest can be exchanged with classic graph tooling in the DIMACS-like edge format:
+/// - `c <text>`: a comment line,
+/// - `p <type> <n> <m>`: the problem line with the number of nodes and edges,
+/// - `n <id> <weight>`: the weight of a node (optional, the default weight is 0),
+/// - `e <u> <v> [<w>]`: an edge between two nodes.
+///
+/// The node ids are numbered from 1 in the file (and from 0 in the link-cut tree).
+/// Edge weights are ignored, as the link-cut tree only stores node weights.
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+    /// Builds a link-cut tree from a DIMACS edge list,
+    /// where node `i` of the file becomes node `i - 1` of the link-cut tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input cannot be read, is malformed,
+    /// or if its edges do not form a forest.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let input = "\
+    /// c a path of three nodes
+    /// p edge 3 2
+    /// n 1 1.5
+    /// n 3 2
+    /// e 1 2
+    /// e 2 3 10
+    /// ";
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::from_dimacs(input.as_bytes()).unwrap();
+    /// assert_eq!(lctree.len(), 3);
+    /// assert_eq!(lctree.path(0, 2).sum, 3.5);
+    /// ```
+    pub fn from_dimacs<R: BufRead>(reader: R) -> Result<Self, ImportError> {
+        let mut num_nodes = None;
+        let mut weights = Vec::new();
+        let mut edges = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let parse_error = |message: String| ImportError::Parse {
+                line: i + 1,
+                message,
+            };
+            let mut tokens = line.split_whitespace();
+            let mut next = || {
+                tokens
+                    .next()
+                    .ok_or_else(|| parse_error("missing field".into()))
+            };
+            match next() {
+                Err(_) | Ok("c") => {}
+                Ok("p") => {
+                    next()?; // the problem type
+                    let n = parse_number(next()?).map_err(parse_error)?;
+                    parse_number(next()?).map_err(parse_error)?;
+                    weights = vec![0.0; n];
+                    num_nodes = Some(n);
+                }
+                Ok(kind) => {
+                    let n = num_nodes.ok_or_else(|| parse_error("missing problem line".into()))?;
+                    let mut node = || {
+                        let id = parse_number(next()?).map_err(parse_error)?;
+                        if id == 0 || id > n {
+                            return Err(parse_error(format!("node {id} is out of range")));
+                        }
+                        Ok(id - 1)
+                    };
+                    match kind {
+                        "n" => {
+                            let v = node()?;
+                            let token = next()?;
+                            weights[v] = token
+                                .parse()
+                                .map_err(|_| parse_error(format!("invalid weight `{token}`")))?;
+                        }
+                        "e" => edges.push((node()?, node()?)),
+                        _ => return Err(parse_error(format!("unknown line type `{kind}`"))),
+                    }
+                }
+            }
+        }
+
+        let mut lctree = Self::new();
+        let _ = lctree.extend_forest(&weights);
+        for (v, w) in edges {
+            if !lctree.link(v, w).is_linked() {
+                return Err(ImportError::Cycle(v, w));
+            }
+        }
+        Ok(lctree)
+    }
+
+    /// Writes the forest as a DIMACS edge list, including the weights of the nodes.
+    /// The nodes are numbered from 1 in increasing order of their ids
+    /// (skipping the ids of removed nodes).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.5);
+    /// lctree.link(alice, bob);
+    ///
+    /// let mut output = Vec::new();
+    /// lctree.write_dimacs(&mut output).unwrap();
+    /// let output = String::from_utf8(output).unwrap();
+    /// assert_eq!(output, "p edge 2 1\nn 1 1\nn 2 2.5\ne 1 2\n");
+    /// ```
+    pub fn write_dimacs<W: Write>(&mut self, mut writer: W) -> io::Result<()> {
+        let weights = self.weights();
+        // the (1-based) position of each node id in the file:
+        let mut positions = vec![0; weights.last().map_or(0, |&(idx, _)| idx + 1)];
+        for (position, &(idx, _)) in weights.iter().enumerate() {
+            positions[idx] = position + 1;
+        }
+        let mut edges = self.edges().collect::<Vec<_>>();
+        edges.sort_unstable();
+
+        writeln!(writer, "p edge {} {}", weights.len(), edges.len())?;
+        for (idx, weight) in weights {
+            writeln!(writer, "n {} {weight}", positions[idx])?;
+        }
+        for (v, w) in edges {
+            writeln!(writer, "e {} {}", positions[v], positions[w])?;
+        }
+        writer.flush()
+    }
+}
+
+fn parse_number(token: &str) -> Result<usize, String> {
+    token
+        .parse()
+        .map_err(|_| format!("invalid number `{token}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FindMax, ImportError, LinkCutTree};
+
+    #[test]
+    pub fn dimacs_round_trip() {
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([3.0, 1.0, 4.0, 1.0, 5.0]);
+        lctree.link(nodes[0], nodes[1]);
+        lctree.link(nodes[2], nodes[1]);
+        lctree.link(nodes[4], nodes[3]);
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(nodes[4], 0.5); // applied to both nodes[3] and nodes[4]
+        #[cfg(not(feature = "lazy-updates"))]
+        lctree.set_weights(&[(nodes[3], 1.5), (nodes[4], 5.5)]);
+
+        let mut output = Vec::new();
+        lctree.write_dimacs(&mut output).unwrap();
+        let mut imported: LinkCutTree<FindMax> = LinkCutTree::from_dimacs(&output[..]).unwrap();
+
+        assert_eq!(imported.len(), 5);
+        let mut edges = imported
+            .edges()
+            .map(|(v, w)| (v.min(w), v.max(w)))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        assert_eq!(edges, vec![(0, 1), (1, 2), (3, 4)]);
+        assert_eq!(imported.path(0, 2).weight, 4.0);
+        assert_eq!(imported.path(3, 4).weight, 5.5);
+        assert_eq!(imported.path(3, 4).idx, 4);
+    }
+
+    #[test]
+    pub fn dimacs_removed_nodes() {
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0]);
+        lctree.remove_tree(nodes[0]).unwrap();
+        lctree.link(nodes[1], nodes[2]);
+
+        let mut output = Vec::new();
+        lctree.write_dimacs(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, "p edge 2 1\nn 1 2\nn 2 3\ne 1 2\n");
+    }
+
+    #[test]
+    pub fn dimacs_errors() {
+        let parse = |input: &str| LinkCutTree::<FindMax>::from_dimacs(input.as_bytes());
+        assert!(matches!(
+            parse("e 1 2"),
+            Err(ImportError::Parse { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("p edge 2 1\ne 1 3"),
+            Err(ImportError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse("p edge 2 1\nn 1 x"),
+            Err(ImportError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse("p edge 3 3\ne 1 2\ne 2 3\ne 3 1"),
+            Err(ImportError::Cycle(2, 0))
+        ));
+        assert!(parse("\np edge 1 0\n\n").is_ok());
+    }
+}
@@ -0,0 +1,213 @@
+use std::{fmt, io};
+
+#[cfg(feature = "lazy-updates")]
+use crate::registry::AggregateId;
+
+/// The error returned when a weight is rejected by the weight validation
+/// (see [`LinkCutTree::set_weight_validation`](crate::LinkCutTree::set_weight_validation)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightError {
+    /// The weight is NaN.
+    NaN,
+    /// The weight is positive or negative infinity.
+    Infinite,
+}
+
+impl WeightError {
+    // Checks that the weight is a finite number.
+    pub(crate) fn check(weight: f64) -> Result<(), WeightError> {
+        if weight.is_nan() {
+            Err(WeightError::NaN)
+        } else if weight.is_infinite() {
+            Err(WeightError::Infinite)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for WeightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightError::NaN => write!(f, "the weight is NaN"),
+            WeightError::Infinite => write!(f, "the weight is infinite"),
+        }
+    }
+}
+
+impl std::error::Error for WeightError {}
+
+/// The error returned by the checked operations (such as
+/// [`LinkCutTree::try_link`](crate::LinkCutTree::try_link)) when an id does not refer to
+/// a node of the forest: it is out of range or the node was removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidNode(pub usize);
+
+impl fmt::Display for InvalidNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "there is no node with id {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidNode {}
+
+/// The error returned by [`LinkCutTree::move_subtree`](crate::LinkCutTree::move_subtree).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// The new parent is in the subtree being moved (or is its root), so moving it would close a cycle.
+    IntoOwnSubtree,
+    /// There is no node with the given id.
+    InvalidNode(usize),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::IntoOwnSubtree => write!(f, "the new parent is in the subtree being moved"),
+            MoveError::InvalidNode(idx) => InvalidNode(*idx).fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+impl From<InvalidNode> for MoveError {
+    fn from(err: InvalidNode) -> Self {
+        MoveError::InvalidNode(err.0)
+    }
+}
+
+/// The error returned by the checked lazy updates (such as
+/// [`LinkCutTree::try_subtree_add`](crate::LinkCutTree::try_subtree_add)), in which case
+/// no weight is changed.
+#[cfg(feature = "lazy-updates")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateError {
+    /// A registered aggregate cannot be updated lazily (see
+    /// [`LinkCutTree::register_updatable_aggregate`](crate::LinkCutTree::register_updatable_aggregate)).
+    NotUpdatable(AggregateId),
+    /// The delta (or the weight) is rejected by the weight validation.
+    Weight(WeightError),
+    /// There is no node with the given id.
+    InvalidNode(usize),
+}
+
+#[cfg(feature = "lazy-updates")]
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::NotUpdatable(id) => {
+                write!(
+                    f,
+                    "the registered aggregate {} cannot be updated lazily",
+                    id.0
+                )
+            }
+            UpdateError::Weight(err) => err.fmt(f),
+            UpdateError::InvalidNode(idx) => InvalidNode(*idx).fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "lazy-updates")]
+impl std::error::Error for UpdateError {}
+
+#[cfg(feature = "lazy-updates")]
+impl From<WeightError> for UpdateError {
+    fn from(err: WeightError) -> Self {
+        UpdateError::Weight(err)
+    }
+}
+
+#[cfg(feature = "lazy-updates")]
+impl From<InvalidNode> for UpdateError {
+    fn from(err: InvalidNode) -> Self {
+        UpdateError::InvalidNode(err.0)
+    }
+}
+
+/// The error returned by [`LinkCutTree::link_rooted`](crate::LinkCutTree::link_rooted)
+/// when the child is not the root of its tree, along with the actual root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotRoot {
+    pub root: usize,
+}
+
+impl fmt::Display for NotRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the node is not the root of its tree (rooted at {})",
+            self.root
+        )
+    }
+}
+
+impl std::error::Error for NotRoot {}
+
+/// The error returned by [`LinkCutTree::remove_tree`](crate::LinkCutTree::remove_tree).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoveError {
+    /// The node is still linked to `degree` other nodes, so its tree has more than one node.
+    NotSingleton { degree: usize },
+    /// There is no node with the given id.
+    InvalidNode(usize),
+}
+
+impl fmt::Display for RemoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoveError::NotSingleton { degree } => write!(
+                f,
+                "the node is linked to {degree} other node(s), so its tree has more than one node"
+            ),
+            RemoveError::InvalidNode(idx) => InvalidNode(*idx).fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RemoveError {}
+
+impl From<InvalidNode> for RemoveError {
+    fn from(err: InvalidNode) -> Self {
+        RemoveError::InvalidNode(err.0)
+    }
+}
+
+/// The error returned when a forest cannot be imported
+/// (see [`LinkCutTree::from_dimacs`](crate::LinkCutTree::from_dimacs)
+/// and [`LinkCutTree::from_json`](crate::LinkCutTree::from_json)).
+#[derive(Debug)]
+pub enum ImportError {
+    /// Reading the input failed.
+    Io(io::Error),
+    /// A line of the input (numbered from 1) is malformed.
+    Parse { line: usize, message: String },
+    /// The edges do not form a forest (the edge between the two nodes closes a cycle).
+    Cycle(usize, usize),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "failed to read the input: {err}"),
+            ImportError::Parse { line, message } => write!(f, "line {line}: {message}"),
+            ImportError::Cycle(v, w) => write!(f, "the edge ({v}, {w}) closes a cycle"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImportError {
+    fn from(err: io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
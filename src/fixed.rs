@@ -0,0 +1,188 @@
+use std::ops::{Index, IndexMut};
+
+use crate::{
+    error::RemoveError,
+    index::IndexType,
+    node::{Node, NodeSlot},
+    outcome::{CutOutcome, LinkOutcome},
+    path::Path,
+    splay::Forest,
+    storage::NodeStorage,
+    LinkCutTree,
+};
+
+/// A link-cut tree that holds at most `N` nodes and performs no heap allocation after construction.
+///
+/// The nodes are stored inline in an array of `N` slots (see [`NodeStorage`]), and the list of
+/// the removed ids is allocated once by [`LinkCutTreeFixed::new`], which makes this forest
+/// suitable for real-time use where allocation is forbidden during operation.
+/// Instead of growing, [`LinkCutTreeFixed::make_tree`] returns `None` when the forest is full.
+/// The optional features of [`LinkCutTree`] that allocate (e.g. the query cache, recording
+/// or the adjacency lists) are not available.
+///
+/// # Examples
+/// ```
+/// use lctree::{FindMax, LinkCutTreeFixed};
+///
+/// let mut lctree: LinkCutTreeFixed<FindMax, 2> = LinkCutTreeFixed::new();
+/// let alice = lctree.make_tree(0.0).unwrap();
+/// let bob = lctree.make_tree(1.0).unwrap();
+/// assert_eq!(lctree.make_tree(2.0), None); // the forest is full
+///
+/// lctree.link(alice, bob);
+/// assert!(lctree.connected(alice, bob));
+/// assert_eq!(lctree.path(alice, bob).idx, bob);
+/// ```
+pub struct LinkCutTreeFixed<P: Path, const N: usize, Ix: IndexType = usize> {
+    lctree: LinkCutTree<P, Ix, ArrayStorage<P, Ix, N>>,
+}
+
+impl<P: Path, const N: usize, Ix: IndexType> LinkCutTreeFixed<P, N, Ix> {
+    /// Creates a new empty forest with space for `N` nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index type `Ix` cannot hold `N` nodes.
+    #[must_use]
+    pub fn new() -> Self {
+        assert!(
+            N < <Ix as IndexType>::max().index(),
+            "LinkCutTreeFixed: the index type cannot hold N nodes"
+        );
+        Self {
+            lctree: LinkCutTree::from_forest(Forest::with_capacity(N)),
+        }
+    }
+
+    /// Returns the maximum number of nodes the forest can hold (i.e. `N`).
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of nodes in the forest.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lctree.len()
+    }
+
+    /// Returns `true` if the forest contains no nodes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lctree.is_empty()
+    }
+
+    /// Creates a new tree with a single node with the given weight and returns its id,
+    /// or returns `None` if the forest already holds `N` nodes.
+    pub fn make_tree(&mut self, weight: f64) -> Option<usize> {
+        if self.lctree.len() >= N {
+            return None;
+        }
+        Some(self.lctree.make_tree(weight))
+    }
+
+    /// Delete a tree with a single node with the given id, freeing its slot.
+    /// See [`LinkCutTree::remove_tree`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RemoveError::NotSingleton`] if the node is still linked to other nodes.
+    pub fn remove_tree(&mut self, idx: usize) -> Result<(), RemoveError> {
+        self.lctree.remove_tree(idx)
+    }
+
+    /// Same as [`LinkCutTreeFixed::remove_tree`], but panics instead of returning an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree contains more than one node.
+    pub fn remove_tree_unchecked(&mut self, idx: usize) {
+        self.lctree.remove_tree_unchecked(idx);
+    }
+
+    /// Checks if two nodes are connected (i.e. in the same tree).
+    /// See [`LinkCutTree::connected`].
+    pub fn connected(&mut self, v: usize, w: usize) -> bool {
+        self.lctree.connected(v, w)
+    }
+
+    /// Merges two trees into a single tree. See [`LinkCutTree::link`].
+    pub fn link(&mut self, v: usize, w: usize) -> LinkOutcome {
+        self.lctree.link(v, w)
+    }
+
+    /// Checks if two nodes are connected by a link. See [`LinkCutTree::linked`].
+    pub fn linked(&mut self, v: usize, w: usize) -> bool {
+        self.lctree.linked(v, w)
+    }
+
+    /// Cuts the link between two nodes (if it exists). See [`LinkCutTree::cut`].
+    pub fn cut(&mut self, v: usize, w: usize) -> CutOutcome {
+        self.lctree.cut(v, w)
+    }
+
+    /// Performs path aggregation on a path between two nodes (if they are connected).
+    /// See [`LinkCutTree::path`].
+    pub fn path(&mut self, v: usize, w: usize) -> P {
+        self.lctree.path(v, w)
+    }
+
+    /// Finds the root of the tree that the query node is in. See [`LinkCutTree::findroot`].
+    pub fn findroot(&mut self, v: usize) -> usize {
+        self.lctree.findroot(v)
+    }
+}
+
+impl<P: Path, const N: usize, Ix: IndexType> Default for LinkCutTreeFixed<P, N, Ix> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Stores the nodes in an array of `N` slots, where the slots past the length hold placeholder nodes
+// (which are overwritten when the nodes are pushed).
+struct ArrayStorage<P: Path, Ix: IndexType, const N: usize> {
+    slots: [NodeSlot<P, Ix>; N],
+    len: usize,
+}
+
+impl<P: Path, Ix: IndexType, const N: usize> Default for ArrayStorage<P, Ix, N> {
+    fn default() -> Self {
+        Self {
+            slots: std::array::from_fn(|idx| NodeSlot(Node::new(idx, 0.0))),
+            len: 0,
+        }
+    }
+}
+
+impl<P: Path, Ix: IndexType, const N: usize> Index<usize> for ArrayStorage<P, Ix, N> {
+    type Output = NodeSlot<P, Ix>;
+
+    fn index(&self, idx: usize) -> &Self::Output {
+        &self.slots[idx]
+    }
+}
+
+impl<P: Path, Ix: IndexType, const N: usize> IndexMut<usize> for ArrayStorage<P, Ix, N> {
+    fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        &mut self.slots[idx]
+    }
+}
+
+impl<P: Path, Ix: IndexType, const N: usize> NodeStorage<NodeSlot<P, Ix>>
+    for ArrayStorage<P, Ix, N>
+{
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+
+    fn push(&mut self, node: NodeSlot<P, Ix>) {
+        assert!(self.len < N, "ArrayStorage: all {N} slots are taken");
+        self.slots[self.len] = node;
+        self.len += 1;
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{index::IndexType, node::NodeSlot, path::Path, storage::NodeStorage, LinkCutTree};
+
+/// Populates a link-cut tree with random trees, deterministically for a given seed.
+///
+/// Each method creates one node per weight (in the order of the weights), links them into
+/// the requested shape and returns the ids of the new nodes. The generator is self-contained
+/// (it does not depend on an external random number generator), so a seed always produces the
+/// same trees, which makes it handy for benchmarks and reproducible tests.
+///
+/// # Examples
+/// ```
+/// use lctree::{LinkCutTree, TreeGenerator};
+///
+/// let mut lctree = LinkCutTree::default();
+/// let mut generator = TreeGenerator::new(42);
+/// let nodes = generator.uniform_tree(&mut lctree, &[0.0; 100]);
+/// assert_eq!(lctree.edges().count(), 99);
+/// assert!(lctree.connected(nodes[0], nodes[99]));
+///
+/// // the same seed produces the same tree:
+/// let mut other = LinkCutTree::default();
+/// TreeGenerator::new(42).uniform_tree(&mut other, &[0.0; 100]);
+/// assert!(lctree.diff(&other).is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TreeGenerator {
+    state: u64,
+}
+
+impl TreeGenerator {
+    /// Creates a generator with the given seed.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // SplitMix64 (see https://prng.di.unimi.it/splitmix64.c):
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Returns a random number in `0..n` (n must be positive).
+    #[allow(clippy::cast_possible_truncation)]
+    fn below(&mut self, n: usize) -> usize {
+        // the widening multiplication maps the random bits to the range without a division:
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+
+    // Returns a random permutation of the given nodes (Fisher-Yates shuffle).
+    fn shuffled(&mut self, nodes: &[usize]) -> Vec<usize> {
+        let mut nodes = nodes.to_vec();
+        for i in (1..nodes.len()).rev() {
+            let j = self.below(i + 1);
+            nodes.swap(i, j);
+        }
+        nodes
+    }
+
+    /// Generates a uniformly random labeled tree, by decoding a random Prüfer sequence in `O(n)`.
+    pub fn uniform_tree<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>>(
+        &mut self,
+        lctree: &mut LinkCutTree<P, Ix, S>,
+        weights: &[f64],
+    ) -> Vec<usize> {
+        let nodes = lctree.extend_forest(weights);
+        let n = nodes.len();
+        if n < 2 {
+            return nodes;
+        }
+        let prufer: Vec<usize> = (0..n - 2).map(|_| self.below(n)).collect();
+        let mut degree = vec![1; n];
+        for &i in &prufer {
+            degree[i] += 1;
+        }
+        // repeatedly link the smallest leaf to the next node of the sequence:
+        let mut ptr = (0..n).find(|&i| degree[i] == 1).unwrap_or(0);
+        let mut leaf = ptr;
+        for &i in &prufer {
+            lctree.link(nodes[leaf], nodes[i]);
+            degree[i] -= 1;
+            if degree[i] == 1 && i < ptr {
+                leaf = i;
+            } else {
+                ptr += 1;
+                while degree[ptr] != 1 {
+                    ptr += 1;
+                }
+                leaf = ptr;
+            }
+        }
+        lctree.link(nodes[leaf], nodes[n - 1]);
+        nodes
+    }
+
+    /// Generates a random recursive tree: every node is attached to a random earlier node.
+    pub fn random_attachment_tree<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>>(
+        &mut self,
+        lctree: &mut LinkCutTree<P, Ix, S>,
+        weights: &[f64],
+    ) -> Vec<usize> {
+        self.random_forest(lctree, weights, 1)
+    }
+
+    /// Generates a random forest of `num_trees` trees (or fewer if there are fewer weights):
+    /// the first `num_trees` nodes are the roots, and every other node is attached to
+    /// a random earlier node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_trees` is zero while there are weights.
+    pub fn random_forest<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>>(
+        &mut self,
+        lctree: &mut LinkCutTree<P, Ix, S>,
+        weights: &[f64],
+        num_trees: usize,
+    ) -> Vec<usize> {
+        assert!(
+            num_trees > 0 || weights.is_empty(),
+            "random_forest: a non-empty forest needs at least one tree"
+        );
+        let nodes = lctree.extend_forest(weights);
+        for i in num_trees..nodes.len() {
+            let parent = self.below(i);
+            lctree.link(nodes[i], nodes[parent]);
+        }
+        nodes
+    }
+
+    /// Generates a path that visits the nodes in a random order.
+    pub fn path<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>>(
+        &mut self,
+        lctree: &mut LinkCutTree<P, Ix, S>,
+        weights: &[f64],
+    ) -> Vec<usize> {
+        let nodes = lctree.extend_forest(weights);
+        let order = self.shuffled(&nodes);
+        for pair in order.windows(2) {
+            lctree.link(pair[0], pair[1]);
+        }
+        nodes
+    }
+
+    /// Generates a star: a random node is linked to all of the other nodes.
+    pub fn star<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>>(
+        &mut self,
+        lctree: &mut LinkCutTree<P, Ix, S>,
+        weights: &[f64],
+    ) -> Vec<usize> {
+        let nodes = lctree.extend_forest(weights);
+        if nodes.is_empty() {
+            return nodes;
+        }
+        let center = nodes[self.below(nodes.len())];
+        for &v in nodes.iter().filter(|&&v| v != center) {
+            lctree.link(v, center);
+        }
+        nodes
+    }
+
+    /// Generates a caterpillar: a random path of `spine_len` nodes (the spine),
+    /// where every other node is a leaf attached to a random node of the spine.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spine_len` is zero or larger than the number of weights (for non-empty weights).
+    pub fn caterpillar<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>>(
+        &mut self,
+        lctree: &mut LinkCutTree<P, Ix, S>,
+        weights: &[f64],
+        spine_len: usize,
+    ) -> Vec<usize> {
+        assert!(
+            weights.is_empty() || (1..=weights.len()).contains(&spine_len),
+            "caterpillar: the spine must have between 1 and {} nodes",
+            weights.len()
+        );
+        let nodes = lctree.extend_forest(weights);
+        let order = self.shuffled(&nodes);
+        let (spine, legs) = order.split_at(spine_len.min(order.len()));
+        for pair in spine.windows(2) {
+            lctree.link(pair[0], pair[1]);
+        }
+        for &leg in legs {
+            let body = spine[self.below(spine.len())];
+            lctree.link(leg, body);
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TreeGenerator;
+    use crate::LinkCutTree;
+
+    // Counts the degree of every node (with the nodes created from 0 in an empty tree):
+    fn degrees_of(lctree: &LinkCutTree<crate::FindMax>) -> Vec<usize> {
+        let mut degrees = vec![0; lctree.len()];
+        for (v, w) in lctree.edges() {
+            degrees[v] += 1;
+            degrees[w] += 1;
+        }
+        degrees
+    }
+
+    #[test]
+    pub fn shapes() {
+        let weights = [0.0; 50];
+        for seed in 0..10 {
+            let mut generator = TreeGenerator::new(seed);
+
+            let mut lctree = LinkCutTree::default();
+            let nodes = generator.uniform_tree(&mut lctree, &weights);
+            assert_eq!(lctree.edges().count(), 49);
+            assert!(nodes.iter().all(|&v| lctree.connected(v, nodes[0])));
+
+            let mut lctree = LinkCutTree::default();
+            generator.path(&mut lctree, &weights);
+            let mut degrees = degrees_of(&lctree);
+            degrees.sort_unstable();
+            assert_eq!(degrees[..2], [1, 1]);
+            assert!(degrees[2..].iter().all(|&d| d == 2));
+
+            let mut lctree = LinkCutTree::default();
+            generator.star(&mut lctree, &weights);
+            let degrees = degrees_of(&lctree);
+            assert_eq!(degrees.iter().filter(|&&d| d == 49).count(), 1);
+
+            let mut lctree = LinkCutTree::default();
+            generator.caterpillar(&mut lctree, &weights, 10);
+            // removing the leaves leaves a path of 10 nodes:
+            let degrees = degrees_of(&lctree);
+            let spine = degrees.iter().filter(|&&d| d > 1).count();
+            assert!(spine <= 10);
+            assert_eq!(lctree.edges().count(), 49);
+
+            let mut lctree = LinkCutTree::default();
+            let nodes = generator.random_forest(&mut lctree, &weights, 5);
+            assert_eq!(lctree.edges().count(), 45);
+            let roots: Vec<usize> = nodes.iter().map(|&v| lctree.findroot(v)).collect();
+            let mut distinct = roots.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            assert_eq!(distinct.len(), 5);
+        }
+    }
+
+    #[test]
+    pub fn uniform_distribution() {
+        // there are 3^(3-2) = 3 labeled trees on 3 nodes (paths centered at each node):
+        let mut counts = [0; 3];
+        let mut generator = TreeGenerator::new(7);
+        for _ in 0..3000 {
+            let mut lctree = LinkCutTree::default();
+            generator.uniform_tree(&mut lctree, &[0.0; 3]);
+            let center = (0..3).position(|v| degrees_of(&lctree)[v] == 2).unwrap();
+            counts[center] += 1;
+        }
+        assert!(counts.iter().all(|&count| (900..1100).contains(&count)));
+    }
+}
@@ -1,32 +1,234 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    fmt::Debug,
+    hash::Hash,
+};
+
+/// The type used to store node ids inside the link-cut tree.
+///
+/// Smaller index types shrink the nodes of the forest, but limit the number of nodes it can hold
+/// (one value of each type is reserved, e.g. a forest indexed by `u16` holds at most 65535 nodes).
+/// Node ids are always exposed as `usize` in the public API.
+pub trait IndexType: Copy + Eq + Ord + Hash + Debug + Default + Send + Sync + 'static {
+    /// Converts a node id to the index type.
+    fn new(idx: usize) -> Self;
+    /// Converts the index type back to a node id.
+    fn index(self) -> usize;
+    /// The largest value of the index type.
+    fn max() -> Self;
+}
+
+macro_rules! impl_index_type {
+    ($($ty:ty),*) => {
+        $(
+            impl IndexType for $ty {
+                #[inline]
+                #[allow(clippy::cast_possible_truncation)]
+                fn new(idx: usize) -> Self {
+                    debug_assert!(
+                        <$ty>::try_from(idx).is_ok(),
+                        "the index {idx} does not fit in {}",
+                        stringify!($ty)
+                    );
+                    idx as $ty
+                }
+
+                #[inline]
+                fn index(self) -> usize {
+                    self as usize
+                }
+
+                #[inline]
+                fn max() -> Self {
+                    <$ty>::MAX
+                }
+            }
+        )*
+    };
+}
+
+impl_index_type!(u16, u32, usize);
+
+/// The order in which the ids of removed nodes are reused by `make_tree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdReusePolicy {
+    /// The most recently removed id is reused first (a stack).
+    #[default]
+    Lifo,
+    /// The least recently removed id is reused first (a queue).
+    Fifo,
+    /// The smallest removed id is reused first (a heap).
+    SmallestFirst,
+}
+
+// The ids of deleted nodes, waiting to be reused in the order of the policy:
+enum FreeIds {
+    Lifo(Vec<usize>),
+    Fifo(VecDeque<usize>),
+    SmallestFirst(BinaryHeap<Reverse<usize>>),
+}
+
+impl FreeIds {
+    fn new(policy: IdReusePolicy, capacity: usize) -> Self {
+        match policy {
+            IdReusePolicy::Lifo => FreeIds::Lifo(Vec::with_capacity(capacity)),
+            IdReusePolicy::Fifo => FreeIds::Fifo(VecDeque::with_capacity(capacity)),
+            IdReusePolicy::SmallestFirst => {
+                FreeIds::SmallestFirst(BinaryHeap::with_capacity(capacity))
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            FreeIds::Lifo(ids) => ids.len(),
+            FreeIds::Fifo(ids) => ids.len(),
+            FreeIds::SmallestFirst(ids) => ids.len(),
+        }
+    }
+
+    fn push(&mut self, id: usize) {
+        match self {
+            FreeIds::Lifo(ids) => ids.push(id),
+            FreeIds::Fifo(ids) => ids.push_back(id),
+            FreeIds::SmallestFirst(ids) => ids.push(Reverse(id)),
+        }
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        match self {
+            FreeIds::Lifo(ids) => ids.pop(),
+            FreeIds::Fifo(ids) => ids.pop_front(),
+            FreeIds::SmallestFirst(ids) => ids.pop().map(|Reverse(id)| id),
+        }
+    }
+
+    // Lists the ids in the order they would be reused.
+    fn to_vec(&self) -> Vec<usize> {
+        match self {
+            FreeIds::Lifo(ids) => ids.iter().rev().copied().collect(),
+            FreeIds::Fifo(ids) => ids.iter().copied().collect(),
+            FreeIds::SmallestFirst(ids) => {
+                let mut ids: Vec<usize> = ids.iter().map(|&Reverse(id)| id).collect();
+                ids.sort_unstable();
+                ids
+            }
+        }
+    }
+
+    // Removes all of the ids in the order they would be reused.
+    fn drain(&mut self) -> Vec<usize> {
+        std::iter::from_fn(|| self.pop()).collect()
+    }
+}
+
 pub struct Index {
     time_id: usize,
-    deleted_ids: Vec<usize>, // maybe use a set instead?
+    deleted_ids: FreeIds,
+    deleted: Vec<bool>, // deleted[id] is true if id is in deleted_ids (or retired)
+    policy: IdReusePolicy,
+    reuse: bool,    // if false, deleted ids are retired instead of being reused
+    retired: usize, // the number of deleted ids that are never reused
 }
 
 impl Index {
     pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
             time_id: 0,
-            deleted_ids: Vec::new(),
+            deleted_ids: FreeIds::new(IdReusePolicy::Lifo, capacity),
+            deleted: Vec::with_capacity(capacity),
+            policy: IdReusePolicy::Lifo,
+            reuse: true,
+            retired: 0,
+        }
+    }
+
+    // The number of ids that are currently allocated.
+    pub fn len(&self) -> usize {
+        self.time_id - self.deleted_ids.len() - self.retired
+    }
+
+    pub fn reuse(&self) -> bool {
+        self.reuse
+    }
+
+    // Enables (or disables) reusing the ids of deleted nodes.
+    // Disabling it retires the ids that are currently free, so they are never reused.
+    pub fn set_reuse(&mut self, enabled: bool) {
+        if self.reuse == enabled {
+            return;
         }
+        self.reuse = enabled;
+        if enabled {
+            for id in 0..self.time_id {
+                if self.deleted[id] {
+                    self.deleted_ids.push(id);
+                }
+            }
+            self.retired = 0;
+        } else {
+            self.retired += self.deleted_ids.len();
+            self.deleted_ids.drain();
+        }
+    }
+
+    pub fn policy(&self) -> IdReusePolicy {
+        self.policy
+    }
+
+    // Changes the order in which the deleted ids are reused
+    // (the ids that are currently free are moved over in their current order).
+    pub fn set_policy(&mut self, policy: IdReusePolicy) {
+        if self.policy == policy {
+            return;
+        }
+        let ids = self.deleted_ids.drain();
+        self.deleted_ids = FreeIds::new(policy, ids.len());
+        for id in ids {
+            self.deleted_ids.push(id);
+        }
+        self.policy = policy;
     }
 
     pub fn insert(&mut self) -> usize {
-        if !self.deleted_ids.is_empty() {
-            return self.deleted_ids.pop().unwrap();
+        if let Some(id) = self.deleted_ids.pop() {
+            self.deleted[id] = false;
+            return id;
         }
+        self.deleted.push(false);
         self.time_id += 1;
         self.time_id - 1
     }
 
     pub fn delete(&mut self, id: usize) {
         assert!(id < self.time_id, "Invalid deletion");
-        self.deleted_ids.push(id);
+        if self.reuse {
+            self.deleted_ids.push(id);
+        } else {
+            self.retired += 1;
+        }
+        self.deleted[id] = true;
+    }
+
+    // The deleted ids in the order they will be reused (empty if the reuse is disabled).
+    pub fn free_ids(&self) -> Vec<usize> {
+        self.deleted_ids.to_vec()
+    }
+
+    // Checks if the given id is currently allocated (i.e. inserted and not deleted).
+    pub fn contains(&self, id: usize) -> bool {
+        id < self.time_id && !self.deleted[id]
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::IdReusePolicy;
 
     #[test]
     pub fn test_indexing() {
@@ -40,13 +242,69 @@ mod tests {
         // delete 1
         index.delete(1);
         assert_eq!(index.time_id, 3);
-        assert_eq!(index.deleted_ids, vec![1]);
+        assert_eq!(index.deleted_ids.len(), 1);
+        assert!(index.deleted[1]);
 
         // next insertion should be 1
+        assert_eq!(index.free_ids(), vec![1]);
         assert_eq!(index.insert(), 1);
         assert_eq!(index.time_id, 3);
     }
 
+    #[test]
+    pub fn test_no_reuse() {
+        let mut index = super::Index::new();
+        assert_eq!(index.insert(), 0);
+        assert_eq!(index.insert(), 1);
+        index.delete(0);
+        index.set_reuse(false);
+        index.delete(1);
+        assert_eq!(index.len(), 0);
+        assert_eq!(index.insert(), 2);
+        assert_eq!(index.len(), 1);
+
+        // the retired ids are reused again once the reuse is enabled:
+        index.set_reuse(true);
+        assert_eq!(index.insert(), 1);
+        assert_eq!(index.insert(), 0);
+        assert_eq!(index.insert(), 3);
+    }
+
+    #[test]
+    pub fn test_reuse_policies() {
+        let mut index = super::Index::new();
+        for id in 0..5 {
+            assert_eq!(index.insert(), id);
+        }
+        for id in [3, 1, 4] {
+            index.delete(id);
+        }
+        index.set_policy(IdReusePolicy::SmallestFirst);
+        assert_eq!(index.insert(), 1);
+        index.set_policy(IdReusePolicy::Fifo);
+        index.delete(0);
+        assert_eq!(index.insert(), 3);
+        assert_eq!(index.insert(), 4);
+        assert_eq!(index.insert(), 0);
+        assert_eq!(index.insert(), 5);
+    }
+
+    #[test]
+    pub fn test_contains() {
+        let mut index = super::Index::new();
+        assert!(!index.contains(0));
+        assert_eq!(index.insert(), 0);
+        assert_eq!(index.insert(), 1);
+        assert!(index.contains(0));
+        assert!(index.contains(1));
+        assert!(!index.contains(2));
+
+        index.delete(0);
+        assert!(!index.contains(0));
+        assert_eq!(index.insert(), 0);
+        assert!(index.contains(0));
+    }
+
     #[test]
     #[should_panic]
     pub fn test_invalid_deletion() {
@@ -0,0 +1,370 @@
+use std::fmt::Write;
+
+use crate::{
+    error::ImportError, index::IndexType, node::NodeSlot, path::Path, storage::NodeStorage,
+    LinkCutTree,
+};
+
+/// # JSON
+/// The forest can be exchanged as a JSON document of the following form:
+/// ```json
+/// {
+///   "nodes": [{"id": 0, "weight": 1.5}, {"id": 1, "weight": 2}],
+///   "edges": [[0, 1]]
+/// }
+/// ```
+/// The node ids are preserved by the conversion (the ids missing from `nodes` are free
+/// to be reused by `make_tree`). JSON cannot represent NaN or infinite numbers,
+/// so such weights are written as `null`, which is read back as NaN.
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+    /// Serializes the forest to a JSON document (see above for the schema).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.5);
+    /// lctree.link(alice, bob);
+    /// assert_eq!(
+    ///     lctree.to_json(),
+    ///     r#"{"nodes":[{"id":0,"weight":1},{"id":1,"weight":2.5}],"edges":[[0,1]]}"#
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_json(&mut self) -> String {
+        let mut edges = self
+            .edges()
+            .map(|(v, w)| (v.min(w), v.max(w)))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+
+        let mut json = String::from(r#"{"nodes":["#);
+        for (i, (idx, weight)) in self.weights().into_iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            if weight.is_finite() {
+                let _ = write!(json, r#"{{"id":{idx},"weight":{weight}}}"#);
+            } else {
+                let _ = write!(json, r#"{{"id":{idx},"weight":null}}"#);
+            }
+        }
+        json.push_str(r#"],"edges":["#);
+        for (i, (v, w)) in edges.into_iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(json, "[{v},{w}]");
+        }
+        json.push_str("]}");
+        json
+    }
+
+    /// Builds a link-cut tree from a JSON document (see above for the schema).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document is malformed, does not follow the schema,
+    /// or if its edges do not form a forest.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let json = r#"{
+    ///     "nodes": [{"id": 0, "weight": 1}, {"id": 1, "weight": 2}, {"id": 2, "weight": 3}],
+    ///     "edges": [[0, 1], [1, 2]]
+    /// }"#;
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::from_json(json).unwrap();
+    /// assert_eq!(lctree.path(0, 2).sum, 6.0);
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, ImportError> {
+        let mut parser = Parser { json, pos: 0 };
+        let document = parser.parse_document()?;
+        let error = |message: &str| ImportError::Parse {
+            line: 1,
+            message: message.to_string(),
+        };
+
+        let mut nodes = Vec::new();
+        for node in document
+            .field("nodes")
+            .and_then(Value::as_array)
+            .ok_or_else(|| error("missing `nodes` array"))?
+        {
+            let id = node
+                .field("id")
+                .and_then(Value::as_id)
+                .ok_or_else(|| error("invalid node id"))?;
+            let weight = match node.field("weight") {
+                Some(Value::Number(weight)) => *weight,
+                Some(Value::Null) => f64::NAN,
+                _ => return Err(error("invalid node weight")),
+            };
+            nodes.push((id, weight));
+        }
+        let mut edges = Vec::new();
+        for edge in document
+            .field("edges")
+            .and_then(Value::as_array)
+            .ok_or_else(|| error("missing `edges` array"))?
+        {
+            match edge.as_array() {
+                Some([v, w]) => edges.push((
+                    v.as_id().ok_or_else(|| error("invalid edge"))?,
+                    w.as_id().ok_or_else(|| error("invalid edge"))?,
+                )),
+                _ => return Err(error("invalid edge")),
+            }
+        }
+
+        // create the nodes with their original ids:
+        let len = nodes.iter().map(|&(id, _)| id + 1).max().unwrap_or(0);
+        let mut weights = vec![None; len];
+        for (id, weight) in nodes {
+            if weights[id].replace(weight).is_some() {
+                return Err(error(&format!("duplicate node id {id}")));
+            }
+        }
+        let mut lctree = Self::new();
+        for weight in &weights {
+            lctree.make_tree(weight.unwrap_or_default());
+        }
+        for (id, weight) in weights.iter().enumerate().rev() {
+            if weight.is_none() {
+                lctree.remove_tree_unchecked(id); // not linked yet
+            }
+        }
+        for (v, w) in edges {
+            if v >= len || w >= len || weights[v].is_none() || weights[w].is_none() {
+                return Err(error(&format!("unknown node in the edge ({v}, {w})")));
+            }
+            if !lctree.link(v, w).is_linked() {
+                return Err(ImportError::Cycle(v, w));
+            }
+        }
+        Ok(lctree)
+    }
+}
+
+// A minimal JSON value (strings are only needed as object keys).
+enum Value {
+    Null,
+    Bool,
+    Number(f64),
+    String,
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn as_id(&self) -> Option<usize> {
+        match self {
+            Value::Number(id) if id.fract() == 0.0 && *id >= 0.0 && *id < 1e15 => {
+                Some(*id as usize)
+            }
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    json: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> ImportError {
+        ImportError::Parse {
+            line: self.json[..self.pos].matches('\n').count() + 1,
+            message: message.to_string(),
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Value, ImportError> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.pos < self.json.len() {
+            return Err(self.error("unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.json[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.json.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ImportError> {
+        self.skip_whitespace();
+        if self.json[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{token}`")))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, ImportError> {
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => self.parse_string().map(|_| Value::String),
+            Some(b'n') => self.expect("null").map(|()| Value::Null),
+            Some(b't') => self.expect("true").map(|()| Value::Bool),
+            Some(b'f') => self.expect("false").map(|()| Value::Bool),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value, ImportError> {
+        self.expect("{")?;
+        let mut fields = Vec::new();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(":")?;
+            fields.push((key, self.parse_value()?));
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(fields));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value, ImportError> {
+        self.expect("[")?;
+        let mut values = Vec::new();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    // Parses a string without escape sequences (which are not needed by the schema).
+    fn parse_string(&mut self) -> Result<String, ImportError> {
+        self.expect("\"")?;
+        let rest = &self.json[self.pos..];
+        match rest.find(['"', '\\']) {
+            Some(end) if rest.as_bytes()[end] == b'"' => {
+                self.pos += end + 1;
+                Ok(rest[..end].to_string())
+            }
+            _ => Err(self.error("unsupported or unterminated string")),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value, ImportError> {
+        let rest = &self.json[self.pos..];
+        let end = rest
+            .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+            .unwrap_or(rest.len());
+        let number = rest[..end]
+            .parse()
+            .map_err(|_| self.error("invalid number"))?;
+        self.pos += end;
+        Ok(Value::Number(number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FindSum, ImportError, LinkCutTree};
+
+    #[test]
+    pub fn json_round_trip() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([0.1, -2.0, 3.0, 1e-9, f64::NAN]);
+        lctree.link(nodes[0], nodes[1]);
+        lctree.link(nodes[2], nodes[1]);
+        lctree.remove_tree(nodes[3]).unwrap();
+
+        let json = lctree.to_json();
+        let mut imported: LinkCutTree<FindSum> = LinkCutTree::from_json(&json).unwrap();
+        assert_eq!(imported.node_ids().collect::<Vec<_>>(), vec![0, 1, 2, 4]);
+        assert_eq!(imported.path(nodes[0], nodes[2]).sum, 1.1);
+        assert!(imported.path(nodes[4], nodes[4]).sum.is_nan());
+        assert_eq!(imported.to_json(), json);
+        // the removed id is reused:
+        assert_eq!(imported.make_tree(0.0), nodes[3]);
+    }
+
+    #[test]
+    pub fn json_errors() {
+        let parse = |json: &str| LinkCutTree::<FindSum>::from_json(json);
+        assert!(parse(r#"{"nodes": [], "edges": []}"#).is_ok());
+        assert!(parse(r#"{"nodes": [{"id": 0, "weight": 1, "label": "a"}], "edges": []}"#).is_ok());
+        let errors = [
+            "",
+            "{",
+            r#"{"nodes": []}"#,
+            r#"{"nodes": [{"id": -1, "weight": 1}], "edges": []}"#,
+            r#"{"nodes": [{"id": 0, "weight": "1"}], "edges": []}"#,
+            r#"{"nodes": [{"id": 0, "weight": 1}, {"id": 0, "weight": 2}], "edges": []}"#,
+            r#"{"nodes": [{"id": 0, "weight": 1}], "edges": [[0, 1]]}"#,
+            r#"{"nodes": [{"id": 0, "weight": 1}], "edges": [[0]]}"#,
+            r#"{"nodes": [], "edges": []} []"#,
+        ];
+        for json in errors {
+            assert!(
+                matches!(parse(json), Err(ImportError::Parse { .. })),
+                "{json}"
+            );
+        }
+        match parse("{\n\"nodes\": [\n{\"id\": 0 \"weight\": 1}]}") {
+            Err(err) => assert_eq!(err.to_string(), "line 3: expected `,` or `}`"),
+            Ok(_) => panic!("the document is malformed"),
+        }
+        assert!(matches!(
+            parse(
+                r#"{"nodes": [{"id": 0, "weight": 1}, {"id": 1, "weight": 1}], "edges": [[0, 1], [1, 0]]}"#
+            ),
+            Err(ImportError::Cycle(1, 0))
+        ));
+    }
+}
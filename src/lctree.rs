@@ -1,10 +1,48 @@
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    fmt::Debug,
+    io::{self, BufRead, Write},
+};
+
+#[cfg(feature = "depth-queries")]
+use std::{
+    cmp::Ordering,
+    ops::{Bound, RangeBounds},
+};
+
+#[cfg(feature = "component-sizes")]
+use crate::components::ComponentSizes;
 use crate::{
+    adjacency::{Adjacency, TourEvent},
+    cache::QueryCache,
+    error::{InvalidNode, MoveError, NotRoot, RemoveError, WeightError},
+    index::{IdReusePolicy, IndexType},
+    matrix::BitMatrix,
+    node::{NodeSlot, NodeView},
+    outcome::{CutOutcome, LinkOutcome},
     path::{FindMax, Path},
+    registry::{AggregateId, RuntimeAggregate},
+    script::{self, Operation, Recorder, ScriptError},
     splay::Forest,
+    stats::{CostCounters, SplayStats, SplayTree},
+    storage::NodeStorage,
+};
+#[cfg(feature = "lazy-updates")]
+use crate::{
+    error::UpdateError,
+    path::{PathAdd, PathAssign},
 };
 
-pub struct LinkCutTree<P: Path> {
-    forest: Forest<P>,
+pub struct LinkCutTree<P: Path, Ix: IndexType = usize, S = Vec<NodeSlot<P, Ix>>> {
+    forest: Forest<P, Ix, S>,
+    validate_weights: bool,
+    adjacency: Option<Adjacency>, // only tracked if enabled
+    #[cfg(feature = "component-sizes")]
+    components: Option<ComponentSizes>, // only tracked if enabled
+    cache: Option<QueryCache<P>>, // only if the queries are memoized
+    bulk_edit: Option<bool>,      // the aggregate mode to restore at the end of a bulk edit
+    recorder: Option<Recorder>,   // only if the operations are recorded
 }
 
 /// # Link-cut-tree.
@@ -64,18 +102,90 @@ pub struct LinkCutTree<P: Path> {
 /// // We check connectivity again:
 /// assert!(!lctree.connected(c, f)); // not connected anymore
 /// ```
-impl<P: Path> LinkCutTree<P> {
+///
+/// # Index types
+/// Internally, node ids are stored using the index type `Ix` (`usize` by default).
+/// Memory-constrained users can shrink the nodes by choosing a smaller index type
+/// (see [`IndexType`](crate::IndexType)), while node ids are still exposed as `usize`:
+/// ```
+/// use lctree::{FindMax, LinkCutTree};
+///
+/// let mut lctree: LinkCutTree<FindMax, u16> = LinkCutTree::new();
+/// let alice = lctree.make_tree(0.0);
+/// let bob = lctree.make_tree(1.0);
+/// lctree.link(alice, bob);
+/// assert!(lctree.connected(alice, bob));
+/// ```
+///
+/// # Node storage
+/// The nodes are kept in a `Vec` by default, or in any other storage `S` implementing
+/// [`NodeStorage`](crate::NodeStorage), e.g. a slab, a fixed arena or a paged store.
+///
+/// # Thread safety
+/// `LinkCutTree<P, Ix>` is `Send` and `Sync` whenever the path aggregate `P` is
+/// (which the built-in aggregates are), so a forest can be moved between threads or shared behind a lock.
+/// This is why the writers given to `record_script` and the functions given to `register_aggregate`
+/// have to be `Send + Sync` as well. Note that the queries take `&mut self`, as they restructure
+/// the splay trees: for lock-free concurrent reads, use the `ConcurrentLinkCutTree`
+/// (requires the `concurrent` feature).
+/// ```
+/// use lctree::LinkCutTree;
+///
+/// let mut lctree = LinkCutTree::default();
+/// let alice = lctree.make_tree(0.0);
+/// let bob = lctree.make_tree(1.0);
+/// let handle = std::thread::spawn(move || {
+///     lctree.link(alice, bob);
+///     lctree
+/// });
+/// let mut lctree = handle.join().unwrap();
+/// assert!(lctree.connected(alice, bob));
+/// ```
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
     /// Creates a new empty link-cut tree.
+    /// To configure the link-cut tree, use [`LinkCutTreeBuilder`](crate::LinkCutTreeBuilder).
     #[must_use]
     pub fn new() -> Self {
         Self {
             forest: Forest::new(),
+            validate_weights: false,
+            adjacency: None,
+            #[cfg(feature = "component-sizes")]
+            components: None,
+            cache: None,
+            bulk_edit: None,
+            recorder: None,
+        }
+    }
+
+    pub(crate) fn from_forest(forest: Forest<P, Ix, S>) -> Self {
+        Self {
+            forest,
+            validate_weights: false,
+            adjacency: None,
+            #[cfg(feature = "component-sizes")]
+            components: None,
+            cache: None,
+            bulk_edit: None,
+            recorder: None,
         }
     }
 
+    /// Returns the number of nodes the link-cut tree can hold without reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.forest.capacity()
+    }
+
     /// Creates a new tree with a single node with the given weight and returns its id.
     /// If possible, reuses the space of a deleted node and returns its id.
     ///
+    /// # Panics
+    ///
+    /// Panics if the index type `Ix` cannot hold any more nodes,
+    /// or if the weight validation is enabled and the weight is NaN or infinite
+    /// (see [`LinkCutTree::try_make_tree`]).
+    ///
     /// # Examples
     /// ```
     /// use lctree::LinkCutTree;
@@ -87,84 +197,184 @@ impl<P: Path> LinkCutTree<P> {
     /// assert_eq!([alice, bob, clay], [0, 1, 2]);
     ///
     /// // Remove bob's tree from the forest
-    /// lctree.remove_tree(bob);
+    /// lctree.remove_tree(bob).unwrap();
     ///
     /// // Reuse the space of bob's tree (which was removed) to create a new tree:
     /// let david = lctree.make_tree(4.0);
     /// assert_eq!(david, bob);
     /// ```
     pub fn make_tree(&mut self, weight: f64) -> usize {
-        self.forest.create_node(weight)
+        if self.validate_weights {
+            if let Err(err) = WeightError::check(weight) {
+                panic!("make_tree: {err}");
+            }
+        }
+        #[cfg(feature = "component-sizes")]
+        if let Some(components) = &mut self.components {
+            components.insert(1);
+        }
+        let idx = self.forest.create_node(weight);
+        self.record(Operation::MakeTree(weight));
+        idx
     }
 
-    /// Extends the forest with n new single-noded trees for the given weights.
+    /// Creates a new tree with a single node with the given weight and returns its id,
+    /// or returns an error if the weight validation is enabled and the weight is NaN or infinite.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`WeightError`] if the weight is rejected by the weight validation.
     ///
     /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, WeightError};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// lctree.set_weight_validation(true);
+    /// assert_eq!(lctree.try_make_tree(1.0), Ok(0));
+    /// assert_eq!(lctree.try_make_tree(f64::NAN), Err(WeightError::NaN));
+    /// assert_eq!(lctree.try_make_tree(f64::INFINITY), Err(WeightError::Infinite));
+    /// ```
+    pub fn try_make_tree(&mut self, weight: f64) -> Result<usize, WeightError> {
+        if self.validate_weights {
+            WeightError::check(weight)?;
+        }
+        #[cfg(feature = "component-sizes")]
+        if let Some(components) = &mut self.components {
+            components.insert(1);
+        }
+        let idx = self.forest.create_node(weight);
+        self.record(Operation::MakeTree(weight));
+        Ok(idx)
+    }
+
+    /// Creates a new tree with a single node with the default weight of the path aggregate
+    /// (see [`Path::DEFAULT_WEIGHT`]) and returns its id.
+    /// This is useful when only the connectivity of the nodes matters.
     ///
+    /// # Panics
+    ///
+    /// Panics if the index type `Ix` cannot hold any more nodes.
+    ///
+    /// # Examples
     /// ```
     /// use lctree::LinkCutTree;
     ///
-    /// let weights = vec![1.0, 2.0, 3.0];
     /// let mut lctree = LinkCutTree::default();
-    /// let trees_ids = lctree.extend_forest(&weights);
-    /// assert_eq!(trees_ids, vec![0, 1, 2]);
+    /// let alice = lctree.make_tree_default();
+    /// let bob = lctree.make_tree_default();
+    /// lctree.link(alice, bob);
+    /// assert!(lctree.connected(alice, bob));
     /// ```
+    pub fn make_tree_default(&mut self) -> usize {
+        self.make_tree(P::DEFAULT_WEIGHT)
+    }
+
+    /// Enables (or disables) the weight validation, which rejects NaN and infinite weights.
+    ///
+    /// The built-in aggregates order weights totally (see [`f64::total_cmp`]), so they never
+    /// misbehave on NaN weights, but a NaN weight usually indicates a bug in the caller.
+    /// When the validation is enabled, `make_tree` and `map_weights` panic on such weights,
+    /// while `try_make_tree` returns an error.
+    pub fn set_weight_validation(&mut self, enabled: bool) {
+        self.validate_weights = enabled;
+    }
+
+    /// Returns `true` if the weight validation is enabled.
     #[must_use]
-    pub fn extend_forest(&mut self, weights: &[f64]) -> Vec<usize> {
-        weights
-            .iter()
-            .map(|&weight| self.make_tree(weight))
-            .collect()
+    pub fn weight_validation(&self) -> bool {
+        self.validate_weights
     }
 
-    /// Delete a tree with a single node with the given id.
+    /// Enables (or disables) reusing the ids of removed nodes, which is enabled by default.
     ///
-    /// # Panics
+    /// When the reuse is disabled, `make_tree` always returns a fresh id that is larger than
+    /// all of the ids returned before, which is useful when the ids are kept in long-lived
+    /// external records. Disabling the reuse also retires the ids that are currently free.
     ///
-    /// Panics if the tree contains more than one node.
-    pub fn remove_tree(&mut self, idx: usize) {
-        self.forest.delete_node(idx);
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// lctree.set_id_reuse(false);
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// lctree.remove_tree(bob).unwrap();
+    ///
+    /// let clay = lctree.make_tree(2.0);
+    /// assert!(clay > bob); // bob's id is not reused
+    /// ```
+    pub fn set_id_reuse(&mut self, enabled: bool) {
+        self.forest.set_id_reuse(enabled);
+        self.record(Operation::IdReuse(enabled));
     }
 
-    /// Constructs a path from a node to the root of the tree.
-    fn access(&mut self, v: usize) {
-        self.forest.splay(v);
-        self.forest.remove_preferred_child(v);
-
-        while let Some(path_idx) = self.forest.path_parent_of(v) {
-            self.forest.splay(path_idx);
-            self.forest.remove_preferred_child(path_idx);
+    /// Returns `true` if the ids of removed nodes are reused.
+    #[must_use]
+    pub fn id_reuse(&self) -> bool {
+        self.forest.id_reuse()
+    }
 
-            self.forest.set_right(path_idx, v);
-            self.forest.splay(v); // just a rotation
-        }
+    /// Changes the order in which the ids of removed nodes are reused (see [`IdReusePolicy`]),
+    /// so that the ids assigned by `make_tree` are predictable across runs.
+    /// The default policy reuses the most recently removed id first.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{IdReusePolicy, LinkCutTree};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// lctree.set_id_reuse_policy(IdReusePolicy::SmallestFirst);
+    /// let nodes = lctree.extend_forest(&[0.0; 4]);
+    /// lctree.remove_tree(nodes[2]).unwrap();
+    /// lctree.remove_tree(nodes[1]).unwrap();
+    /// lctree.remove_tree(nodes[3]).unwrap();
+    ///
+    /// assert_eq!(lctree.make_tree(0.0), nodes[1]);
+    /// assert_eq!(lctree.make_tree(0.0), nodes[2]);
+    /// ```
+    pub fn set_id_reuse_policy(&mut self, policy: IdReusePolicy) {
+        self.forest.set_id_reuse_policy(policy);
+        self.record(Operation::IdReusePolicy(policy));
     }
 
-    /// Makes v the root of its represented tree by flipping the path from v to the root.
-    fn reroot(&mut self, v: usize) {
-        self.access(v);
-        self.forest.flip(v);
+    /// Returns the order in which the ids of removed nodes are reused.
+    #[must_use]
+    pub fn id_reuse_policy(&self) -> IdReusePolicy {
+        self.forest.id_reuse_policy()
     }
 
-    /// Checks if two nodes are connected (i.e. in the same tree).
+    /// Extends the forest with n new single-noded trees for the given weights
+    /// (a slice, a vector or any iterator of weights) and returns their ids.
     ///
     /// # Examples
+    ///
     /// ```
     /// use lctree::LinkCutTree;
     ///
+    /// let weights = vec![1.0, 2.0, 3.0];
     /// let mut lctree = LinkCutTree::default();
-    /// let alice = lctree.make_tree(0.0);
-    /// let bob = lctree.make_tree(1.0);
-    /// assert!(!lctree.connected(alice, bob)); // not connected yet
+    /// let trees_ids = lctree.extend_forest(&weights);
+    /// assert_eq!(trees_ids, vec![0, 1, 2]);
     ///
-    /// lctree.link(alice, bob);
-    /// assert!(lctree.connected(alice, bob)); // now connected
+    /// let trees_ids = lctree.extend_forest((0..2).map(f64::from));
+    /// assert_eq!(trees_ids, vec![3, 4]);
     /// ```
-    pub fn connected(&mut self, v: usize, w: usize) -> bool {
-        v == w || self.findroot(v) == self.findroot(w)
+    #[must_use]
+    pub fn extend_forest<I>(&mut self, weights: I) -> Vec<usize>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<f64>,
+    {
+        weights
+            .into_iter()
+            .map(|weight| self.make_tree(*weight.borrow()))
+            .collect()
     }
 
-    /// Merges two trees into a single tree.
+    /// Returns an iterator over the ids of all nodes currently in the forest
+    /// (in increasing order, skipping the ids of removed nodes).
     ///
     /// # Examples
     /// ```
@@ -174,134 +384,3350 @@ impl<P: Path> LinkCutTree<P> {
     /// let alice = lctree.make_tree(0.0);
     /// let bob = lctree.make_tree(1.0);
     /// let clay = lctree.make_tree(2.0);
+    /// lctree.remove_tree(bob).unwrap();
+    ///
+    /// let ids = lctree.node_ids().collect::<Vec<_>>();
+    /// assert_eq!(ids, vec![alice, clay]);
+    /// ```
+    pub fn node_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.forest.node_ids()
+    }
+
+    /// Returns `true` if `v` is the id of a node in the forest
+    /// (i.e. it was returned by `make_tree` and not removed since).
+    #[must_use]
+    pub fn contains(&self, v: usize) -> bool {
+        self.forest.contains(v)
+    }
+
+    /// Returns a read-only view of node `v` (see [`NodeView`]),
+    /// or `None` if there is no such node.
     ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree, ParentKind};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
     /// lctree.link(alice, bob);
-    /// lctree.link(bob, clay);
-    /// assert!(lctree.connected(alice, clay));
+    /// lctree.replace_weight(alice, 11.0);
+    ///
+    /// let view = lctree.node(alice).unwrap();
+    /// assert_eq!(view.weight, 11.0);
+    /// assert_eq!(view.degree, 1);
+    /// println!("{:?}", view.parent); // e.g. ParentKind::Root
+    /// assert!(lctree.node(7).is_none());
     /// ```
-    pub fn link(&mut self, v: usize, w: usize) -> bool {
-        self.reroot(v);
-        self.access(w);
-        // if access(w) messed with the root of the tree, then v and w are connected:
-        if self.forest.parent_of(v).is_some() || v == w {
-            return false;
+    #[must_use]
+    pub fn node(&self, v: usize) -> Option<NodeView<P>> {
+        if self.contains(v) {
+            Some(self.forest.view_of(v))
+        } else {
+            None
         }
-        // v is the root of its represented tree:
-        self.forest.set_left(v, w);
-        true
     }
 
-    /// Checks if two nodes are connected by a link
-    /// (i.e. v is the parent of w or vice versa).
+    /// Returns the number of edges connected to `v` in `O(1)` time
+    /// (counted in `O(n)` without the `degree-tracking` feature).
     ///
     /// # Examples
     /// ```
     /// use lctree::LinkCutTree;
     ///
     /// let mut lctree = LinkCutTree::default();
-    /// let alice = lctree.make_tree(0.0);
-    /// let bob = lctree.make_tree(0.0);
-    /// let clay = lctree.make_tree(0.0);
-    ///
-    /// lctree.link(alice, bob);
-    /// lctree.link(bob, clay);
-    ///
-    /// assert!(lctree.linked(alice, bob)); // alice and bob are connected by a link
-    /// assert!(!lctree.linked(alice, clay)); // alice and clay are not connected by a link
+    /// let nodes = lctree.extend_forest([0.0; 3]);
+    /// lctree.link(nodes[1], nodes[0]);
+    /// lctree.link(nodes[2], nodes[0]);
+    /// assert_eq!(lctree.degree(nodes[0]), 2);
+    /// assert_eq!(lctree.degree(nodes[1]), 1);
     /// ```
-    pub fn linked(&mut self, v: usize, w: usize) -> bool {
-        self.reroot(v);
-        self.access(w);
-        self.forest.left_of(w) == Some(v) && self.forest.right_of(v).is_none()
+    #[must_use]
+    pub fn degree(&self, v: usize) -> usize {
+        self.forest.degree_of(v)
     }
 
-    /// Cuts the link between two nodes (if it exists)
+    fn check_node(&self, v: usize) -> Result<(), InvalidNode> {
+        if self.contains(v) {
+            Ok(())
+        } else {
+            Err(InvalidNode(v))
+        }
+    }
+
+    /// Returns the number of nodes in the forest.
     ///
     /// # Examples
     /// ```
     /// use lctree::LinkCutTree;
     ///
     /// let mut lctree = LinkCutTree::default();
+    /// assert!(lctree.is_empty());
+    ///
     /// let alice = lctree.make_tree(0.0);
     /// let bob = lctree.make_tree(1.0);
-    /// assert!(!lctree.connected(alice, bob)); // not connected yet
+    /// assert_eq!(lctree.len(), 2);
+    ///
+    /// lctree.remove_tree(bob).unwrap();
+    /// assert_eq!(lctree.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.forest.len()
+    }
+
+    /// Returns `true` if the forest contains no nodes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Rewrites the weight of every node to `f(idx, weight)` and recomputes the path aggregates
+    /// in a single `O(n)` pass.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight validation is enabled and `f` returns a NaN or infinite weight.
     ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
     /// lctree.link(alice, bob);
-    /// assert!(lctree.connected(alice, bob)); // now connected
+    /// assert_eq!(lctree.path(alice, bob).sum, 3.0);
     ///
-    /// lctree.cut(alice, bob);
-    /// assert!(!lctree.connected(alice, bob)); // not connected again
+    /// // Rescale all weights:
+    /// lctree.map_weights(|_, weight| weight * 10.0);
+    /// assert_eq!(lctree.path(alice, bob).sum, 30.0);
     /// ```
-    pub fn cut(&mut self, v: usize, w: usize) -> bool {
-        if !self.linked(v, w) {
-            return false;
+    pub fn map_weights<F: FnMut(usize, f64) -> f64>(&mut self, mut f: F) {
+        if self.validate_weights {
+            self.forest.map_weights(|idx, weight| {
+                let weight = f(idx, weight);
+                if let Err(err) = WeightError::check(weight) {
+                    panic!("map_weights: {err}");
+                }
+                weight
+            });
+        } else {
+            self.forest.map_weights(f);
+        }
+        if self.recorder.as_ref().map_or(false, Recorder::is_log) {
+            for (v, weight) in self.weights() {
+                self.record(Operation::ReplaceWeight(v, weight));
+            }
         }
-        self.forest.cut_left(w);
-        true
     }
 
-    /// Performs path aggregation on a path between two nodes (if they are connected)
+    /// Recomputes the path aggregates of the whole forest bottom-up in `O(n)`.
+    ///
+    /// This is useful after editing many weights in bulk (or after changing the behavior of a
+    /// custom [`Path`] aggregate), as it is cheaper than repairing the aggregates node by node.
+    /// It also drops the memoized query results (see [`LinkCutTree::set_query_cache`]).
     ///
     /// # Examples
     /// ```
-    /// use lctree::{LinkCutTree, FindMax};
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    ///
+    /// lctree.rebuild_aggregates();
+    /// assert_eq!(lctree.path(alice, bob).sum, 3.0);
+    /// ```
+    pub fn rebuild_aggregates(&mut self) {
+        self.forest.rebuild_aggregates();
+    }
+
+    /// Enables (or disables) deferring the path aggregates: the links, cuts and weight updates
+    /// only mark the aggregates they change as dirty, and the dirty aggregates are recomputed
+    /// when a path query reaches them. This makes the mutations cheaper for link/cut-heavy workloads
+    /// with few queries (especially with expensive aggregates), at the cost of slower queries.
+    /// Disabled by default, and disabling it recomputes all aggregates in `O(n)`.
+    ///
+    /// The sizes and anchors (and the runtime aggregates of `register_aggregate`) are still
+    /// maintained eagerly, while the aggregates reported by [`LinkCutTree::node`] and `dump_state`
+    /// may be out of date.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// lctree.set_lazy_aggregates(true);
+    /// let nodes = lctree.extend_forest([1.0, 2.0, 3.0]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    /// lctree.replace_weight(nodes[1], 5.0);
+    ///
+    /// // the aggregates are recomputed by the query:
+    /// assert_eq!(lctree.path(nodes[0], nodes[2]).sum, 9.0);
+    /// ```
+    pub fn set_lazy_aggregates(&mut self, enabled: bool) {
+        self.forest.set_lazy_aggregates(enabled);
+    }
+
+    /// Returns `true` if the path aggregates are only recomputed when they are queried.
+    #[must_use]
+    pub fn lazy_aggregates(&self) -> bool {
+        self.forest.lazy_aggregates()
+    }
+
+    /// Starts a bulk edit: the path aggregates are not maintained by the links, cuts and
+    /// weight updates until `end_bulk_edit` recomputes them all at once in `O(n)`,
+    /// which is cheaper for bulk loads than repairing them operation by operation.
+    /// The queries in between still work, but recompute the aggregates they reach
+    /// (as with [`LinkCutTree::set_lazy_aggregates`]). Does nothing during a bulk edit.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let nodes = lctree.extend_forest([1.0; 100]);
+    ///
+    /// lctree.begin_bulk_edit();
+    /// for i in 1..nodes.len() {
+    ///     lctree.link(nodes[i - 1], nodes[i]);
+    /// }
+    /// lctree.end_bulk_edit();
+    /// assert_eq!(lctree.path(nodes[0], nodes[99]).sum, 100.0);
+    /// ```
+    pub fn begin_bulk_edit(&mut self) {
+        if self.bulk_edit.is_none() {
+            self.bulk_edit = Some(self.forest.lazy_aggregates());
+            self.forest.set_lazy_aggregates(true);
+        }
+    }
+
+    /// Ends a bulk edit, recomputing all path aggregates in `O(n)` and restoring
+    /// the previous aggregate mode. Does nothing if there is no bulk edit in progress.
+    pub fn end_bulk_edit(&mut self) {
+        if let Some(lazy) = self.bulk_edit.take() {
+            self.forest.set_lazy_aggregates(false);
+            self.forest.set_lazy_aggregates(lazy);
+        }
+    }
+
+    /// Returns `true` between `begin_bulk_edit` and `end_bulk_edit`.
+    #[must_use]
+    pub fn in_bulk_edit(&self) -> bool {
+        self.bulk_edit.is_some()
+    }
+
+    /// Converts the link-cut tree into a link-cut tree with another path aggregate type,
+    /// preserving its structure and weights, and recomputing the aggregates in `O(n)`.
+    /// The nodes of the converted link-cut tree are kept in a `Vec`.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, FindSum, LinkCutTree};
     ///
     /// let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
-    /// let alice = lctree.make_tree(0.0);
-    /// let bob = lctree.make_tree(10.0);
-    /// let clay = lctree.make_tree(1.0);
-    /// let dave = lctree.make_tree(2.0);
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    /// assert_eq!(lctree.path(alice, bob).weight, 2.0);
     ///
-    /// // Form a path from Alice to Dave:
+    /// let mut lctree: LinkCutTree<FindSum> = lctree.into_aggregate();
+    /// assert_eq!(lctree.path(alice, bob).sum, 3.0);
+    /// ```
+    #[must_use]
+    pub fn into_aggregate<Q: Path>(self) -> LinkCutTree<Q, Ix> {
+        LinkCutTree::<Q, Ix> {
+            forest: self.forest.into_aggregate(),
+            validate_weights: self.validate_weights,
+            adjacency: self.adjacency,
+            #[cfg(feature = "component-sizes")]
+            components: self.components,
+            cache: self.cache.map(|_| QueryCache::new()),
+            bulk_edit: self.bulk_edit,
+            recorder: self.recorder,
+        }
+    }
+
+    /// Delete a tree with a single node with the given id.
+    /// The id may be reused by the next `make_tree` (see [`LinkCutTree::set_id_reuse`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RemoveError::NotSingleton`] with the degree of the node if the node is still
+    /// linked to other nodes, or [`RemoveError::InvalidNode`] if there is no node with the given id
+    /// (the forest is left unchanged). Without the `degree-tracking` feature, the degree is counted
+    /// in `O(n)` for the error.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, RemoveError};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
     /// lctree.link(alice, bob);
-    /// lctree.link(bob, clay);
-    /// lctree.link(clay, dave);
+    /// assert_eq!(lctree.remove_tree(alice), Err(RemoveError::NotSingleton { degree: 1 }));
     ///
-    /// // Find the richest guy in the path from Alice to Dave:
-    /// let richest_guy = lctree.path(alice, dave);
-    /// assert_eq!(richest_guy.idx, bob);
-    /// assert_eq!(richest_guy.weight, 10.0);
+    /// lctree.cut(alice, bob);
+    /// assert_eq!(lctree.remove_tree(alice), Ok(()));
     /// ```
-    pub fn path(&mut self, v: usize, w: usize) -> P {
-        self.reroot(v);
-        self.access(w);
-        if self.forest.parent_of(v).is_none() && v != w {
-            return P::default(f64::INFINITY, usize::MAX);
+    pub fn remove_tree(&mut self, idx: usize) -> Result<(), RemoveError> {
+        self.check_node(idx)?;
+        if !self.forest.is_singleton(idx) {
+            let degree = self.forest.degree_of(idx);
+            return Err(RemoveError::NotSingleton { degree });
         }
-        self.forest.aggregated_path_of(w)
+        self.remove_tree_unchecked(idx);
+        Ok(())
     }
 
-    /// Finds the root of the tree that the query node is in.
-    pub fn findroot(&mut self, v: usize) -> usize {
+    /// Same as [`LinkCutTree::remove_tree`], but panics instead of returning an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree contains more than one node.
+    pub fn remove_tree_unchecked(&mut self, idx: usize) {
+        self.forest.delete_node(idx);
+        #[cfg(feature = "component-sizes")]
+        if let Some(components) = &mut self.components {
+            components.remove(1);
+        }
+        self.record(Operation::RemoveTree(idx));
+    }
+
+    /// Constructs a path from a node to the root of the tree.
+    /// Returns the last node where the path joined the previously accessed path
+    /// (i.e. the lowest common ancestor of `v` and the previously accessed node).
+    fn access(&mut self, v: usize) -> usize {
+        self.forest.count_access();
+        self.forest.splay_and_remove_preferred_child(v);
+
+        let mut last = v;
+        while let Some(path_idx) = self.forest.path_parent_of(v) {
+            last = path_idx;
+            self.forest.splay_and_switch_preferred_child(path_idx, v);
+            self.forest.splay(v); // just a rotation
+        }
+        last
+    }
+
+    // Makes v the root of its represented tree by flipping the path from v to the root.
+    fn evert(&mut self, v: usize) {
+        self.access(v);
+        self.forest.flip(v);
+    }
+
+    /// Same as `evert(v)`, but also returns the previous root of the tree.
+    fn reroot_from(&mut self, v: usize) -> usize {
         self.access(v);
+        // the previous root is the leftmost node of the accessed path:
         let mut root = v;
         while let Some(left) = self.forest.left_of(root) {
             root = left;
+            self.forest.normalize(root);
         }
-        self.forest.splay(root); // fast access to the root next time
+        self.forest.splay(root);
+        self.forest.splay(v); // v is the last node of the path, so it has no right child again
+        self.forest.flip(v);
         root
     }
+
+    /// Checks if two nodes are connected (i.e. in the same tree).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// assert!(!lctree.connected(alice, bob)); // not connected yet
+    ///
+    /// lctree.link(alice, bob);
+    /// assert!(lctree.connected(alice, bob)); // now connected
+    /// ```
+    pub fn connected(&mut self, v: usize, w: usize) -> bool {
+        let connected = self.is_connected(v, w);
+        self.record(Operation::Connected(v, w));
+        connected
+    }
+
+    fn is_connected(&mut self, v: usize, w: usize) -> bool {
+        let version = self.forest.version();
+        if let Some(connected) = self
+            .cache
+            .as_mut()
+            .and_then(|cache| cache.connected(version, v, w))
+        {
+            return connected;
+        }
+        let connected = v == w || self.find_root(v) == self.find_root(w);
+        if let Some(cache) = &mut self.cache {
+            cache.insert_connected(version, v, w, connected);
+        }
+        connected
+    }
+
+    /// Same as [`LinkCutTree::connected`], but returns an error instead of panicking
+    /// if a node does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{InvalidNode, LinkCutTree};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// assert_eq!(lctree.try_connected(alice, bob), Ok(false));
+    /// assert_eq!(lctree.try_connected(alice, 7), Err(InvalidNode(7)));
+    ///
+    /// lctree.remove_tree(bob).unwrap();
+    /// assert_eq!(lctree.try_connected(alice, bob), Err(InvalidNode(bob)));
+    /// ```
+    pub fn try_connected(&mut self, v: usize, w: usize) -> Result<bool, InvalidNode> {
+        self.check_node(v)?;
+        self.check_node(w)?;
+        Ok(self.connected(v, w))
+    }
+
+    /// Computes the pairwise connectivity of a set of nodes: the bit at row `i` and column `j`
+    /// of the returned matrix is set if `nodes[i]` and `nodes[j]` are in the same tree.
+    ///
+    /// Every node is accessed once to find the root of its tree (which does not reroot the tree)
+    /// and the nodes are grouped by the roots, which takes `O(klogn + k^2/64)` time for `k` nodes
+    /// instead of the `O(k^2logn)` time of calling `connected` for every pair.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest(&[0.0; 4]);
+    /// lctree.link(nodes[0], nodes[2]);
+    ///
+    /// let matrix = lctree.connectivity_matrix(&[nodes[0], nodes[1], nodes[2]]);
+    /// assert!(matrix.get(0, 2) && matrix.get(2, 0));
+    /// assert!(!matrix.get(0, 1));
+    /// assert_eq!(matrix.to_string(), "101\n010\n101\n");
+    /// ```
+    pub fn connectivity_matrix(&mut self, nodes: &[usize]) -> BitMatrix {
+        let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, &v) in nodes.iter().enumerate() {
+            groups.entry(self.find_root(v)).or_default().push(i);
+        }
+        let mut matrix = BitMatrix::new(nodes.len());
+        for group in groups.values() {
+            for &i in group {
+                for &j in group {
+                    matrix.set(i, j);
+                }
+            }
+        }
+        matrix
+    }
+
+    /// Merges two trees into a single tree by adding an edge between `v` and `w`:
+    /// the tree of `v` is rerooted at `v` and attached as a child of `w`,
+    /// so the root of the tree containing `w` becomes the root of the merged tree.
+    ///
+    /// Returns [`LinkOutcome::AlreadyConnected`] if the nodes are already connected,
+    /// in which case their tree is only rerooted at `v` (which is the reported root).
+    /// Use [`LinkCutTree::link_reporting`] to keep and report the previous root instead.
+    ///
+    /// The edges of a `LinkCutTree` carry no weight: to aggregate the weights of the edges
+    /// on a path (e.g. the heaviest edge for a minimum spanning forest), link the nodes
+    /// of a [`WeightedLinkCutTree`](crate::WeightedLinkCutTree) with a weight instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, LinkOutcome};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let clay = lctree.make_tree(2.0);
+    ///
+    /// assert_eq!(lctree.link(alice, bob), LinkOutcome::Linked);
+    /// assert_eq!(lctree.link(bob, clay), LinkOutcome::Linked);
+    /// assert!(lctree.connected(alice, clay));
+    ///
+    /// // the tree containing bob is rerooted at alice:
+    /// assert_eq!(lctree.link(alice, clay), LinkOutcome::AlreadyConnected { root: alice });
+    /// ```
+    pub fn link(&mut self, v: usize, w: usize) -> LinkOutcome {
+        self.evert(v);
+        let outcome = self.link_trees(v, w);
+        self.record(Operation::Link(v, w));
+        outcome
+    }
+
+    /// Same as [`LinkCutTree::link`], but if the nodes are already connected, the root of their tree
+    /// does not change and is reported by [`LinkOutcome::AlreadyConnected`].
+    /// Finding the root and rerooting the tree back make this slower than `link`.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, LinkOutcome};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let clay = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    ///
+    /// // clay is the root of the tree containing bob:
+    /// assert_eq!(
+    ///     lctree.link_reporting(alice, clay),
+    ///     LinkOutcome::AlreadyConnected { root: clay }
+    /// );
+    /// assert_eq!(lctree.findroot(alice), clay);
+    /// ```
+    pub fn link_reporting(&mut self, v: usize, w: usize) -> LinkOutcome {
+        let root = self.reroot_from(v);
+        let outcome = match self.link_trees(v, w) {
+            LinkOutcome::Linked => LinkOutcome::Linked,
+            LinkOutcome::AlreadyConnected { .. } => {
+                self.evert(root);
+                LinkOutcome::AlreadyConnected { root }
+            }
+        };
+        self.record(Operation::Link(v, w));
+        outcome
+    }
+
+    // Links `v` (the root of its represented tree) to `w` if they are not connected.
+    fn link_trees(&mut self, v: usize, w: usize) -> LinkOutcome {
+        self.access(w);
+        // if access(w) messed with the root of the tree, then v and w are connected:
+        if self.forest.parent_of(v).is_some() || v == w {
+            return LinkOutcome::AlreadyConnected { root: v };
+        }
+        #[cfg(feature = "component-sizes")]
+        self.link_components(v, w);
+        #[cfg(not(feature = "component-sizes"))]
+        self.forest.set_left(v, w);
+        if let Some(adjacency) = &mut self.adjacency {
+            adjacency.link(v, w);
+        }
+        LinkOutcome::Linked
+    }
+
+    // Links the root `v` of its represented tree to `w` (both were just accessed, so they know
+    // the sizes and the anchors of their trees), merging the sizes and the anchors of the trees.
+    #[cfg(feature = "component-sizes")]
+    fn link_components(&mut self, v: usize, w: usize) {
+        let (v_size, w_size) = (self.forest.tree_size_of(v), self.forest.tree_size_of(w));
+        if let Some(components) = &mut self.components {
+            components.merge(v_size, w_size);
+        }
+        // the merged tree keeps the id of the larger tree (see `component_id`), and the other
+        // anchor is dropped before linking, so that the summed anchors fit in the index type:
+        let (dropped_tree, dropped_anchor) = if v_size > w_size {
+            (w, self.forest.tree_anchor_of(w) - 1)
+        } else {
+            (v, self.forest.tree_anchor_of(v) - 1)
+        };
+        if dropped_anchor == dropped_tree {
+            self.forest.set_anchor(dropped_anchor, false);
+        } else {
+            self.access(dropped_anchor);
+            self.forest.set_anchor(dropped_anchor, false);
+            // restore the access of the root v or of w:
+            self.access(dropped_tree);
+        }
+        self.forest.set_left(v, w);
+    }
+
+    /// Same as [`LinkCutTree::link`], but returns an error instead of panicking
+    /// if a node does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn try_link(&mut self, v: usize, w: usize) -> Result<LinkOutcome, InvalidNode> {
+        self.check_node(v)?;
+        self.check_node(w)?;
+        Ok(self.link(v, w))
+    }
+
+    /// Attaches the tree rooted at `child` as a child of `parent` without rerooting either tree,
+    /// so the root of the tree containing `parent` stays the root of the merged tree,
+    /// as the rooted trees of e.g. the dynamic tree algorithms for maximum flows expect.
+    /// Note that the queries between two nodes (such as `path` and `connected`)
+    /// reroot the tree at their first node.
+    ///
+    /// Returns [`LinkOutcome::AlreadyConnected`] if `parent` is in the tree of `child`,
+    /// in which case nothing changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NotRoot`] with the root of the tree of `child` if `child` is not that root,
+    /// in which case nothing changes.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, NotRoot};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c, d) = (0, 1, 2, 3);
+    /// lctree.extend_forest(&[0.0; 4]);
+    /// lctree.link_rooted(b, a).unwrap();
+    /// lctree.link_rooted(d, c).unwrap(); // c is the root of c - d
+    /// assert_eq!(lctree.link_rooted(d, b), Err(NotRoot { root: c }));
+    ///
+    /// lctree.link_rooted(c, b).unwrap();
+    /// assert_eq!(lctree.findroot(d), a);
+    /// assert_eq!(lctree.parent(c), Some(b)); // d - c - b - a
+    /// ```
+    pub fn link_rooted(&mut self, child: usize, parent: usize) -> Result<LinkOutcome, NotRoot> {
+        let root = self.find_root(child);
+        if root != child {
+            return Err(NotRoot { root });
+        }
+        // rerooting the tree of `child` at its root is a no-op:
+        Ok(self.link(child, parent))
+    }
+
+    /// Links the given pairs of nodes in order and returns the outcome of each link,
+    /// as if `link` was called for each pair (so a pair is `AlreadyConnected` if the previous
+    /// links of the batch already connected it), e.g. to load the edges of a graph in bulk.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, LinkOutcome};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let _ = lctree.extend_forest([0.0; 4]);
+    /// let outcomes = lctree.link_all(&[(0, 1), (2, 3), (1, 3), (0, 2)]);
+    /// assert!(outcomes[..3].iter().all(LinkOutcome::is_linked));
+    /// assert!(!outcomes[3].is_linked()); // 0 and 2 were connected by the previous links
+    /// ```
+    #[allow(clippy::must_use_candidate)]
+    pub fn link_all(&mut self, edges: &[(usize, usize)]) -> Vec<LinkOutcome> {
+        edges.iter().map(|&(v, w)| self.link(v, w)).collect()
+    }
+
+    /// Checks if two nodes are connected by a link
+    /// (i.e. v is the parent of w or vice versa).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(0.0);
+    /// let clay = lctree.make_tree(0.0);
+    ///
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    ///
+    /// assert!(lctree.linked(alice, bob)); // alice and bob are connected by a link
+    /// assert!(!lctree.linked(alice, clay)); // alice and clay are not connected by a link
+    /// ```
+    pub fn linked(&mut self, v: usize, w: usize) -> bool {
+        let linked = self.is_linked(v, w);
+        self.record(Operation::Linked(v, w));
+        linked
+    }
+
+    fn is_linked(&mut self, v: usize, w: usize) -> bool {
+        self.evert(v);
+        self.access(w);
+        self.has_edge_to(v, w)
+    }
+
+    // Checks if v is the parent of w, right after `access(w)` in the tree rooted at v.
+    fn has_edge_to(&self, v: usize, w: usize) -> bool {
+        self.forest.left_of(w) == Some(v) && self.forest.right_of(v).is_none()
+    }
+
+    /// Cuts the link between two nodes (if it exists), splitting their tree into two trees.
+    /// The tree is rerooted at `v` first, so `v` and `w` are the roots of the two trees
+    /// (use [`LinkCutTree::cut_reporting`] to keep the root of the tree instead).
+    ///
+    /// Returns [`CutOutcome::NoSuchEdge`] if there is no edge between the nodes,
+    /// in which case the tree is only rerooted at `v`.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{CutOutcome, LinkCutTree};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let clay = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    /// assert!(lctree.connected(alice, clay));
+    ///
+    /// let outcome = lctree.cut(alice, bob);
+    /// assert_eq!(outcome, CutOutcome::Cut { remaining_roots: (alice, bob) });
+    /// assert!(!lctree.connected(alice, bob)); // not connected again
+    ///
+    /// assert_eq!(lctree.cut(alice, clay), CutOutcome::NoSuchEdge);
+    /// ```
+    pub fn cut(&mut self, v: usize, w: usize) -> CutOutcome {
+        self.evert(v);
+        let outcome = if self.cut_edge(v, w) {
+            CutOutcome::Cut {
+                remaining_roots: (v, w),
+            }
+        } else {
+            CutOutcome::NoSuchEdge
+        };
+        self.record(Operation::Cut(v, w));
+        outcome
+    }
+
+    /// Same as [`LinkCutTree::cut`], but the tree that contains the root keeps it,
+    /// and the other tree is rooted at the endpoint of the edge it contains
+    /// (if there is no edge between the nodes, nothing changes).
+    /// Finding the root and rerooting the tree back make this slower than `cut`.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{CutOutcome, LinkCutTree};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let clay = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    ///
+    /// // clay is the root of the tree, so it stays with bob:
+    /// let outcome = lctree.cut_reporting(alice, bob);
+    /// assert_eq!(outcome, CutOutcome::Cut { remaining_roots: (alice, clay) });
+    /// assert_eq!(lctree.findroot(bob), clay);
+    /// ```
+    pub fn cut_reporting(&mut self, v: usize, w: usize) -> CutOutcome {
+        let root = self.reroot_from(v);
+        let outcome = if !self.cut_edge(v, w) {
+            self.evert(root);
+            CutOutcome::NoSuchEdge
+        } else if root == v {
+            CutOutcome::Cut {
+                remaining_roots: (v, w),
+            }
+        } else {
+            // v and w are now the roots of their trees, and the original root is in one of them:
+            let remaining_roots = if self.reroot_from(root) == v {
+                (root, w)
+            } else {
+                (v, root)
+            };
+            CutOutcome::Cut { remaining_roots }
+        };
+        self.record(Operation::Cut(v, w));
+        outcome
+    }
+
+    // Cuts `w` from its parent `v` (`w` was just accessed, so `v` is in its left subtree),
+    // splitting the sizes of the trees and giving the tree without the anchor a new one.
+    #[cfg(feature = "component-sizes")]
+    fn cut_components(&mut self, v: usize, w: usize) {
+        let size = self.forest.tree_size_of(w);
+        self.forest.cut_left(w);
+        if let Some(components) = &mut self.components {
+            let w_size = self.forest.tree_size_of(w);
+            components.split(size - w_size, w_size);
+        }
+        // the tree without the anchor gets a new one (see `component_id`):
+        if self.forest.tree_anchor_of(w) == 0 {
+            self.forest.set_anchor(w, true);
+        } else {
+            // v is in the splay tree that was cut off from w:
+            self.forest.splay(v);
+            self.forest.set_anchor(v, true);
+        }
+    }
+
+    // Cuts the edge between `v` (the root of its represented tree) and `w` if it exists.
+    fn cut_edge(&mut self, v: usize, w: usize) -> bool {
+        self.access(w);
+        if !self.has_edge_to(v, w) {
+            return false;
+        }
+        self.detach(v, w);
+        true
+    }
+
+    // Cuts `w` from its parent `v`, right after `access(w)`.
+    fn detach(&mut self, v: usize, w: usize) {
+        #[cfg(feature = "component-sizes")]
+        self.cut_components(v, w);
+        #[cfg(not(feature = "component-sizes"))]
+        self.forest.cut_left(w);
+        if let Some(adjacency) = &mut self.adjacency {
+            adjacency.cut(v, w);
+        }
+    }
+
+    /// Same as [`LinkCutTree::cut`], but returns an error instead of panicking
+    /// if a node does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn try_cut(&mut self, v: usize, w: usize) -> Result<CutOutcome, InvalidNode> {
+        self.check_node(v)?;
+        self.check_node(w)?;
+        Ok(self.cut(v, w))
+    }
+
+    /// Cuts the link between `v` and its parent under the current root of its tree
+    /// in `O(logn)` amortized time, so that the subtree of `v` becomes a tree rooted at `v`,
+    /// and returns the former parent (or `None` if `v` is the root, in which case nothing changes).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// // a is the root of the tree:
+    /// //     a
+    /// //    / \
+    /// //   b   c
+    /// //   |
+    /// //   d
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c, d) = (0, 1, 2, 3);
+    /// lctree.extend_forest(&[0.0; 4]);
+    /// lctree.link_all(&[(b, a), (c, a), (d, b)]);
+    ///
+    /// assert_eq!(lctree.cut_from_parent(b), Some(a));
+    /// assert_eq!(lctree.findroot(d), b);
+    /// assert_eq!(lctree.findroot(c), a);
+    /// assert_eq!(lctree.cut_from_parent(a), None);
+    /// ```
+    #[allow(clippy::must_use_candidate)]
+    pub fn cut_from_parent(&mut self, v: usize) -> Option<usize> {
+        let parent = self.parent(v)?;
+        // the path above v is its left subtree after accessing it, so the tree keeps the root:
+        self.access(v);
+        self.detach(parent, v);
+        self.record(Operation::Cut(parent, v));
+        Some(parent)
+    }
+
+    /// Cuts the given edges in order and returns the outcome of each cut, as if `cut` was called
+    /// for each edge, e.g. to remove all the edges that expired at once.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{CutOutcome, LinkCutTree};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let _ = lctree.extend_forest([0.0; 4]);
+    /// lctree.link_all(&[(0, 1), (1, 2), (2, 3)]);
+    /// let outcomes = lctree.cut_many(&[(1, 0), (3, 2), (0, 3)]);
+    /// assert!(outcomes[0].is_cut() && outcomes[1].is_cut());
+    /// assert_eq!(outcomes[2], CutOutcome::NoSuchEdge);
+    /// assert!(lctree.connected(1, 2));
+    /// ```
+    #[allow(clippy::must_use_candidate)]
+    pub fn cut_many(&mut self, edges: &[(usize, usize)]) -> Vec<CutOutcome> {
+        edges.iter().map(|&(v, w)| self.cut(v, w)).collect()
+    }
+
+    /// Detaches the subtree of `v` (under the current root of its tree) from its parent and attaches
+    /// it as a child of `new_parent` in `O(logn)` amortized time, e.g. to move a node of a scene graph
+    /// or an org chart along with everything below it. The roots of the trees do not change,
+    /// except that a tree rooted at `v` is attached to the tree of `new_parent` as a whole.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MoveError::IntoOwnSubtree`] if `new_parent` is in the subtree of `v`
+    /// (including `v` itself), or [`MoveError::InvalidNode`] with the first id
+    /// that is not a node of the forest, in which case nothing changes.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, MoveError};
+    ///
+    /// // a is the root of the tree:
+    /// //     a
+    /// //    / \
+    /// //   b   c
+    /// //   |
+    /// //   d
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c, d) = (0, 1, 2, 3);
+    /// lctree.extend_forest(&[0.0; 4]);
+    /// lctree.link_all(&[(b, a), (c, a), (d, b)]);
+    ///
+    /// lctree.move_subtree(b, c).unwrap();
+    /// assert_eq!(lctree.parent(b), Some(c)); // d - b - c - a
+    /// assert_eq!(lctree.move_subtree(c, d), Err(MoveError::IntoOwnSubtree));
+    /// ```
+    pub fn move_subtree(&mut self, v: usize, new_parent: usize) -> Result<(), MoveError> {
+        self.check_node(v)?;
+        self.check_node(new_parent)?;
+        if self.find_root(v) == self.find_root(new_parent) && self.connected_lca(v, new_parent) == v
+        {
+            return Err(MoveError::IntoOwnSubtree);
+        }
+        // the subtree of v keeps v as its root when it is cut off:
+        self.cut_from_parent(v);
+        self.link(v, new_parent);
+        Ok(())
+    }
+
+    /// Performs path aggregation on a path between two nodes (if they are connected)
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, FindMax};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(10.0);
+    /// let clay = lctree.make_tree(1.0);
+    /// let dave = lctree.make_tree(2.0);
+    ///
+    /// // Form a path from Alice to Dave:
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    /// lctree.link(clay, dave);
+    ///
+    /// // Find the richest guy in the path from Alice to Dave:
+    /// let richest_guy = lctree.path(alice, dave);
+    /// assert_eq!(richest_guy.idx, bob);
+    /// assert_eq!(richest_guy.weight, 10.0);
+    /// ```
+    pub fn path(&mut self, v: usize, w: usize) -> P {
+        let path = self.aggregate_path(v, w);
+        self.record(Operation::Path(v, w));
+        path
+    }
+
+    fn aggregate_path(&mut self, v: usize, w: usize) -> P {
+        let version = self.forest.version();
+        if let Some(path) = self
+            .cache
+            .as_mut()
+            .and_then(|cache| cache.path(version, v, w))
+        {
+            return path;
+        }
+        self.evert(v);
+        self.access(w);
+        let path = if self.forest.parent_of(v).is_none() && v != w {
+            P::default(f64::INFINITY, usize::MAX)
+        } else {
+            self.forest.aggregated_path_of(w)
+        };
+        if let Some(cache) = &mut self.cache {
+            cache.insert_path(version, v, w, path.clone());
+        }
+        path
+    }
+
+    /// Same as [`LinkCutTree::path`], but returns an error instead of panicking
+    /// if a node does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn try_path(&mut self, v: usize, w: usize) -> Result<P, InvalidNode> {
+        self.check_node(v)?;
+        self.check_node(w)?;
+        Ok(self.path(v, w))
+    }
+
+    /// Calls `f` with the id and the weight of every node on the path from `v` to `w` in order,
+    /// or returns `false` without calling it if `v` and `w` are not connected.
+    /// This takes `O(logn)` amortized time to expose the path plus `O(1)` per node on it.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest([1.0, 2.0, 3.0, 4.0]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    /// lctree.link(nodes[1], nodes[3]);
+    ///
+    /// let mut route = Vec::new();
+    /// assert!(lctree.for_each_on_path(nodes[3], nodes[2], |v, weight| route.push((v, weight))));
+    /// assert_eq!(route, vec![(nodes[3], 4.0), (nodes[1], 2.0), (nodes[2], 3.0)]);
+    /// ```
+    pub fn for_each_on_path<F: FnMut(usize, f64)>(&mut self, v: usize, w: usize, mut f: F) -> bool {
+        match self.path_nodes(v, w) {
+            Some(nodes) => {
+                for idx in nodes {
+                    f(idx, self.forest.weight_of(idx));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Same as [`LinkCutTree::for_each_on_path`], but `f` may change the weights of the nodes,
+    /// after which the aggregates of the path are recomputed in `O(k)` time for `k` nodes on it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight validation is enabled and a new weight is NaN or infinite.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let nodes = lctree.extend_forest([1.0, 2.0, 3.0, 4.0]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    /// lctree.link(nodes[2], nodes[3]);
+    ///
+    /// // double the weights from the second node on:
+    /// let mut position = 0;
+    /// lctree.for_each_on_path_mut(nodes[0], nodes[3], |_, weight| {
+    ///     if position > 0 {
+    ///         *weight *= 2.0;
+    ///     }
+    ///     position += 1;
+    /// });
+    /// assert_eq!(lctree.path(nodes[0], nodes[3]).sum, 19.0);
+    /// assert_eq!(lctree.path(nodes[0], nodes[1]).sum, 5.0);
+    /// ```
+    pub fn for_each_on_path_mut<F: FnMut(usize, &mut f64)>(
+        &mut self,
+        v: usize,
+        w: usize,
+        mut f: F,
+    ) -> bool {
+        let nodes = match self.path_nodes(v, w) {
+            Some(nodes) => nodes,
+            None => return false,
+        };
+        let mut changes = Vec::new();
+        for idx in nodes {
+            let old = self.forest.weight_of(idx);
+            let mut weight = old;
+            f(idx, &mut weight);
+            if weight.to_bits() != old.to_bits() {
+                if self.validate_weights {
+                    if let Err(err) = WeightError::check(weight) {
+                        panic!("for_each_on_path_mut: {err}");
+                    }
+                }
+                changes.push((idx, weight));
+            }
+        }
+        if !changes.is_empty() {
+            self.forest.set_splay_tree_weights(w, &changes);
+        }
+        for &(idx, weight) in &changes {
+            self.record(Operation::ReplaceWeight(idx, weight));
+        }
+        true
+    }
+
+    /// Folds the ids and the weights of the nodes on the path from `v` to `w` in order
+    /// into an accumulator starting from `init`, or returns `None` if `v` and `w` are not connected.
+    /// This takes `O(logn)` amortized time to expose the path plus `O(1)` per node on it,
+    /// which suits one-off aggregations that do not need their own [`Path`] type.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest([3.0, 1.0, 4.0, 1.0, 5.0]);
+    /// for i in 1..nodes.len() {
+    ///     lctree.link(nodes[i], nodes[i - 1]);
+    /// }
+    ///
+    /// // the product of the weights on a path, and the ids on another one:
+    /// let product = lctree.path_fold(nodes[1], nodes[4], 1.0, |acc, _, weight| acc * weight);
+    /// assert_eq!(product, Some(20.0));
+    /// let route = lctree.path_fold(nodes[4], nodes[2], String::new(), |acc, v, _| acc + &v.to_string());
+    /// assert_eq!(route.as_deref(), Some("432"));
+    /// ```
+    pub fn path_fold<T, F: FnMut(T, usize, f64) -> T>(
+        &mut self,
+        v: usize,
+        w: usize,
+        init: T,
+        mut f: F,
+    ) -> Option<T> {
+        let nodes = self.path_nodes(v, w)?;
+        Some(
+            nodes
+                .into_iter()
+                .fold(init, |acc, idx| f(acc, idx, self.forest.weight_of(idx))),
+        )
+    }
+
+    /// Aggregates the weights of a contiguous segment of the path from `v` to `w`,
+    /// addressed by the positions of the nodes on the path (`v` is at position 0),
+    /// e.g. `path_range_aggregate(v, w, 3..8)` aggregates the nodes 3 to 7 hops away from `v`.
+    /// Returns `None` if `v` and `w` are not connected, or if the range is empty
+    /// or goes past the end of the path.
+    ///
+    /// The segment is cut out of the exposed path by the sizes of the splay trees,
+    /// which takes `O(logn)` amortized time (and requires the `depth-queries` feature).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let nodes = lctree.extend_forest([1.0, 2.0, 4.0, 8.0, 16.0]);
+    /// for i in 1..nodes.len() {
+    ///     lctree.link(nodes[i], nodes[i - 1]);
+    /// }
+    ///
+    /// assert_eq!(lctree.path_range_aggregate(nodes[0], nodes[4], 1..3).unwrap().sum, 6.0);
+    /// assert_eq!(lctree.path_range_aggregate(nodes[4], nodes[0], 1..=3).unwrap().sum, 14.0);
+    /// assert_eq!(lctree.path_range_aggregate(nodes[4], nodes[2], 2..).unwrap().sum, 4.0);
+    /// assert!(lctree.path_range_aggregate(nodes[4], nodes[2], 2..4).is_none());
+    /// ```
+    #[cfg(feature = "depth-queries")]
+    pub fn path_range_aggregate<R: RangeBounds<usize>>(
+        &mut self,
+        v: usize,
+        w: usize,
+        range: R,
+    ) -> Option<P> {
+        self.evert(v);
+        self.access(w);
+        if self.forest.parent_of(v).is_none() && v != w {
+            return None;
+        }
+        // the path from the root `v` to `w` is the splay tree of `w`:
+        let len = self.forest.size_of(w);
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1)?,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        };
+        if start >= end || end > len {
+            return None;
+        }
+        // the positions on the path are the depths under `v`:
+        let last = self.ancestor_at_depth(w, end - 1);
+        if start == 0 {
+            self.access(last);
+            Some(self.forest.aggregated_path_of(last))
+        } else {
+            let before = self.ancestor_at_depth(last, start - 1);
+            Some(self.path_below(before, last))
+        }
+    }
+
+    // Exposes the path between `v` and `w` as the splay tree of `w`, and lists its nodes
+    // from `v` to `w` with their pending additions pushed down (or `None` if not connected).
+    fn path_nodes(&mut self, v: usize, w: usize) -> Option<Vec<usize>> {
+        self.evert(v);
+        self.access(w);
+        if self.forest.parent_of(v).is_none() && v != w {
+            return None;
+        }
+        Some(self.forest.normalized_splay_tree(w))
+    }
+
+    /// Performs path aggregation for a batch of queries, returning `None` for the pairs
+    /// of nodes that are not connected.
+    ///
+    /// The queries are grouped by their first node, so that each group only reroots the tree once
+    /// and reuses the exposed preferred paths, which is faster than calling `path` in a loop
+    /// for query-heavy workloads. The results are returned in the order of the queries.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
+    /// let clay = lctree.make_tree(3.0);
+    /// let dave = lctree.make_tree(4.0);
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    ///
+    /// let sums = lctree
+    ///     .path_many(&[(alice, clay), (bob, alice), (alice, dave), (alice, bob)])
+    ///     .into_iter()
+    ///     .map(|path| path.map(|path| path.sum))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(sums, vec![Some(6.0), Some(3.0), None, Some(3.0)]);
+    /// ```
+    pub fn path_many(&mut self, pairs: &[(usize, usize)]) -> Vec<Option<P>> {
+        let mut order = (0..pairs.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| pairs[i].0);
+
+        let mut results = vec![None; pairs.len()];
+        let mut root = None;
+        for i in order {
+            let (v, w) = pairs[i];
+            if root == Some(v) {
+                // accessing other nodes does not change the root of the represented tree,
+                // but v has to be the root of its splay tree to check the connectivity:
+                self.forest.splay(v);
+            } else {
+                self.evert(v);
+                root = Some(v);
+            }
+            self.access(w);
+            if self.forest.parent_of(v).is_some() || v == w {
+                results[i] = Some(self.forest.aggregated_path_of(w));
+            }
+            self.record(Operation::Path(v, w));
+        }
+        results
+    }
+
+    /// Registers an additional path aggregate at runtime and returns its id.
+    /// The aggregate is defined by two functions:
+    /// - `init(weight, idx)`: the value of a single node,
+    /// - `combine(a, b)`: combines two values.
+    ///
+    /// `combine` has to be associative and commutative: the values of a splay tree are combined
+    /// node first (before its left child), and the flips of the splay trees are ignored,
+    /// so the values of a path are not combined in the order of the path.
+    ///
+    /// Registered aggregates are maintained alongside the aggregate `P` by every operation,
+    /// so a single forest can serve several statistics at once (see [`LinkCutTree::path_aggregate`]).
+    /// Registering an aggregate computes its values for the whole forest in `O(n)`.
+    /// Such an aggregate cannot be updated lazily, so `subtree_add`, `path_add` and `path_assign`
+    /// panic once it is registered (see [`LinkCutTree::register_updatable_aggregate`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    /// let sum = lctree.register_aggregate(|weight, _| weight, |a, b| a + b);
+    /// let min = lctree.register_aggregate(|weight, _| weight, f64::min);
+    ///
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(5.0);
+    /// let clay = lctree.make_tree(3.0);
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    ///
+    /// assert_eq!(lctree.path(alice, clay).weight, 5.0);
+    /// assert_eq!(lctree.path_aggregate(alice, clay, sum), Some(9.0));
+    /// assert_eq!(lctree.path_aggregate(alice, clay, min), Some(1.0));
+    /// ```
+    pub fn register_aggregate<I, C>(&mut self, init: I, combine: C) -> AggregateId
+    where
+        I: Fn(f64, usize) -> f64 + Send + Sync + 'static,
+        C: Fn(f64, f64) -> f64 + Send + Sync + 'static,
+    {
+        AggregateId(
+            self.forest
+                .register_aggregate(RuntimeAggregate::new(init, combine)),
+        )
+    }
+
+    /// Registers an additional path aggregate at runtime like [`LinkCutTree::register_aggregate`],
+    /// which can also be updated lazily by `subtree_add`, `path_add` and `path_assign`.
+    /// Besides `init` and `combine`, the aggregate is defined by two functions, which compute
+    /// the value of a splay subtree of `size` nodes (the counterparts of [`PathAdd::add_delta`]
+    /// and [`PathAssign::assign`]):
+    /// - `add(value, delta, size)`: after `delta` was added to the weights of its nodes,
+    /// - `assign(weight, idx, size)`: after the weights of its nodes were set to `weight`
+    ///   (where `idx` is the root of the splay subtree).
+    ///
+    /// Requires the `lazy-updates` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    /// let sum = lctree.register_updatable_aggregate(
+    ///     |weight, _| weight,
+    ///     |a, b| a + b,
+    ///     |sum, delta, size| sum + delta * size as f64,
+    ///     |weight, _, size| weight * size as f64,
+    /// );
+    ///
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(5.0);
+    /// let clay = lctree.make_tree(3.0);
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    ///
+    /// lctree.path_add(alice, bob, 2.0);
+    /// assert_eq!(lctree.path_aggregate(alice, clay, sum), Some(13.0));
+    /// lctree.path_assign(bob, clay, 4.0);
+    /// assert_eq!(lctree.path_aggregate(alice, clay, sum), Some(11.0));
+    /// ```
+    #[cfg(feature = "lazy-updates")]
+    pub fn register_updatable_aggregate<I, C, A, U>(
+        &mut self,
+        init: I,
+        combine: C,
+        add: A,
+        assign: U,
+    ) -> AggregateId
+    where
+        I: Fn(f64, usize) -> f64 + Send + Sync + 'static,
+        C: Fn(f64, f64) -> f64 + Send + Sync + 'static,
+        A: Fn(f64, f64, usize) -> f64 + Send + Sync + 'static,
+        U: Fn(f64, usize, usize) -> f64 + Send + Sync + 'static,
+    {
+        AggregateId(
+            self.forest
+                .register_aggregate(RuntimeAggregate::updatable(init, combine, add, assign)),
+        )
+    }
+
+    /// Computes a registered aggregate on the path between two nodes
+    /// (or returns `None` if they are not connected).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the aggregate was not registered on this forest.
+    pub fn path_aggregate(&mut self, v: usize, w: usize, aggregate: AggregateId) -> Option<f64> {
+        self.evert(v);
+        self.access(w);
+        if self.forest.parent_of(v).is_none() && v != w {
+            return None;
+        }
+        Some(self.forest.runtime_aggregate_of(w, aggregate.0))
+    }
+
+    /// Adds `delta` to the weights of all nodes in the subtree of `v`,
+    /// where the tree is rooted at its current root (see [`LinkCutTree::findroot`]).
+    ///
+    /// Note that the operations taking two nodes (`link`, `cut`, `linked`, `path`...)
+    /// make their first node the new root of its tree.
+    /// The aggregate `P` has to implement [`PathAdd`] (and the `lazy-updates` feature is required).
+    ///
+    /// # Panics
+    ///
+    /// Panics if an aggregate was registered without its lazy updates
+    /// (see [`LinkCutTree::register_updatable_aggregate`]), or if the weight validation
+    /// is enabled and `delta` is NaN or infinite (see [`LinkCutTree::try_subtree_add`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// // We form a link-cut tree for the following rooted tree:
+    /// //         a(1)
+    /// //        /    \
+    /// //     b(2)    c(3)
+    /// //     /
+    /// //   d(4)
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let a = lctree.make_tree(1.0);
+    /// let b = lctree.make_tree(2.0);
+    /// let c = lctree.make_tree(3.0);
+    /// let d = lctree.make_tree(4.0);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    /// lctree.link(d, b);
+    /// assert_eq!(lctree.findroot(d), a);
+    ///
+    /// // Add 10 to the weights of b and d:
+    /// lctree.subtree_add(b, 10.0);
+    /// assert_eq!(lctree.path(c, d).sum, 3.0 + 1.0 + 12.0 + 14.0);
+    /// ```
+    #[cfg(feature = "lazy-updates")]
+    pub fn subtree_add(&mut self, v: usize, delta: f64)
+    where
+        P: PathAdd,
+    {
+        if let Err(err) = self.check_update(delta) {
+            panic!("subtree_add: {err}");
+        }
+        self.add_to_subtree(v, delta);
+    }
+
+    /// Same as [`LinkCutTree::subtree_add`], but returns an error instead of panicking
+    /// (in which case no weight is changed).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`UpdateError`] if a registered aggregate cannot be updated lazily,
+    /// if the weight validation rejects `delta`, or if `v` is not a node of the forest.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree, UpdateError};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let alice = lctree.make_tree(1.0);
+    /// assert_eq!(lctree.try_subtree_add(alice, 2.0), Ok(()));
+    /// assert_eq!(lctree.try_subtree_add(7, 2.0), Err(UpdateError::InvalidNode(7)));
+    ///
+    /// let max = lctree.register_aggregate(|weight, _| weight, f64::max);
+    /// assert_eq!(lctree.try_subtree_add(alice, 2.0), Err(UpdateError::NotUpdatable(max)));
+    /// assert_eq!(lctree.path(alice, alice).sum, 3.0);
+    /// ```
+    #[cfg(feature = "lazy-updates")]
+    pub fn try_subtree_add(&mut self, v: usize, delta: f64) -> Result<(), UpdateError>
+    where
+        P: PathAdd,
+    {
+        self.check_node(v)?;
+        self.check_update(delta)?;
+        self.add_to_subtree(v, delta);
+        Ok(())
+    }
+
+    // Checks that the registered aggregates can follow a lazy update by the given delta (or weight).
+    #[cfg(feature = "lazy-updates")]
+    fn check_update(&self, value: f64) -> Result<(), UpdateError> {
+        if let Some(idx) = self.forest.fixed_aggregate() {
+            return Err(UpdateError::NotUpdatable(AggregateId(idx)));
+        }
+        if self.validate_weights {
+            WeightError::check(value)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "lazy-updates")]
+    fn add_to_subtree(&mut self, v: usize, delta: f64)
+    where
+        P: PathAdd,
+    {
+        // the subtree depends on the root, which is changed by queries that are not logged:
+        let root = self
+            .recorder
+            .as_ref()
+            .map_or(false, Recorder::is_log)
+            .then(|| self.find_root(v));
+        self.access(v);
+        self.forest.add_to_subtree(v, delta);
+        if let Some(root) = root {
+            self.record(Operation::Reroot(root));
+        }
+        self.record(Operation::SubtreeAdd(v, delta));
+    }
+
+    /// Adds `delta` to the weights of all nodes on the path between `v` and `w`
+    /// in `O(logn)` amortized time, or returns `false` if they are not connected.
+    ///
+    /// Requires the `lazy-updates` feature.
+    ///
+    /// The path is exposed as a single splay tree, and the addition is left pending at its root
+    /// until the nodes below are visited, so the path aggregate has to implement
+    /// [`PathAdd`] (as for `subtree_add`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if an aggregate was registered without its lazy updates
+    /// (see [`LinkCutTree::register_updatable_aggregate`]), or if the weight validation
+    /// is enabled and `delta` is NaN or infinite (see [`LinkCutTree::try_path_add`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree};
+    ///
+    /// // a chain of links with their loads:
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    /// let nodes = lctree.extend_forest([2.0, 5.0, 1.0, 3.0]);
+    /// lctree.link(nodes[1], nodes[0]);
+    /// lctree.link(nodes[2], nodes[1]);
+    /// lctree.link(nodes[3], nodes[2]);
+    ///
+    /// // route 3 more units from node 2 to node 3:
+    /// assert!(lctree.path_add(nodes[2], nodes[3], 3.0));
+    /// let busiest = lctree.path(nodes[0], nodes[3]);
+    /// assert_eq!((busiest.idx, busiest.weight), (nodes[3], 6.0));
+    /// assert_eq!(lctree.path(nodes[0], nodes[1]).weight, 5.0);
+    /// ```
+    #[allow(clippy::must_use_candidate)]
+    #[cfg(feature = "lazy-updates")]
+    pub fn path_add(&mut self, v: usize, w: usize, delta: f64) -> bool
+    where
+        P: PathAdd,
+    {
+        if let Err(err) = self.check_update(delta) {
+            panic!("path_add: {err}");
+        }
+        self.add_to_path(v, w, delta)
+    }
+
+    /// Same as [`LinkCutTree::path_add`], but returns an error instead of panicking
+    /// (in which case no weight is changed).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`UpdateError`] if a registered aggregate cannot be updated lazily,
+    /// if the weight validation rejects `delta`, or if a node does not exist.
+    #[cfg(feature = "lazy-updates")]
+    pub fn try_path_add(&mut self, v: usize, w: usize, delta: f64) -> Result<bool, UpdateError>
+    where
+        P: PathAdd,
+    {
+        self.check_node(v)?;
+        self.check_node(w)?;
+        self.check_update(delta)?;
+        Ok(self.add_to_path(v, w, delta))
+    }
+
+    #[cfg(feature = "lazy-updates")]
+    fn add_to_path(&mut self, v: usize, w: usize, delta: f64) -> bool
+    where
+        P: PathAdd,
+    {
+        self.evert(v);
+        self.access(w);
+        let connected = self.forest.parent_of(v).is_some() || v == w;
+        if connected {
+            self.forest.add_to_path(w, delta);
+        }
+        self.record(Operation::PathAdd(v, w, delta));
+        connected
+    }
+
+    /// Sets the weights of all nodes on the path between `v` and `w` to `weight`
+    /// in `O(logn)` amortized time, or returns `false` if they are not connected.
+    ///
+    /// Requires the `lazy-updates` feature.
+    ///
+    /// As for `path_add`, the assignment is left pending at the root of the exposed path,
+    /// so the path aggregate has to implement [`PathAssign`] (which the built-in aggregates do).
+    /// The additions of a later `subtree_add` or `path_add` apply on top of the assigned weights.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an aggregate was registered without its lazy updates
+    /// (see [`LinkCutTree::register_updatable_aggregate`]), or if the weight validation
+    /// is enabled and the weight is NaN or infinite (see [`LinkCutTree::try_path_assign`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// // the links of a network, weighted by their reserved bandwidth:
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let nodes = lctree.extend_forest([0.0; 5]);
+    /// for i in 1..nodes.len() {
+    ///     lctree.link(nodes[i], nodes[i - 1]);
+    /// }
+    ///
+    /// // reserve 10 units from node 1 to node 3, then release node 2:
+    /// assert!(lctree.path_assign(nodes[1], nodes[3], 10.0));
+    /// assert!(lctree.path_assign(nodes[2], nodes[2], 0.0));
+    /// assert_eq!(lctree.path(nodes[0], nodes[4]).sum, 20.0);
+    /// ```
+    #[allow(clippy::must_use_candidate)]
+    #[cfg(feature = "lazy-updates")]
+    pub fn path_assign(&mut self, v: usize, w: usize, weight: f64) -> bool
+    where
+        P: PathAssign,
+    {
+        if let Err(err) = self.check_update(weight) {
+            panic!("path_assign: {err}");
+        }
+        self.assign_to_path(v, w, weight)
+    }
+
+    /// Same as [`LinkCutTree::path_assign`], but returns an error instead of panicking
+    /// (in which case no weight is changed).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`UpdateError`] if a registered aggregate cannot be updated lazily,
+    /// if the weight validation rejects the weight, or if a node does not exist.
+    #[cfg(feature = "lazy-updates")]
+    pub fn try_path_assign(&mut self, v: usize, w: usize, weight: f64) -> Result<bool, UpdateError>
+    where
+        P: PathAssign,
+    {
+        self.check_node(v)?;
+        self.check_node(w)?;
+        self.check_update(weight)?;
+        Ok(self.assign_to_path(v, w, weight))
+    }
+
+    #[cfg(feature = "lazy-updates")]
+    fn assign_to_path(&mut self, v: usize, w: usize, weight: f64) -> bool
+    where
+        P: PathAssign,
+    {
+        self.evert(v);
+        self.access(w);
+        let connected = self.forest.parent_of(v).is_some() || v == w;
+        if connected {
+            self.forest.assign_path(w, weight);
+        }
+        self.record(Operation::PathAssign(v, w, weight));
+        connected
+    }
+
+    /// Sets the weight of node `v` and repairs the path aggregates in `O(logn)` amortized time
+    /// (`v` is accessed first, so that only its own aggregate has to be recomputed).
+    /// The weight replaces any additions made by `subtree_add` to `v`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight validation is enabled and the weight is NaN or infinite.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    /// let a = lctree.make_tree(1.0);
+    /// let b = lctree.make_tree(2.0);
+    /// lctree.link(a, b);
+    /// assert_eq!(lctree.path(a, b).idx, b);
+    ///
+    /// lctree.set_weight(a, 3.0);
+    /// assert_eq!(lctree.path(a, b).idx, a);
+    /// ```
+    pub fn set_weight(&mut self, v: usize, weight: f64) {
+        if self.validate_weights {
+            if let Err(err) = WeightError::check(weight) {
+                panic!("set_weight: {err}");
+            }
+        }
+        self.update_weight(v, weight);
+        self.record(Operation::ReplaceWeight(v, weight));
+    }
+
+    /// Replaces the weight of node `v` and returns its previous weight
+    /// (including the additions made by `subtree_add`) in `O(logn)` amortized time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight validation is enabled and the weight is NaN or infinite.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let a = lctree.make_tree(1.0);
+    /// let b = lctree.make_tree(2.0);
+    /// lctree.link(a, b);
+    /// assert_eq!(lctree.replace_weight(b, 5.0), 2.0);
+    /// assert_eq!(lctree.path(a, b).sum, 6.0);
+    /// ```
+    #[allow(clippy::must_use_candidate)]
+    pub fn replace_weight(&mut self, v: usize, weight: f64) -> f64 {
+        if self.validate_weights {
+            if let Err(err) = WeightError::check(weight) {
+                panic!("replace_weight: {err}");
+            }
+        }
+        self.access(v);
+        let old = self.forest.weight_of(v);
+        self.forest.set_weight(v, weight);
+        self.record(Operation::ReplaceWeight(v, weight));
+        old
+    }
+
+    /// Replaces the weights of many nodes at once, as if `replace_weight` was called for each
+    /// `(node, weight)` pair in order. A few updates are applied one by one in `O(logn)` amortized
+    /// time each, while larger batches replace the weights first and then recompute all
+    /// the path aggregates in a single `O(n)` pass (see [`LinkCutTree::rebuild_aggregates`]).
+    ///
+    /// The whole batch is checked before any weight is replaced, so nothing changes
+    /// (and nothing is recorded) if it fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an id does not refer to a node of the forest (see [`LinkCutTree::try_set_weights`]),
+    /// or if the weight validation is enabled and a weight is NaN or infinite.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    /// let nodes = lctree.extend_forest([1.0, 2.0, 3.0]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    /// lctree.set_weights(&[(nodes[0], 5.0), (nodes[2], 0.0)]);
+    /// assert_eq!(lctree.path(nodes[0], nodes[2]).idx, nodes[0]);
+    /// ```
+    pub fn set_weights(&mut self, weights: &[(usize, f64)]) {
+        for &(v, weight) in weights {
+            if let Err(err) = self.check_node(v) {
+                panic!("set_weights: {err}");
+            }
+            if self.validate_weights {
+                if let Err(err) = WeightError::check(weight) {
+                    panic!("set_weights: {err}");
+                }
+            }
+        }
+        // a pass over the forest pays off once the accesses cost more than it:
+        let log_len = (usize::BITS - self.len().leading_zeros()) as usize;
+        if weights.len() * log_len < self.len() {
+            for &(v, weight) in weights {
+                self.update_weight(v, weight);
+            }
+        } else {
+            self.forest.set_weights(weights);
+        }
+        for &(v, weight) in weights {
+            self.record(Operation::ReplaceWeight(v, weight));
+        }
+    }
+
+    /// Same as [`LinkCutTree::set_weights`], but returns an error instead of panicking
+    /// if a node does not exist (in which case no weight is replaced).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn try_set_weights(&mut self, weights: &[(usize, f64)]) -> Result<(), InvalidNode> {
+        for &(v, _) in weights {
+            self.check_node(v)?;
+        }
+        self.set_weights(weights);
+        Ok(())
+    }
+
+    /// Enables (or disables) tracking the neighbors of every node,
+    /// which is needed to traverse the represented trees (see [`LinkCutTree::dfs`]).
+    /// Enabling the tracking collects the current edges of the forest in `O(n)`,
+    /// after which `link` and `cut` maintain the neighbors of their endpoints
+    /// (`cut` takes time proportional to the degrees of its endpoints).
+    pub fn set_adjacency_tracking(&mut self, enabled: bool) {
+        self.adjacency = if enabled {
+            Some(Adjacency::from_edges(self.edges()))
+        } else {
+            None
+        };
+    }
+
+    /// Returns `true` if the neighbors of the nodes are tracked.
+    #[must_use]
+    pub fn adjacency_tracking(&self) -> bool {
+        self.adjacency.is_some()
+    }
+
+    fn adjacency(&self, caller: &str) -> &Adjacency {
+        match &self.adjacency {
+            Some(adjacency) => adjacency,
+            None => panic!("{caller}: adjacency tracking is disabled"),
+        }
+    }
+
+    /// Returns an iterator over the neighbors of `v` (the other endpoints of its edges)
+    /// in no particular order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the adjacency tracking is disabled (see [`LinkCutTree::set_adjacency_tracking`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree, LinkCutTreeBuilder};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTreeBuilder::new()
+    ///     .adjacency_tracking(true)
+    ///     .build();
+    /// let nodes = lctree.extend_forest([0.0; 4]);
+    /// lctree.link_all(&[(nodes[1], nodes[0]), (nodes[2], nodes[0]), (nodes[3], nodes[2])]);
+    ///
+    /// let mut neighbors = lctree.neighbors(nodes[0]).collect::<Vec<_>>();
+    /// neighbors.sort_unstable();
+    /// assert_eq!(neighbors, vec![nodes[1], nodes[2]]);
+    /// ```
+    pub fn neighbors(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        self.adjacency("neighbors").neighbors(v).iter().copied()
+    }
+
+    /// Returns an iterator over the nodes of the tree containing `v` in depth-first order
+    /// of the represented tree, starting from `v` (i.e. as if the tree was rooted at `v`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the adjacency tracking is disabled (see [`LinkCutTree::set_adjacency_tracking`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree, LinkCutTreeBuilder};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTreeBuilder::new()
+    ///     .adjacency_tracking(true)
+    ///     .build();
+    /// // We form a link-cut tree for the following tree:
+    /// //     a
+    /// //    / \
+    /// //   b   d
+    /// //   |
+    /// //   c
+    /// let a = lctree.make_tree(0.0);
+    /// let b = lctree.make_tree(0.0);
+    /// let c = lctree.make_tree(0.0);
+    /// let d = lctree.make_tree(0.0);
+    /// lctree.link(b, a);
+    /// lctree.link(c, b);
+    /// lctree.link(d, a);
+    ///
+    /// assert_eq!(lctree.dfs(a).collect::<Vec<_>>(), vec![a, b, c, d]);
+    /// assert_eq!(lctree.dfs(c).collect::<Vec<_>>(), vec![c, b, a, d]);
+    /// ```
+    pub fn dfs(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        self.adjacency("dfs").dfs(v)
+    }
+
+    /// Returns an iterator over the nodes of the tree containing `root` in breadth-first
+    /// (level) order when it is rooted at `root`, yielding each node together with its depth.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the adjacency tracking is disabled (see [`LinkCutTree::set_adjacency_tracking`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree, LinkCutTreeBuilder};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTreeBuilder::new()
+    ///     .adjacency_tracking(true)
+    ///     .build();
+    /// // We form a link-cut tree for the following tree:
+    /// //     a
+    /// //    / \
+    /// //   b   d
+    /// //   |
+    /// //   c
+    /// let a = lctree.make_tree(0.0);
+    /// let b = lctree.make_tree(0.0);
+    /// let c = lctree.make_tree(0.0);
+    /// let d = lctree.make_tree(0.0);
+    /// lctree.link(b, a);
+    /// lctree.link(c, b);
+    /// lctree.link(d, a);
+    ///
+    /// let levels = lctree.bfs(a).collect::<Vec<_>>();
+    /// assert_eq!(levels, vec![(a, 0), (b, 1), (d, 1), (c, 2)]);
+    /// ```
+    pub fn bfs(&self, root: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.adjacency("bfs").bfs(root)
+    }
+
+    /// Returns the Euler tour of the tree containing `root` when it is rooted at `root`:
+    /// every node is entered before its subtree is visited (in depth-first order)
+    /// and exited after it, so the tour contains `2k` events for a tree of `k` nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the adjacency tracking is disabled (see [`LinkCutTree::set_adjacency_tracking`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree, LinkCutTreeBuilder, TourEvent};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTreeBuilder::new()
+    ///     .adjacency_tracking(true)
+    ///     .build();
+    /// let a = lctree.make_tree(0.0);
+    /// let b = lctree.make_tree(0.0);
+    /// let c = lctree.make_tree(0.0);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    ///
+    /// use TourEvent::{Enter, Exit};
+    /// assert_eq!(
+    ///     lctree.euler_tour(a),
+    ///     vec![Enter(a), Enter(b), Exit(b), Enter(c), Exit(c), Exit(a)]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn euler_tour(&self, root: usize) -> Vec<TourEvent> {
+        self.adjacency("euler_tour").euler_tour(root)
+    }
+
+    /// Returns the number of nodes in the tree containing `v` in `O(logn)` amortized time.
+    ///
+    /// Requires the `component-sizes` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest([0.0; 4]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[2], nodes[1]);
+    /// assert_eq!(lctree.component_size(nodes[0]), 3);
+    /// assert_eq!(lctree.component_size(nodes[3]), 1);
+    /// ```
+    #[cfg(feature = "component-sizes")]
+    pub fn component_size(&mut self, v: usize) -> usize {
+        // the accessed node is the root of the splay tree of the root path,
+        // which all the other splay trees of the tree hang off:
+        self.access(v);
+        self.forest.tree_size_of(v)
+    }
+
+    /// Returns the number of nodes in the subtree of `v` when its tree is rooted at `root`
+    /// in `O(logn)` amortized time (or `None` if `v` and `root` are not connected).
+    /// The tree stays rooted at `root` afterwards.
+    ///
+    /// Requires the `component-sizes` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// //     a
+    /// //    / \
+    /// //   b   c
+    /// //  / \
+    /// // d   e
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c, d, e) = (0, 1, 2, 3, 4);
+    /// lctree.extend_forest([0.0; 6]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    /// lctree.link(d, b);
+    /// lctree.link(e, b);
+    ///
+    /// assert_eq!(lctree.subtree_size(b, a), Some(3));
+    /// assert_eq!(lctree.subtree_size(a, a), Some(5));
+    /// assert_eq!(lctree.subtree_size(a, d), Some(2)); // rooted at d, a is below b
+    /// assert_eq!(lctree.subtree_size(a, 5), None);
+    /// ```
+    #[cfg(feature = "component-sizes")]
+    pub fn subtree_size(&mut self, v: usize, root: usize) -> Option<usize> {
+        self.evert(root);
+        self.access(v);
+        if self.forest.parent_of(root).is_none() && root != v {
+            return None;
+        }
+        // the path from the root to v is in the splay tree of v, and the subtrees of v
+        // hang off v by path-parent pointers:
+        Some(1 + self.forest.virtual_size_of(v))
+    }
+
+    /// Returns the id of the tree containing `v` in `O(logn)` amortized time.
+    /// The id is the id of a node of the tree (its representative), which stays the same
+    /// until the tree is linked with another tree or cut, unlike its root (see `findroot`),
+    /// which changes whenever the tree is rerooted by the queries.
+    /// When two trees are linked, the merged tree keeps the id of the larger tree, and when a tree
+    /// is cut, the part containing its representative keeps its id.
+    ///
+    /// Requires the `component-sizes` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest([0.0; 4]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    /// let id = lctree.component_id(nodes[0]);
+    /// assert_eq!(lctree.component_id(nodes[2]), id);
+    ///
+    /// // queries do not change the id (but they may change the root):
+    /// lctree.path(nodes[2], nodes[0]);
+    /// assert_eq!(lctree.component_id(nodes[1]), id);
+    ///
+    /// // linking a smaller tree does not change the id:
+    /// lctree.link(nodes[3], nodes[0]);
+    /// assert_eq!(lctree.component_id(nodes[3]), id);
+    /// ```
+    #[cfg(feature = "component-sizes")]
+    pub fn component_id(&mut self, v: usize) -> usize {
+        // the accessed node sums up the ids of the anchors of its tree, and there is only one:
+        self.access(v);
+        self.forest.tree_anchor_of(v) - 1
+    }
+
+    /// Enables (or disables) tracking the number of trees of every size, which is needed
+    /// to find the largest tree in the forest (see [`LinkCutTree::max_component_size`]).
+    /// Enabling the tracking finds the sizes of the current trees in `O(nlogn)`,
+    /// after which `link`, `cut`, `make_tree` and `remove_tree` maintain them in `O(logn)`.
+    ///
+    /// Requires the `component-sizes` feature.
+    #[cfg(feature = "component-sizes")]
+    pub fn set_component_tracking(&mut self, enabled: bool) {
+        self.components = if enabled {
+            let ids: Vec<usize> = self.forest.node_ids().collect();
+            let mut sizes = vec![0; ids.last().map_or(0, |&idx| idx + 1)];
+            for v in ids {
+                sizes[self.find_root(v)] += 1;
+            }
+            Some(ComponentSizes::from_sizes(
+                sizes.into_iter().filter(|&size| size > 0),
+            ))
+        } else {
+            None
+        };
+    }
+
+    /// Returns `true` if the sizes of the trees are tracked.
+    ///
+    /// Requires the `component-sizes` feature.
+    #[must_use]
+    #[cfg(feature = "component-sizes")]
+    pub fn component_tracking(&self) -> bool {
+        self.components.is_some()
+    }
+
+    /// Enables (or disables) memoizing the results of `path` and `connected`, so that repeating
+    /// a query skips the splaying until the forest changes (by a link, cut, new or removed node
+    /// or weight update). Disabled by default.
+    ///
+    /// This pays off when the same pairs of nodes are queried many times between the changes,
+    /// at the cost of a hash map lookup per query and memory for the distinct queries
+    /// (at most 65536 results of each kind, after which the cache starts over).
+    /// Note that a memoized `path` does not reroot the tree at `v` like an actual one does.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// lctree.set_query_cache(true);
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    ///
+    /// lctree.set_instrumentation(true);
+    /// assert_eq!(lctree.path(alice, bob).sum, 3.0);
+    /// assert_eq!(lctree.path(alice, bob).sum, 3.0); // memoized
+    /// assert_eq!(lctree.cost_counters().unwrap().accesses, 2); // by the first query only
+    ///
+    /// lctree.replace_weight(bob, 5.0); // invalidates the results
+    /// assert_eq!(lctree.path(alice, bob).sum, 6.0);
+    /// ```
+    pub fn set_query_cache(&mut self, enabled: bool) {
+        self.cache = if enabled {
+            Some(QueryCache::new())
+        } else {
+            None
+        };
+    }
+
+    /// Returns `true` if the results of `path` and `connected` are memoized.
+    #[must_use]
+    pub fn query_cache(&self) -> bool {
+        self.cache.is_some()
+    }
+
+    /// Returns the number of nodes in the largest tree of the forest (0 if the forest is empty)
+    /// in `O(logn)` time.
+    ///
+    /// Requires the `component-sizes` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component tracking is disabled (see [`LinkCutTree::set_component_tracking`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindMax, LinkCutTree, LinkCutTreeBuilder};
+    ///
+    /// let mut lctree: LinkCutTree<FindMax> = LinkCutTreeBuilder::new()
+    ///     .component_tracking(true)
+    ///     .build();
+    /// let nodes = lctree.extend_forest([0.0; 5]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[2], nodes[3]);
+    /// lctree.link(nodes[3], nodes[4]);
+    /// assert_eq!(lctree.max_component_size(), 3);
+    ///
+    /// // the histogram lists the sizes of the trees along with the number of trees of each size:
+    /// assert_eq!(lctree.component_size_histogram().collect::<Vec<_>>(), vec![(2, 1), (3, 1)]);
+    ///
+    /// lctree.cut(nodes[3], nodes[4]);
+    /// assert_eq!(lctree.max_component_size(), 2);
+    /// ```
+    #[must_use]
+    #[cfg(feature = "component-sizes")]
+    pub fn max_component_size(&self) -> usize {
+        self.components("max_component_size").max()
+    }
+
+    /// Returns an iterator over the sizes of the trees in increasing order,
+    /// along with the number of trees of each size.
+    ///
+    /// Requires the `component-sizes` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component tracking is disabled (see [`LinkCutTree::set_component_tracking`]).
+    #[cfg(feature = "component-sizes")]
+    pub fn component_size_histogram(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.components("component_size_histogram").histogram()
+    }
+
+    /// Finds the root of the tree that the query node is in.
+    pub fn findroot(&mut self, v: usize) -> usize {
+        let root = self.find_root(v);
+        self.record(Operation::FindRoot(v));
+        root
+    }
+
+    /// Same as [`LinkCutTree::findroot`], but returns an error instead of panicking
+    /// if a node does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn try_findroot(&mut self, v: usize) -> Result<usize, InvalidNode> {
+        self.check_node(v)?;
+        Ok(self.findroot(v))
+    }
+
+    /// Finds the root of the tree containing `v` like `findroot`, but without restructuring
+    /// the splay trees, so it only needs a shared reference (e.g. behind an `Arc` or a read lock).
+    /// This walks up and down the splay trees in time proportional to their depths,
+    /// which is `O(n)` in the worst case, since the walk is not amortized by splaying.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest([0.0; 4]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    ///
+    /// let lctree = &lctree;
+    /// assert_eq!(lctree.root_of(nodes[0]), nodes[2]);
+    /// assert_eq!(lctree.root_of(nodes[3]), nodes[3]);
+    /// ```
+    #[must_use]
+    pub fn root_of(&self, v: usize) -> usize {
+        self.forest.root_of(v)
+    }
+
+    /// Makes `v` the root of its tree (an evert) in `O(logn)` amortized time,
+    /// which changes the parents and the subtrees of the rooted queries (such as `lca` and `ancestor`).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// // a path rooted at a: a - b - c
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c) = (0, 1, 2);
+    /// lctree.extend_forest(&[0.0; 3]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, b);
+    /// assert_eq!(lctree.findroot(c), a);
+    ///
+    /// lctree.reroot(c);
+    /// assert_eq!(lctree.findroot(a), c);
+    /// assert_eq!(lctree.parent(a), Some(b));
+    /// ```
+    pub fn reroot(&mut self, v: usize) {
+        self.evert(v);
+        self.record(Operation::Reroot(v));
+    }
+
+    /// Finds the lowest common ancestor of two nodes under the current root of their tree
+    /// (or returns `None` if they are not connected).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// // a is the root of the tree:
+    /// //     a
+    /// //    / \
+    /// //   b   c
+    /// //  / \
+    /// // d   e
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c, d, e) = (0, 1, 2, 3, 4);
+    /// lctree.extend_forest(&[0.0; 5]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    /// lctree.link(d, b);
+    /// lctree.link(e, b);
+    ///
+    /// assert_eq!(lctree.lca(d, e), Some(b));
+    /// assert_eq!(lctree.lca(d, c), Some(a));
+    /// assert_eq!(lctree.lca(b, e), Some(b));
+    /// ```
+    pub fn lca(&mut self, v: usize, w: usize) -> Option<usize> {
+        self.lca_many(&[v, w])
+    }
+
+    /// Finds the lowest common ancestor of a set of nodes under the current root of their tree
+    /// with `O(k)` accesses (or returns `None` if the set is empty or not connected).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// // a is the root of the tree:
+    /// //       a
+    /// //      / \
+    /// //     b   c
+    /// //    / \   \
+    /// //   d   e   f
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c, d, e, f) = (0, 1, 2, 3, 4, 5);
+    /// lctree.extend_forest(&[0.0; 6]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    /// lctree.link(d, b);
+    /// lctree.link(e, b);
+    /// lctree.link(f, c);
+    ///
+    /// assert_eq!(lctree.lca_many(&[d, e]), Some(b));
+    /// assert_eq!(lctree.lca_many(&[d, e, f]), Some(a));
+    /// assert_eq!(lctree.lca_many(&[f]), Some(f));
+    /// assert_eq!(lctree.lca_many(&[]), None);
+    /// ```
+    pub fn lca_many(&mut self, nodes: &[usize]) -> Option<usize> {
+        let (&first, rest) = nodes.split_first()?;
+        let root = self.find_root(first);
+        let mut lca = first;
+        for &v in rest {
+            if self.find_root(v) != root {
+                return None;
+            }
+            lca = self.connected_lca(lca, v);
+        }
+        Some(lca)
+    }
+
+    /// Finds the `k`-th ancestor of `v` under the current root of its tree (i.e. the node `k` steps
+    /// up the path from `v` to the root) in `O(logn)` amortized time,
+    /// or returns `None` if `v` is less than `k` steps away from the root.
+    ///
+    /// Requires the `depth-queries` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// // a path rooted at a: a - b - c - d
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c, d) = (0, 1, 2, 3);
+    /// lctree.extend_forest(&[0.0; 4]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, b);
+    /// lctree.link(d, c);
+    ///
+    /// assert_eq!(lctree.ancestor(d, 0), Some(d));
+    /// assert_eq!(lctree.ancestor(d, 1), Some(c));
+    /// assert_eq!(lctree.ancestor(d, 3), Some(a));
+    /// assert_eq!(lctree.ancestor(d, 4), None);
+    /// ```
+    #[cfg(feature = "depth-queries")]
+    pub fn ancestor(&mut self, v: usize, k: usize) -> Option<usize> {
+        let depth = self.depth_of(v).checked_sub(k)?;
+        Some(self.ancestor_at_depth(v, depth))
+    }
+
+    /// Returns the parent of `v` under the current root of its tree (see `findroot`)
+    /// in `O(logn)` amortized time, or `None` if `v` is the root.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// // a is the root of the tree:
+    /// //     a
+    /// //    / \
+    /// //   b   c
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c) = (0, 1, 2);
+    /// lctree.extend_forest(&[0.0; 3]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    ///
+    /// assert_eq!(lctree.parent(b), Some(a));
+    /// assert_eq!(lctree.parent(a), None);
+    ///
+    /// lctree.reroot(b);
+    /// assert_eq!(lctree.parent(a), Some(b));
+    /// ```
+    pub fn parent(&mut self, v: usize) -> Option<usize> {
+        self.access(v);
+        // the parent is the last node of the path from the root to the parent of v:
+        let mut node = self.forest.left_of(v)?;
+        loop {
+            self.forest.normalize(node);
+            match self.forest.right_of(node) {
+                Some(right) => node = right,
+                None => break,
+            }
+        }
+        self.forest.splay(node); // fast access to the parent next time
+        Some(node)
+    }
+
+    /// Returns the number of edges between `v` and the current root of its tree (see `findroot`)
+    /// in `O(logn)` amortized time.
+    ///
+    /// Requires the `depth-queries` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// // a path rooted at a: a - b - c
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c) = (0, 1, 2);
+    /// lctree.extend_forest(&[0.0; 3]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, b);
+    ///
+    /// assert_eq!(lctree.depth(a), 0);
+    /// assert_eq!(lctree.depth(c), 2);
+    /// ```
+    #[cfg(feature = "depth-queries")]
+    pub fn depth(&mut self, v: usize) -> usize {
+        self.depth_of(v)
+    }
+
+    /// Returns the number of edges on the path between `v` and `w` in `O(logn)` amortized time
+    /// (or `None` if they are not connected).
+    /// The tree is rerooted at `v`, as by `path(v, w)`.
+    /// For the weighted length of the path, aggregate the edge weights
+    /// with a [`WeightedLinkCutTree`](crate::WeightedLinkCutTree) instead.
+    ///
+    /// Requires the `depth-queries` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// //     a
+    /// //    / \
+    /// //   b   c
+    /// //  /
+    /// // d
+    /// let mut lctree = LinkCutTree::default();
+    /// let (a, b, c, d, e) = (0, 1, 2, 3, 4);
+    /// lctree.extend_forest(&[0.0; 5]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    /// lctree.link(d, b);
+    ///
+    /// assert_eq!(lctree.distance(d, c), Some(3));
+    /// assert_eq!(lctree.distance(b, b), Some(0));
+    /// assert_eq!(lctree.distance(d, e), None);
+    /// ```
+    #[cfg(feature = "depth-queries")]
+    pub fn distance(&mut self, v: usize, w: usize) -> Option<usize> {
+        self.evert(v);
+        self.access(w);
+        if self.forest.parent_of(v).is_none() && v != w {
+            return None;
+        }
+        // the path from v to w is in the splay tree of w, so w is at depth `distance`:
+        Some(self.depth_of(w))
+    }
+
+    // Finds the lowest common ancestor of two connected nodes: the path from `w` to the root
+    // joins the path from `v` to the root at their lca.
+    pub(crate) fn connected_lca(&mut self, v: usize, w: usize) -> usize {
+        self.access(v);
+        self.access(w)
+    }
+
+    // The depth of `v` under the current root (the root has depth 0).
+    #[cfg(feature = "depth-queries")]
+    pub(crate) fn depth_of(&mut self, v: usize) -> usize {
+        self.access(v);
+        // the left subtree holds the path from the root to the parent of v:
+        self.forest
+            .left_of(v)
+            .map_or(0, |left| self.forest.size_of(left))
+    }
+
+    // Finds the ancestor of `v` at the given depth (at most the depth of `v`) under the current root.
+    #[cfg(feature = "depth-queries")]
+    pub(crate) fn ancestor_at_depth(&mut self, v: usize, depth: usize) -> usize {
+        self.access(v);
+        // find the node at position `depth` of the path from the root to v:
+        let mut node = v;
+        let mut position = depth;
+        loop {
+            self.forest.normalize(node);
+            let left = self.forest.left_of(node);
+            let left_size = left.map_or(0, |left| self.forest.size_of(left));
+            match position.cmp(&left_size) {
+                Ordering::Less => node = left.unwrap(),
+                Ordering::Equal => break,
+                Ordering::Greater => {
+                    position -= left_size + 1;
+                    node = self.forest.right_of(node).unwrap();
+                }
+            }
+        }
+        self.forest.splay(node); // fast access to the ancestor next time
+        node
+    }
+
+    // Aggregates the weights on the path from `v` up to its ancestor `ancestor`
+    // under the current root, excluding the ancestor.
+    #[cfg(feature = "depth-queries")]
+    pub(crate) fn path_below(&mut self, ancestor: usize, v: usize) -> P {
+        self.access(v);
+        self.forest.splay(ancestor);
+        let below = self.forest.right_of(ancestor).unwrap();
+        self.forest.aggregated_path_of(below)
+    }
+
+    pub(crate) fn find_root(&mut self, v: usize) -> usize {
+        self.access(v);
+        let mut root = v;
+        while let Some(left) = self.forest.left_of(root) {
+            root = left;
+            self.forest.normalize(root);
+        }
+        self.forest.splay(root); // fast access to the root next time
+        root
+    }
+
+    /// Starts recording the operations performed on the link-cut tree to `writer`,
+    /// one operation per line in a human-readable format (see [`Operation`]).
+    /// The recorded script can be replayed with [`LinkCutTree::apply_script`], which makes it
+    /// easy to share and reproduce failing sequences of operations.
+    ///
+    /// The operations that take closures (e.g. `map_weights`) or that do not change the forest
+    /// are not recorded, so a script should be recorded from an empty link-cut tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let path = std::env::temp_dir().join("lctree_record_script.txt");
+    /// let mut lctree = LinkCutTree::default();
+    /// lctree.record_script(std::fs::File::create(&path).unwrap());
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    /// lctree.stop_recording().unwrap();
+    ///
+    /// let script = std::fs::read_to_string(&path).unwrap();
+    /// assert_eq!(script, "make_tree 1\nmake_tree 2\nlink 0 1\n");
+    /// ```
+    pub fn record_script<W: Write + Send + Sync + 'static>(&mut self, writer: W) {
+        self.recorder = Some(Recorder::new(Box::new(writer)));
+    }
+
+    /// Starts appending every change of the forest to `writer` as a write-ahead log,
+    /// so that the forest can be reconstructed after a crash with [`LinkCutTree::recover`]
+    /// without taking full snapshots. The log uses the script format, but it skips the queries,
+    /// includes the weights set by `map_weights`, and logs the root of the tree before
+    /// every `subtree_add` (as `reroot`). It starts with the current id reuse settings,
+    /// which decide the ids of the nodes created later, so that the log can be recovered
+    /// into a new link-cut tree. The writer is flushed after every change.
+    /// This replaces the script recording (if any), and it is stopped by `stop_recording`.
+    ///
+    /// A log should be started from an empty link-cut tree (or after a snapshot of it is saved).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let path = std::env::temp_dir().join("lctree_write_ahead_log.txt");
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// lctree.write_ahead_log(std::fs::File::create(&path).unwrap());
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    /// assert_eq!(lctree.path(alice, bob).sum, 3.0); // not logged
+    /// lctree.replace_weight(bob, 3.0);
+    /// drop(lctree); // crash
+    ///
+    /// let log = std::fs::read_to_string(&path).unwrap();
+    /// assert_eq!(
+    ///     log,
+    ///     "id_reuse true\nid_reuse_policy lifo\n\
+    ///      make_tree 1\nmake_tree 2\nlink 0 1\nreplace_weight 1 3\n"
+    /// );
+    ///
+    /// let mut recovered: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// assert_eq!(recovered.recover(log.as_bytes()).unwrap(), 6);
+    /// assert_eq!(recovered.path(alice, bob).sum, 4.0);
+    /// ```
+    pub fn write_ahead_log<W: Write + Send + Sync + 'static>(&mut self, writer: W) {
+        self.recorder = Some(Recorder::log(Box::new(writer)));
+        self.record(Operation::IdReuse(self.id_reuse()));
+        self.record(Operation::IdReusePolicy(self.id_reuse_policy()));
+    }
+
+    // The weight of `v`, including the pending additions (which are pushed down by the access).
+    #[cfg(feature = "depth-queries")]
+    pub(crate) fn weight_of(&mut self, v: usize) -> f64 {
+        self.access(v);
+        self.forest.weight_of(v)
+    }
+
+    // Replaces the weight of `v` (without validating or recording it), which is moved to the root
+    // of its splay tree first so that only its own aggregate has to be recomputed.
+    pub(crate) fn update_weight(&mut self, v: usize, weight: f64) {
+        self.access(v);
+        self.forest.set_weight(v, weight);
+    }
+
+    // Lists the weights of all nodes (indexed by id) without applying the pending subtree additions.
+    pub(crate) fn effective_weights(&self) -> Vec<f64> {
+        self.forest.effective_weights()
+    }
+
+    // Lists the ids and weights of all nodes, applying the pending subtree additions first.
+    pub(crate) fn weights(&mut self) -> Vec<(usize, f64)> {
+        self.forest.push_lazy_updates();
+        self.forest
+            .node_ids()
+            .map(|idx| (idx, self.forest.weight_of(idx)))
+            .collect()
+    }
+
+    /// Returns an iterator over the edges currently present in the forest.
+    /// Each edge is reported once as a `(child, parent)` pair, where `parent` is the parent of
+    /// `child` when its tree is rooted at its current root (see [`LinkCutTree::findroot`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let clay = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    /// lctree.link(bob, clay);
+    ///
+    /// let mut edges = lctree
+    ///     .edges()
+    ///     .map(|(v, w)| (v.min(w), v.max(w)))
+    ///     .collect::<Vec<_>>();
+    /// edges.sort();
+    /// assert_eq!(edges, vec![(alice, bob), (bob, clay)]);
+    /// ```
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.forest.edges().into_iter()
+    }
+
+    /// Lists the preferred paths that the forest is currently decomposed into
+    /// (each of them is stored as an auxiliary splay tree).
+    /// Every path is listed from its top-most node (the closest to the root) to its bottom-most node,
+    /// and the paths are ordered by the ids of their splay roots.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let clay = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    /// lctree.link(clay, bob);
+    ///
+    /// // accessing a node exposes the path from the root to it:
+    /// assert_eq!(lctree.findroot(alice), bob);
+    /// let paths = lctree.preferred_paths();
+    /// assert!(paths.contains(&vec![bob, alice]));
+    /// assert!(paths.contains(&vec![clay]));
+    /// ```
+    #[must_use]
+    pub fn preferred_paths(&self) -> Vec<Vec<usize>> {
+        self.forest.preferred_paths()
+    }
+
+    /// Reports the shapes of the auxiliary splay trees that represent the forest:
+    /// the depth distribution of the nodes, the sizes of the preferred paths,
+    /// and the maximum depth of a splayed node encountered so far.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// let clay = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    ///
+    /// let stats = lctree.splay_stats();
+    /// assert_eq!(stats.depth_histogram.iter().sum::<usize>(), 3);
+    /// assert_eq!(stats.path_sizes.iter().sum::<usize>(), 3);
+    /// ```
+    #[must_use]
+    pub fn splay_stats(&self) -> SplayStats {
+        self.forest.splay_stats()
+    }
+
+    /// Returns the auxiliary splay tree that stores the preferred path of `v` (see [`SplayTree`]),
+    /// to inspect the internal representation of the forest.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest([0.0; 3]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    ///
+    /// // accessing a node exposes the path from the root to it, splayed at the node:
+    /// assert_eq!(lctree.findroot(nodes[1]), nodes[2]);
+    /// let tree = lctree.splay_tree(nodes[1]);
+    /// assert_eq!(tree.root, nodes[2]); // findroot splays the root
+    /// assert_eq!(tree.nodes, vec![(nodes[2], 0), (nodes[1], 1)]);
+    /// assert_eq!(tree.path_parent, None);
+    /// println!("{tree}");
+    /// ```
+    #[must_use]
+    pub fn splay_tree(&self, v: usize) -> SplayTree {
+        self.forest.splay_tree(v)
+    }
+
+    /// Enables (or disables) collecting the cost counters of the operations.
+    /// Enabling the instrumentation resets the counters to zero.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// assert!(lctree.cost_counters().is_none());
+    ///
+    /// lctree.set_instrumentation(true);
+    /// lctree.link(alice, bob);
+    /// let counters = lctree.cost_counters().unwrap();
+    /// assert_eq!(counters.accesses, 2); // link(v, w) accesses both v and w
+    /// ```
+    pub fn set_instrumentation(&mut self, enabled: bool) {
+        self.forest.set_instrumentation(enabled);
+    }
+
+    /// Returns the cost counters collected since the instrumentation was enabled
+    /// (or `None` if the instrumentation is disabled).
+    #[must_use]
+    pub fn cost_counters(&self) -> Option<CostCounters> {
+        self.forest.cost_counters()
+    }
+
+    /// Computes the potential function used in the amortized analysis of link-cut trees:
+    /// the sum of `log2(size(v))` over all nodes `v`, where `size(v)` is the number of
+    /// descendants of `v` in the virtual tree (the splay trees connected by path-parent pointers).
+    ///
+    /// The amortized cost of an operation is its actual cost (see [`CostCounters`])
+    /// plus the change in potential it caused.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(0.0);
+    /// let bob = lctree.make_tree(1.0);
+    /// assert_eq!(lctree.potential(), 0.0); // two single-noded trees
+    ///
+    /// lctree.link(alice, bob);
+    /// assert_eq!(lctree.potential(), 1.0); // log2(2) + log2(1)
+    /// ```
+    #[must_use]
+    pub fn potential(&self) -> f64 {
+        self.forest.potential()
+    }
+
+    /// Stops recording the operations and flushes the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error that occurred while writing the script (if any).
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    fn record(&mut self, operation: Operation) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(operation);
+        }
+    }
+
+    #[cfg(feature = "component-sizes")]
+    fn components(&self, caller: &str) -> &ComponentSizes {
+        match &self.components {
+            Some(components) => components,
+            None => panic!("{caller}: component tracking is disabled"),
+        }
+    }
+}
+
+// Replaying needs the lazy updates, which the scripts may contain with the `lazy-updates` feature,
+// so the aggregate has to support them (only) if the feature is enabled:
+macro_rules! impl_replay {
+    ($($bound:tt)+) => {
+        impl<P: $($bound)+, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+            /// Replays a write-ahead log (see [`LinkCutTree::write_ahead_log`]) and returns
+            /// the number of applied operations. This is the same as [`LinkCutTree::apply_script`],
+            /// except that an incomplete last line (without a line break) is skipped,
+            /// as it may have been cut short by the crash.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the log cannot be read or contains an invalid line
+            /// (the operations before the invalid line are still applied).
+            ///
+            /// # Panics
+            ///
+            /// Panics if an operation panics, e.g. when it refers to a node that does not exist.
+            pub fn recover<R: BufRead>(&mut self, mut reader: R) -> Result<usize, ScriptError> {
+                let mut applied = 0;
+                let mut line = String::new();
+                for i in 1.. {
+                    line.clear();
+                    if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+                        break;
+                    }
+                    if let Some(operation) = script::parse_line(&line, i)? {
+                        self.apply_operation(operation);
+                        applied += 1;
+                    }
+                }
+                Ok(applied)
+            }
+
+            /// Replays a script of operations (as recorded by [`LinkCutTree::record_script`])
+            /// and returns the number of applied operations.
+            /// Empty lines and comments (starting with `#`) are skipped.
+            /// As scripts may contain lazy updates, the aggregate has to implement [`PathAdd`]
+            /// and [`PathAssign`] with the `lazy-updates` feature.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the script cannot be read or contains an invalid line
+            /// (the operations before the invalid line are still applied).
+            ///
+            /// # Panics
+            ///
+            /// Panics if an operation panics, e.g. when it refers to a node that does not exist.
+            ///
+            /// # Examples
+            /// ```
+            /// use lctree::{FindSum, LinkCutTree};
+            ///
+            /// let script = "
+            ///     make_tree 1 # a path of three nodes
+            ///     make_tree 2
+            ///     make_tree 3
+            ///     link 0 1
+            ///     link 1 2
+            /// ";
+            /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+            /// assert_eq!(lctree.apply_script(script.as_bytes()).unwrap(), 5);
+            /// assert_eq!(lctree.path(0, 2).sum, 6.0);
+            /// ```
+            pub fn apply_script<R: BufRead>(&mut self, reader: R) -> Result<usize, ScriptError> {
+                let mut applied = 0;
+                for (i, line) in reader.lines().enumerate() {
+                    if let Some(operation) = script::parse_line(&line?, i + 1)? {
+                        self.apply_operation(operation);
+                        applied += 1;
+                    }
+                }
+                Ok(applied)
+            }
+
+            fn apply_operation(&mut self, operation: Operation) {
+                match operation {
+                    Operation::MakeTree(weight) => {
+                        self.make_tree(weight);
+                    }
+                    Operation::RemoveTree(v) => self.remove_tree_unchecked(v),
+                    Operation::Link(v, w) => {
+                        self.link(v, w);
+                    }
+                    Operation::Cut(v, w) => {
+                        self.cut(v, w);
+                    }
+                    Operation::Linked(v, w) => {
+                        self.linked(v, w);
+                    }
+                    Operation::Connected(v, w) => {
+                        self.connected(v, w);
+                    }
+                    Operation::Path(v, w) => {
+                        self.path(v, w);
+                    }
+                    Operation::FindRoot(v) => {
+                        self.findroot(v);
+                    }
+                    #[cfg(feature = "lazy-updates")]
+                    Operation::SubtreeAdd(v, delta) => self.subtree_add(v, delta),
+                    Operation::ReplaceWeight(v, weight) => {
+                        self.replace_weight(v, weight);
+                    }
+                    #[cfg(feature = "lazy-updates")]
+                    Operation::PathAdd(v, w, delta) => {
+                        self.path_add(v, w, delta);
+                    }
+                    #[cfg(feature = "lazy-updates")]
+                    Operation::PathAssign(v, w, weight) => {
+                        self.path_assign(v, w, weight);
+                    }
+                    #[cfg(not(feature = "lazy-updates"))]
+                    Operation::SubtreeAdd(..) | Operation::PathAdd(..) | Operation::PathAssign(..) => {
+                        unreachable!("the lazy updates are rejected by `parse_line`")
+                    }
+                    Operation::Reroot(v) => self.reroot(v),
+                    Operation::IdReuse(enabled) => self.set_id_reuse(enabled),
+                    Operation::IdReusePolicy(policy) => self.set_id_reuse_policy(policy),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "lazy-updates")]
+impl_replay!(PathAdd + PathAssign);
+#[cfg(not(feature = "lazy-updates"))]
+impl_replay!(Path);
+
+impl<P: Path + Debug, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+    /// Writes the internal state of the forest for debugging: the free ids in the order
+    /// they will be reused, and for every node slot its splay tree links, pending flip and tags,
+    /// sizes and aggregate (one line per node, or `removed`).
+    ///
+    /// The format is meant to be read by people (e.g. attached to a bug report along with
+    /// a recorded script, see [`LinkCutTree::record_script`]) and may change between versions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.0);
+    /// lctree.link(alice, bob);
+    ///
+    /// let mut output = Vec::new();
+    /// lctree.dump_state(&mut output).unwrap();
+    /// let output = String::from_utf8(output).unwrap();
+    /// assert!(output.starts_with("forest: 2 nodes in 2 slots, free ids: []\n0: parent "));
+    /// ```
+    pub fn dump_state<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.forest.dump_state(writer)
+    }
+}
+
+impl<Ix: IndexType, S: NodeStorage<NodeSlot<FindMax, Ix>>> LinkCutTree<FindMax, Ix, S> {
+    /// Checks if any node on the path between `v` and `w` has a weight above `threshold`
+    /// (e.g. "is any node on the route over capacity?"), or returns `false` if they are not connected.
+    ///
+    /// With the lazy aggregates (see [`LinkCutTree::set_lazy_aggregates`]), the stale aggregates
+    /// of the path are not recomputed: the search descends only into the parts of the path
+    /// that changed since they were last aggregated, and stops at the first node above `threshold`.
+    /// Otherwise, the maximum of the path is up to date and this is a single comparison.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// // the loads of the nodes on a route:
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest(&[0.2, 0.9, 0.4, 0.7]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    /// lctree.link(nodes[2], nodes[3]);
+    ///
+    /// assert!(lctree.path_exceeds(nodes[0], nodes[3], 0.8));
+    /// assert!(!lctree.path_exceeds(nodes[2], nodes[3], 0.8));
+    /// ```
+    pub fn path_exceeds(&mut self, v: usize, w: usize, threshold: f64) -> bool {
+        self.evert(v);
+        self.access(w);
+        if self.forest.parent_of(v).is_none() && v != w {
+            return false;
+        }
+        self.forest.exceeds(w, threshold)
+    }
+
+    /// Finds the maximum on the path between `v` and `w` along with its position on the path
+    /// (the number of edges from `v` to it), or returns `None` if they are not connected.
+    /// This tells where on a route the bottleneck is, e.g. to split the route there.
+    ///
+    /// The position is the depth of the maximum when `v` is the root, which is counted
+    /// from the sizes of the splay trees in `O(logn)` amortized time
+    /// (and requires the `depth-queries` feature).
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest(&[0.2, 0.4, 0.9, 0.7]);
+    /// lctree.link(nodes[0], nodes[1]);
+    /// lctree.link(nodes[1], nodes[2]);
+    /// lctree.link(nodes[2], nodes[3]);
+    ///
+    /// let (max, position) = lctree.path_max_position(nodes[0], nodes[3]).unwrap();
+    /// assert_eq!((max.idx, position), (nodes[2], 2));
+    /// let (_, position) = lctree.path_max_position(nodes[3], nodes[0]).unwrap();
+    /// assert_eq!(position, 1);
+    /// ```
+    #[cfg(feature = "depth-queries")]
+    pub fn path_max_position(&mut self, v: usize, w: usize) -> Option<(FindMax, usize)> {
+        self.evert(v);
+        self.access(w);
+        if self.forest.parent_of(v).is_none() && v != w {
+            return None;
+        }
+        let max = self.forest.aggregated_path_of(w);
+        Some((max, self.depth_of(max.idx)))
+    }
+}
+
+impl Default for LinkCutTree<FindMax> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a new single-noded tree for every weight (with the ids assigned in order,
+/// as by [`LinkCutTree::extend_forest`]).
+///
+/// # Examples
+/// ```
+/// use lctree::LinkCutTree;
+///
+/// let mut lctree = LinkCutTree::default();
+/// lctree.extend([1.0, 2.0]);
+/// lctree.extend((0..3).map(|i| f64::from(i) / 2.0));
+/// assert_eq!(lctree.len(), 5);
+/// ```
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> Extend<f64>
+    for LinkCutTree<P, Ix, S>
+{
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, weights: I) {
+        for weight in weights {
+            self.make_tree(weight);
+        }
+    }
+}
+
+/// Builds a forest of single-noded trees from the weights (with the ids `0..n` in order).
+///
+/// # Examples
+/// ```
+/// use lctree::{FindSum, LinkCutTree};
+///
+/// let mut lctree: LinkCutTree<FindSum> = (1..=4).map(f64::from).collect();
+/// lctree.link(0, 1);
+/// lctree.link(1, 3);
+/// assert_eq!(lctree.path(0, 3).sum, 7.0);
+/// ```
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> FromIterator<f64>
+    for LinkCutTree<P, Ix, S>
+{
+    fn from_iter<I: IntoIterator<Item = f64>>(weights: I) -> Self {
+        let mut lctree = Self::new();
+        lctree.extend(weights);
+        lctree
+    }
 }
 
-impl Default for LinkCutTree<FindMax> {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "lazy-updates")]
+    use crate::UpdateError;
+    use crate::{
+        CutOutcome, FindMax, FindMin, FindSum, IdReusePolicy, InvalidNode, LinkCutTree,
+        LinkCutTreeBuilder, LinkOutcome, MoveError, NotRoot, ParentKind, RemoveError, ScriptError,
+        TourEvent, WeightError,
+    };
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    // A writer that can be inspected after it was moved into the link-cut tree:
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn link_cut() {
+        // We form a link-cut tree from the following rooted tree:
+        //     a
+        //    / \
+        //   b   e
+        //  / \   \
+        // c   d   f
+
+        let mut lctree = super::LinkCutTree::default();
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(0.0);
+        let c = lctree.make_tree(0.0);
+        let d = lctree.make_tree(0.0);
+        let e = lctree.make_tree(0.0);
+        let f = lctree.make_tree(0.0);
+
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+
+        // Checking connectivity:
+        let nodes = [a, b, c, d, e, f];
+        for i in nodes {
+            for j in nodes {
+                assert!(lctree.connected(i, j));
+            }
+        }
+
+        // We cut node e from its parent a:
+        lctree.cut(e, a);
+
+        // The forest should now look like this:
+        //     a
+        //    /
+        //   b      e
+        //  / \      \
+        // c   d      f
+
+        // We check connectivity again for the two trees:
+        let left_tree = [a, b, c, d];
+        let right_tree = [e, f];
+        for i in left_tree {
+            for j in left_tree {
+                assert!(lctree.connected(i, j));
+            }
+        }
+        for i in right_tree {
+            for j in right_tree {
+                assert!(lctree.connected(i, j));
+            }
+        }
+        for left in left_tree {
+            for right in right_tree {
+                assert!(!lctree.connected(left, right));
+            }
+        }
+    }
+
+    #[test]
+    pub fn connected_so_no_need_to_link() {
+        let mut lctree = super::LinkCutTree::default();
+        let alice = lctree.make_tree(0.0);
+        let bob = lctree.make_tree(10.0);
+        let clay = lctree.make_tree(2.0);
+        lctree.link(alice, bob);
+        lctree.link(bob, clay);
+        // Try to link two nodes that are already connected:
+        assert_eq!(
+            lctree.link(alice, clay),
+            LinkOutcome::AlreadyConnected { root: alice }
+        );
+    }
+
+    #[test]
+    pub fn cut_reporting_keeps_the_root() {
+        //     a
+        //    / \
+        //   b   c
+        //  / \
+        // d   e
+        let mut lctree = super::LinkCutTree::default();
+        let nodes: Vec<usize> = (0..5).map(|_| lctree.make_tree(0.0)).collect();
+        let (a, b, c, d, e) = (nodes[0], nodes[1], nodes[2], nodes[3], nodes[4]);
+        lctree.link(b, a);
+        lctree.link(c, a);
+        lctree.link(d, b);
+        lctree.link(e, b);
+        assert_eq!(lctree.findroot(e), a);
+
+        assert_eq!(
+            lctree.cut_reporting(b, d),
+            CutOutcome::Cut {
+                remaining_roots: (a, d)
+            }
+        );
+        assert_eq!(
+            lctree.cut_reporting(a, b),
+            CutOutcome::Cut {
+                remaining_roots: (a, b)
+            }
+        );
+        assert_eq!(lctree.findroot(c), a);
+        assert_eq!(lctree.findroot(e), b);
+        assert_eq!(lctree.cut_reporting(a, e), CutOutcome::NoSuchEdge);
+        assert!(lctree.cut_reporting(c, a).is_cut());
+        assert_eq!(lctree.findroot(c), c);
+
+        // a failed link does not change the root either:
+        assert_eq!(
+            lctree.link_reporting(e, b),
+            LinkOutcome::AlreadyConnected { root: b }
+        );
+        assert_eq!(
+            lctree.link_reporting(e, e),
+            LinkOutcome::AlreadyConnected { root: b }
+        );
+        assert_eq!(lctree.findroot(e), b);
+    }
+
+    #[test]
+    pub fn connected_but_no_edge_to_cut() {
+        let mut lctree = super::LinkCutTree::default();
+        let alice = lctree.make_tree(0.0);
+        let bob = lctree.make_tree(10.0);
+        let clay = lctree.make_tree(2.0);
+        lctree.link(alice, bob);
+        lctree.link(bob, clay);
+        // Try to cut an edge that doesn't exist:
+        assert_eq!(lctree.cut(alice, clay), CutOutcome::NoSuchEdge);
+    }
+
+    #[test]
+    pub fn linked() {
+        let mut lctree = super::LinkCutTree::default();
+        let alice = lctree.make_tree(0.0);
+        let bob = lctree.make_tree(0.0);
+        let clay = lctree.make_tree(0.0);
+
+        lctree.link(alice, bob);
+        lctree.link(bob, clay);
+
+        assert!(lctree.linked(alice, bob));
+        assert!(lctree.linked(bob, clay));
+        // alice and clay are not connected by a link
+        assert!(!lctree.linked(alice, clay));
+    }
+
+    #[test]
+    pub fn findroot() {
+        // We form a link-cut tree from the following rooted tree:
+        //     a
+        //    / \
+        //   b   e
+        //  / \   \
+        // c   d   f
+        let mut lctree = super::LinkCutTree::default();
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(0.0);
+        let c = lctree.make_tree(0.0);
+        let d = lctree.make_tree(0.0);
+        let e = lctree.make_tree(0.0);
+        let f = lctree.make_tree(0.0);
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+
+        // Checking findroot:
+        let nodes = [a, b, c, d, e, f];
+        for i in nodes {
+            assert_eq!(lctree.findroot(i), a);
+        }
+
+        // We cut node e from its parent a:
+        lctree.cut(e, a);
+
+        // The forest should now look like this:
+        //     a
+        //    /
+        //   b      e
+        //  / \      \
+        // c   d      f
+
+        // We check findroot again for the two trees:
+        let left_tree = [a, b, c, d];
+        for i in left_tree {
+            assert_eq!(lctree.findroot(i), a);
+        }
+
+        let right_tree = [e, f];
+        for i in right_tree {
+            assert_eq!(lctree.findroot(i), e);
+        }
+    }
+
+    #[test]
+    pub fn reroot() {
+        // We form a link-cut tree from the following rooted tree:
+        //     a
+        //    / \
+        //   b   e
+        //  / \   \
+        // c   d   f
+        let mut lctree = super::LinkCutTree::default();
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(0.0);
+        let c = lctree.make_tree(0.0);
+        let d = lctree.make_tree(0.0);
+        let e = lctree.make_tree(0.0);
+        let f = lctree.make_tree(0.0);
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+
+        // Checking findroot (which should be a for all nodes):
+        let nodes = [a, b, c, d, e, f];
+        for i in nodes {
+            assert_eq!(lctree.findroot(i), a);
+        }
+
+        // we make b the root of the tree:
+        lctree.reroot(b);
+
+        // The root of the tree should now be b:
+        for i in nodes {
+            assert_eq!(lctree.findroot(i), b);
+        }
+    }
+
+    #[test]
+    pub fn findmax() {
+        // We form a link-cut tree from the following rooted tree
+        // (the numbers in parentheses are the weights of the nodes):
+        //         a(0)
+        //        /    \
+        //     b(10)   e(7)
+        //     /   \     \
+        //   c(3)  d(11)  f(2)
+        let mut lctree = super::LinkCutTree::default();
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(10.);
+        let c = lctree.make_tree(3.);
+        let d = lctree.make_tree(11.);
+        let e = lctree.make_tree(7.);
+        let f = lctree.make_tree(2.);
+
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+
+        // We check the node index with max weight in the path from each node to the root:
+        assert_eq!(lctree.path(c, f).idx, b);
+        assert_eq!(lctree.path(d, f).idx, d);
+        assert_eq!(lctree.path(a, f).idx, e);
+        assert_eq!(lctree.path(a, a).idx, a);
+    }
+
+    #[test]
+    pub fn findmin() {
+        // We form a link-cut tree from the following rooted tree
+        // (the numbers in parentheses are the weights of the nodes):
+        //         a(0)
+        //        /    \
+        //     b(10)   e(7)
+        //     /   \     \
+        //   c(3)  d(11)  f(2)
+        let mut lctree: LinkCutTree<FindMin> = super::LinkCutTree::new();
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(10.);
+        let c = lctree.make_tree(3.);
+        let d = lctree.make_tree(11.);
+        let e = lctree.make_tree(7.);
+        let f = lctree.make_tree(2.);
+
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+
+        // We check the node index with max weight in the path from each node to the root:
+        assert_eq!(lctree.path(c, f).idx, a);
+        assert_eq!(lctree.path(d, f).idx, a);
+        assert_eq!(lctree.path(a, f).idx, a);
+        assert_eq!(lctree.path(e, f).idx, f);
+        assert_eq!(lctree.path(c, d).idx, c);
+    }
+
+    #[test]
+    pub fn findsum() {
+        // We form a link-cut tree from the following rooted tree
+        // (the numbers in parentheses are the weights of the nodes):
+        //         a(0)
+        //        /    \
+        //     b(10)   e(7)
+        //     /   \     \
+        //   c(3)  d(11)  f(2)
+        let mut lctree: LinkCutTree<FindSum> = super::LinkCutTree::new();
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(10.);
+        let c = lctree.make_tree(3.);
+        let d = lctree.make_tree(11.);
+        let e = lctree.make_tree(7.);
+        let f = lctree.make_tree(2.);
+
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+
+        // We check the node index with max weight in the path from each node to the root:
+        assert_eq!(lctree.path(c, f).sum, 22.);
+        assert_eq!(lctree.path(d, f).sum, 30.);
+        assert_eq!(lctree.path(a, f).sum, 9.);
+        assert_eq!(lctree.path(a, a).sum, 0.);
+        assert_eq!(lctree.path(c, d).sum, 24.);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{FindMin, FindSum, LinkCutTree};
+    #[test]
+    pub fn extend_from_iterators() {
+        let mut lctree: LinkCutTree<FindSum, u16> = [1.0, 2.0].into_iter().collect();
+        lctree.extend(vec![3.0, 4.0]);
+        let ids = lctree.extend_forest([5.0, 6.0].iter().filter(|&&w| w > 5.0));
+        assert_eq!(ids, vec![4]);
+        lctree.link(0, 4);
+        lctree.link(3, 4);
+        assert_eq!(lctree.path(0, 3).sum, 11.0);
+    }
 
     #[test]
-    pub fn link_cut() {
+    pub fn test_extend_forest() {
+        let weights = vec![1.0, 2.0, 3.0];
+        let mut lctree = LinkCutTree::default();
+        let trees_ids = lctree.extend_forest(&weights);
+        assert_eq!(trees_ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    pub fn edges() {
         // We form a link-cut tree from the following rooted tree:
         //     a
         //    / \
         //   b   e
         //  / \   \
         // c   d   f
-
         let mut lctree = super::LinkCutTree::default();
         let a = lctree.make_tree(0.0);
         let b = lctree.make_tree(0.0);
@@ -309,93 +3735,610 @@ mod tests {
         let d = lctree.make_tree(0.0);
         let e = lctree.make_tree(0.0);
         let f = lctree.make_tree(0.0);
-
         lctree.link(b, a);
         lctree.link(c, b);
         lctree.link(d, b);
         lctree.link(e, a);
         lctree.link(f, e);
 
-        // Checking connectivity:
-        let nodes = [a, b, c, d, e, f];
-        for i in nodes {
-            for j in nodes {
-                assert!(lctree.connected(i, j));
-            }
-        }
+        // The edges are reported as (child, parent) pairs w.r.t. the current root:
+        let root = lctree.findroot(a);
+        assert_eq!(root, a);
+        let mut edges = lctree.edges().collect::<Vec<_>>();
+        edges.sort_unstable();
+        assert_eq!(edges, vec![(b, a), (c, b), (d, b), (e, a), (f, e)]);
 
-        // We cut node e from its parent a:
         lctree.cut(e, a);
+        let mut edges = lctree.edges().collect::<Vec<_>>();
+        edges.sort_unstable();
+        assert_eq!(edges.len(), 4);
+        assert!(!edges.contains(&(e, a)) && !edges.contains(&(a, e)));
+    }
 
-        // The forest should now look like this:
-        //     a
-        //    /
-        //   b      e
-        //  / \      \
-        // c   d      f
+    #[test]
+    pub fn cost_counters() {
+        let mut lctree = LinkCutTree::default();
+        let nodes = lctree.extend_forest([0.0; 100]);
+        lctree.set_instrumentation(true);
+        for i in 1..nodes.len() {
+            lctree.link(nodes[i - 1], nodes[i]);
+        }
+        let potential_before = lctree.potential();
+        let counters = lctree.cost_counters().unwrap();
+        assert_eq!(counters.accesses, 2 * 99);
+        assert!(counters.splays >= counters.accesses);
+        // every rotation recomputes the node that moved down, every splay the splayed node,
+        // and every link the linked nodes (and their anchors):
+        assert!(counters.updates <= counters.rotations + counters.splays + 2 * 99);
 
-        // We check connectivity again for the two trees:
-        let left_tree = [a, b, c, d];
-        let right_tree = [e, f];
-        for i in left_tree {
-            for j in left_tree {
-                assert!(lctree.connected(i, j));
-            }
+        // the amortized cost of the queries is bounded by O(logn):
+        lctree.set_instrumentation(true);
+        for &node in &nodes {
+            lctree.findroot(node);
         }
-        for i in right_tree {
-            for j in right_tree {
-                assert!(lctree.connected(i, j));
-            }
+        let counters = lctree.cost_counters().unwrap();
+        let amortized = counters.rotations as f64 + lctree.potential() - potential_before;
+        assert!(amortized <= 100.0 * 3.0 * (100_f64.log2() + 1.0));
+
+        lctree.set_instrumentation(false);
+        assert!(lctree.cost_counters().is_none());
+    }
+
+    #[test]
+    pub fn builder() {
+        let mut lctree: LinkCutTree<FindSum> = crate::LinkCutTreeBuilder::new()
+            .capacity(10)
+            .instrumentation(true)
+            .build();
+        assert!(lctree.capacity() >= 10);
+        assert_eq!(lctree.cost_counters(), Some(crate::CostCounters::default()));
+
+        let alice = lctree.make_tree(1.0);
+        let bob = lctree.make_tree(2.0);
+        lctree.link(alice, bob);
+        assert_eq!(lctree.path(alice, bob).sum, 3.0);
+        assert!(lctree.cost_counters().unwrap().accesses > 0);
+    }
+
+    #[test]
+    pub fn index_types() {
+        let mut lctree: LinkCutTree<FindSum, u16> = super::LinkCutTree::new();
+        let nodes = lctree.extend_forest(vec![1.0; 65535]);
+        for i in 1..nodes.len() {
+            lctree.link(nodes[i - 1], nodes[i]);
         }
-        for left in left_tree {
-            for right in right_tree {
-                assert!(!lctree.connected(left, right));
+        assert_eq!(lctree.path(nodes[0], nodes[65534]).sum, 65535.0);
+        assert_eq!(lctree.findroot(nodes[100]), nodes[0]);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn index_type_overflow() {
+        let mut lctree: LinkCutTree<FindSum, u16> = super::LinkCutTree::new();
+        // one value of the index type is reserved:
+        let _ = lctree.extend_forest(vec![1.0; 65536]);
+    }
+
+    #[test]
+    pub fn node_ids() {
+        let mut lctree = LinkCutTree::default();
+        let nodes = lctree.extend_forest([0.0; 5]);
+        assert_eq!(lctree.node_ids().collect::<Vec<_>>(), nodes);
+
+        lctree.remove_tree(nodes[1]).unwrap();
+        lctree.remove_tree(nodes[3]).unwrap();
+        assert_eq!(
+            lctree.node_ids().collect::<Vec<_>>(),
+            vec![nodes[0], nodes[2], nodes[4]]
+        );
+
+        assert_eq!(lctree.len(), 3);
+
+        // the freed ids are reused:
+        let reused = lctree.make_tree(0.0);
+        assert_eq!(reused, nodes[3]);
+        assert_eq!(
+            lctree.node_ids().collect::<Vec<_>>(),
+            vec![nodes[0], nodes[2], nodes[3], nodes[4]]
+        );
+        assert_eq!(lctree.len(), 4);
+        assert!(!lctree.is_empty());
+    }
+
+    #[test]
+    pub fn splay_tree() {
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([0.0; 6]);
+        for i in 1..nodes.len() {
+            lctree.link(nodes[i], nodes[i - 1]);
+        }
+        lctree.findroot(nodes[3]);
+        lctree.findroot(nodes[1]);
+        // every preferred path is listed in order by the splay tree of any of its nodes:
+        for path in lctree.preferred_paths() {
+            for &v in &path {
+                let tree = lctree.splay_tree(v);
+                let in_order: Vec<usize> = tree.nodes.iter().map(|&(node, _)| node).collect();
+                assert_eq!(in_order, path);
+                assert!(tree.nodes.contains(&(tree.root, 0)));
             }
         }
+        let tree = lctree.splay_tree(nodes[5]);
+        assert_eq!(tree.path_parent, Some(nodes[3]));
+        assert_eq!(
+            tree.to_string(),
+            "splay tree of 4 (path-parent: 3)\n4\n  5\n"
+        );
     }
 
     #[test]
-    pub fn connected_so_no_need_to_link() {
+    pub fn set_weight() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0]);
+        lctree.link(nodes[0], nodes[1]);
+        lctree.link(nodes[2], nodes[1]);
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(nodes[1], 10.0); // all of them, the root is 1
+        #[cfg(not(feature = "lazy-updates"))]
+        lctree.set_weights(&[(nodes[0], 11.0), (nodes[1], 12.0), (nodes[2], 13.0)]);
+        assert_eq!(lctree.path(nodes[0], nodes[2]).sum, 36.0);
+        lctree.set_weight(nodes[1], 0.0);
+        assert_eq!(lctree.path(nodes[0], nodes[2]).sum, 24.0);
+        assert_eq!(lctree.node(nodes[1]).unwrap().weight, 0.0);
+        lctree.set_weight(nodes[2], -1.0);
+        assert_eq!(lctree.path(nodes[2], nodes[0]).sum, 10.0);
+    }
+
+    #[test]
+    pub fn set_weights() {
+        // a path of 64 nodes, where a few updates are applied one by one
+        // and many updates rebuild the aggregates:
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0; 64]);
+        for i in 1..nodes.len() {
+            lctree.link(nodes[i], nodes[i - 1]);
+        }
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(nodes[32], 1.0);
+        #[cfg(not(feature = "lazy-updates"))]
+        for &v in &nodes[32..] {
+            lctree.replace_weight(v, 2.0);
+        }
+        lctree.set_weights(&[(nodes[0], 3.0), (nodes[63], 0.0)]);
+        assert_eq!(
+            lctree.path(nodes[0], nodes[63]).sum,
+            3.0 + 31.0 + 2.0 * 31.0
+        );
+
+        let updates: Vec<(usize, f64)> = nodes.iter().map(|&v| (v, 0.5)).collect();
+        lctree.set_weights(&updates[..32]);
+        assert_eq!(lctree.path(nodes[0], nodes[63]).sum, 16.0 + 2.0 * 31.0);
+        // the later updates of the same node win:
+        lctree.set_weights(&[(nodes[1], 7.0), (nodes[1], 9.0)]);
+        assert_eq!(lctree.path(nodes[1], nodes[1]).sum, 9.0);
+    }
+
+    #[test]
+    pub fn set_weights_checks_the_batch_first() {
+        let buffer = SharedBuffer::default();
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0; 3]);
+        lctree.remove_tree(nodes[2]).unwrap();
+        lctree.set_weight_validation(true);
+        lctree.record_script(buffer.clone());
+
+        assert_eq!(
+            lctree.try_set_weights(&[(nodes[0], 2.0), (nodes[2], 2.0)]),
+            Err(InvalidNode(nodes[2]))
+        );
+        let failed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            lctree.set_weights(&[(nodes[0], 2.0), (nodes[1], f64::NAN)]);
+        }));
+        assert!(failed.is_err());
+        assert_eq!(lctree.node(nodes[0]).unwrap().weight, 1.0);
+        assert!(buffer.0.lock().unwrap().is_empty());
+
+        assert_eq!(lctree.try_set_weights(&[(nodes[1], 3.0)]), Ok(()));
+        assert_eq!(lctree.node(nodes[1]).unwrap().weight, 3.0);
+    }
+
+    #[test]
+    pub fn map_weights() {
+        // We form a link-cut tree from the following rooted tree
+        // (the numbers in parentheses are the weights of the nodes):
+        //         a(0)
+        //        /    \
+        //     b(10)   e(7)
+        //     /   \     \
+        //   c(3)  d(11)  f(2)
         let mut lctree = super::LinkCutTree::default();
-        let alice = lctree.make_tree(0.0);
-        let bob = lctree.make_tree(10.0);
-        let clay = lctree.make_tree(2.0);
-        lctree.link(alice, bob);
-        lctree.link(bob, clay);
-        // Try to link two nodes that are already connected:
-        assert!(!lctree.link(alice, clay));
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(10.);
+        let c = lctree.make_tree(3.);
+        let d = lctree.make_tree(11.);
+        let e = lctree.make_tree(7.);
+        let f = lctree.make_tree(2.);
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+        assert_eq!(lctree.path(c, f).idx, b);
+
+        // negate the weights, the heaviest node becomes the lightest:
+        lctree.map_weights(|_, weight| -weight);
+        assert_eq!(lctree.path(c, f).idx, a);
+        assert_eq!(lctree.path(c, f).weight, 0.0);
+        assert_eq!(lctree.path(d, f).idx, a);
+        assert_eq!(lctree.path(c, c).weight, -3.0);
     }
 
     #[test]
-    pub fn connected_but_no_edge_to_cut() {
+    pub fn rebuild_aggregates() {
+        let mut lctree: LinkCutTree<FindSum> = super::LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0, 4.0, 5.0]);
+        for i in 1..nodes.len() {
+            lctree.link(nodes[i - 1], nodes[i]);
+        }
+        lctree.cut(nodes[1], nodes[2]);
+        lctree.rebuild_aggregates();
+        assert_eq!(lctree.path(nodes[0], nodes[1]).sum, 3.0);
+        assert_eq!(lctree.path(nodes[2], nodes[4]).sum, 12.0);
+        assert_eq!(lctree.path(nodes[4], nodes[3]).sum, 9.0);
+
+        // the memoized query results are dropped:
+        let version = lctree.forest.version();
+        lctree.rebuild_aggregates();
+        assert!(lctree.forest.version() > version);
+    }
+
+    #[test]
+    pub fn lazy_aggregates() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let num_nodes = 60;
+        let mut lazy: LinkCutTree<FindSum> =
+            LinkCutTreeBuilder::new().lazy_aggregates(true).build();
+        let mut eager: LinkCutTree<FindSum> = LinkCutTree::new();
+        lazy.extend(vec![1.0; num_nodes]);
+        eager.extend(vec![1.0; num_nodes]);
+        assert!(lazy.lazy_aggregates());
+        for _ in 0..3000 {
+            let v = rng.gen_range(0..num_nodes);
+            let w = rng.gen_range(0..num_nodes);
+            match rng.gen_range(0..5) {
+                0 => assert_eq!(lazy.link(v, w), eager.link(v, w)),
+                1 => assert_eq!(lazy.cut(v, w), eager.cut(v, w)),
+                2 => {
+                    let weight = f64::from(rng.gen_range(0..10));
+                    lazy.replace_weight(v, weight);
+                    eager.replace_weight(v, weight);
+                }
+                #[cfg(feature = "lazy-updates")]
+                3 => {
+                    let delta = f64::from(rng.gen_range(-3..=3));
+                    lazy.subtree_add(v, delta);
+                    eager.subtree_add(v, delta);
+                }
+                _ => assert_eq!(lazy.path(v, w).sum, eager.path(v, w).sum),
+            }
+        }
+
+        // disabling it brings all aggregates up to date:
+        lazy.set_lazy_aggregates(false);
+        let mut output = Vec::new();
+        lazy.dump_state(&mut output).unwrap();
+        assert!(!String::from_utf8(output).unwrap().contains("dirty true"));
+        for v in 0..num_nodes {
+            let (actual, expected) = (lazy.node(v).unwrap(), eager.node(v).unwrap());
+            assert_eq!(actual.aggregate.sum, expected.aggregate.sum);
+        }
+    }
+
+    #[test]
+    pub fn bulk_edit() {
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([3.0, 1.0, 4.0, 1.0, 5.0]);
+        lctree.begin_bulk_edit();
+        assert!(lctree.in_bulk_edit() && lctree.lazy_aggregates());
+        lctree.link_all(&[(1, 0), (2, 1), (3, 2)]);
+        assert_eq!(lctree.path(nodes[0], nodes[3]).idx, nodes[2]);
+        lctree.begin_bulk_edit(); // already in a bulk edit
+        lctree.link(nodes[4], nodes[3]);
+        lctree.replace_weight(nodes[2], 0.0);
+        lctree.end_bulk_edit();
+        assert!(!lctree.in_bulk_edit() && !lctree.lazy_aggregates());
+        assert_eq!(lctree.path(nodes[0], nodes[3]).idx, nodes[0]);
+        assert_eq!(lctree.path(nodes[0], nodes[4]).idx, nodes[4]);
+
+        // the lazy aggregate mode is restored:
+        lctree.set_lazy_aggregates(true);
+        lctree.begin_bulk_edit();
+        lctree.end_bulk_edit();
+        lctree.end_bulk_edit();
+        assert!(lctree.lazy_aggregates());
+    }
+
+    #[test]
+    pub fn into_aggregate() {
+        // We form a link-cut tree from the following rooted tree
+        // (the numbers in parentheses are the weights of the nodes):
+        //         a(0)
+        //        /    \
+        //     b(10)   e(7)
+        //     /   \     \
+        //   c(3)  d(11)  f(2)
         let mut lctree = super::LinkCutTree::default();
-        let alice = lctree.make_tree(0.0);
-        let bob = lctree.make_tree(10.0);
-        let clay = lctree.make_tree(2.0);
-        lctree.link(alice, bob);
-        lctree.link(bob, clay);
-        // Try to cut an edge that doesn't exist:
-        assert!(!lctree.cut(alice, clay));
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(10.);
+        let c = lctree.make_tree(3.);
+        let d = lctree.make_tree(11.);
+        let e = lctree.make_tree(7.);
+        let f = lctree.make_tree(2.);
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+        lctree.cut(f, e);
+        assert_eq!(lctree.path(c, e).idx, b);
+
+        let mut lctree: LinkCutTree<FindMin> = lctree.into_aggregate();
+        assert_eq!(lctree.path(c, e).idx, a);
+        assert_eq!(lctree.path(d, c).idx, c);
+        assert!(!lctree.connected(f, e));
+
+        let mut lctree: LinkCutTree<FindSum> = lctree.into_aggregate();
+        assert_eq!(lctree.path(c, e).sum, 20.);
+        assert_eq!(lctree.path(d, e).sum, 28.);
+        assert_eq!(lctree.len(), 6);
     }
 
     #[test]
-    pub fn linked() {
+    pub fn path_many() {
+        // We form a link-cut tree from the following rooted tree
+        // (the numbers in parentheses are the weights of the nodes):
+        //         a(0)
+        //        /    \
+        //     b(10)   e(7)
+        //     /   \     \
+        //   c(3)  d(11)  f(2)      g(5)
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(10.);
+        let c = lctree.make_tree(3.);
+        let d = lctree.make_tree(11.);
+        let e = lctree.make_tree(7.);
+        let f = lctree.make_tree(2.);
+        let g = lctree.make_tree(5.);
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+
+        let pairs = [
+            (c, f),
+            (d, g),
+            (c, d),
+            (f, c),
+            (c, c),
+            (a, f),
+            (g, g),
+            (c, a),
+        ];
+        let expected = pairs
+            .iter()
+            .map(|&(v, w)| {
+                let path = lctree.path(v, w);
+                if path.sum.is_finite() {
+                    Some(path.sum)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let actual = lctree
+            .path_many(&pairs)
+            .into_iter()
+            .map(|path| path.map(|path| path.sum))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+        assert_eq!(actual[0], Some(22.));
+        assert_eq!(actual[1], None);
+        assert!(lctree.path_many(&[]).is_empty());
+    }
+
+    #[test]
+    pub fn runtime_aggregates() {
+        // We form a link-cut tree from the following rooted tree
+        // (the numbers in parentheses are the weights of the nodes):
+        //         a(0)
+        //        /    \
+        //     b(10)   e(7)
+        //     /   \     \
+        //   c(3)  d(11)  f(2)
         let mut lctree = super::LinkCutTree::default();
-        let alice = lctree.make_tree(0.0);
-        let bob = lctree.make_tree(0.0);
-        let clay = lctree.make_tree(0.0);
+        let a = lctree.make_tree(0.0);
+        let b = lctree.make_tree(10.);
+        let c = lctree.make_tree(3.);
+        // registering in the middle of the construction:
+        let sum = lctree.register_aggregate(|weight, _| weight, |x, y| x + y);
+        let d = lctree.make_tree(11.);
+        let e = lctree.make_tree(7.);
+        let f = lctree.make_tree(2.);
+        let count = lctree.register_aggregate(|_, _| 1.0, |x, y| x + y);
+        lctree.link(b, a);
+        lctree.link(c, b);
+        lctree.link(d, b);
+        lctree.link(e, a);
+        lctree.link(f, e);
+
+        assert_eq!(lctree.path(c, f).idx, b);
+        assert_eq!(lctree.path_aggregate(c, f, sum), Some(22.));
+        assert_eq!(lctree.path_aggregate(c, f, count), Some(5.));
+        assert_eq!(lctree.path_aggregate(d, c, sum), Some(24.));
+        assert_eq!(lctree.path_aggregate(a, a, count), Some(1.));
+
+        lctree.cut(e, a);
+        assert_eq!(lctree.path_aggregate(c, f, sum), None);
+        assert_eq!(lctree.path_aggregate(e, f, sum), Some(9.));
+
+        // the registered aggregates survive the conversion to another aggregate type:
+        let mut lctree: LinkCutTree<FindSum> = lctree.into_aggregate();
+        assert_eq!(lctree.path_aggregate(c, a, count), Some(3.));
+    }
+
+    #[test]
+    #[cfg(feature = "lazy-updates")]
+    pub fn updatable_aggregates() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let sum = lctree.register_updatable_aggregate(
+            |weight, _| weight,
+            |x, y| x + y,
+            |sum, delta, size| sum + delta * size as f64,
+            |weight, _, size| weight * size as f64,
+        );
+        let min = lctree.register_updatable_aggregate(
+            |weight, _| weight,
+            f64::min,
+            |min, delta, _| min + delta,
+            |weight, _, _| weight,
+        );
+        let mut mins: LinkCutTree<FindMin> = LinkCutTree::new();
+        let num_nodes = 30;
+        let _ = lctree.extend_forest(vec![1.0; num_nodes]);
+        let _ = mins.extend_forest(vec![1.0; num_nodes]);
+        for _ in 0..2000 {
+            let v = rng.gen_range(0..num_nodes);
+            let w = rng.gen_range(0..num_nodes);
+            let x = f64::from(rng.gen_range(-2..=2));
+            match rng.gen_range(0..5) {
+                0 => {
+                    lctree.link(v, w);
+                    mins.link(v, w);
+                }
+                1 => {
+                    lctree.cut(v, w);
+                    mins.cut(v, w);
+                }
+                2 => {
+                    lctree.subtree_add(v, x);
+                    let root = lctree.findroot(v);
+                    mins.reroot(root);
+                    mins.subtree_add(v, x);
+                }
+                3 => {
+                    lctree.path_add(v, w, x);
+                    mins.path_add(v, w, x);
+                }
+                _ => {
+                    lctree.path_assign(v, w, x);
+                    mins.path_assign(v, w, x);
+                }
+            }
+            let expected = Some(lctree.path(v, w).sum).filter(|sum| sum.is_finite());
+            assert_eq!(lctree.path_aggregate(v, w, sum), expected);
+            let expected = expected.map(|_| mins.path(v, w).weight);
+            assert_eq!(lctree.path_aggregate(v, w, min), expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "path_add: the registered aggregate 0 cannot be updated lazily")]
+    #[cfg(feature = "lazy-updates")]
+    pub fn fixed_aggregate_path_add() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let _ = lctree.register_aggregate(|weight, _| weight, f64::max);
+        let a = lctree.make_tree(1.0);
+        lctree.path_add(a, a, 1.0);
+    }
+
+    #[test]
+    #[cfg(feature = "lazy-updates")]
+    pub fn checked_lazy_updates() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        lctree.set_weight_validation(true);
+        let nodes = lctree.extend_forest([1.0; 3]);
+        lctree.link(nodes[0], nodes[1]);
+        assert_eq!(lctree.try_path_add(nodes[0], nodes[1], 2.0), Ok(true));
+        assert_eq!(lctree.try_path_add(nodes[0], nodes[2], 2.0), Ok(false));
+        assert_eq!(
+            lctree.try_path_add(nodes[0], 5, 2.0),
+            Err(UpdateError::InvalidNode(5))
+        );
+        assert_eq!(
+            lctree.try_path_add(nodes[0], nodes[1], f64::NAN),
+            Err(UpdateError::Weight(WeightError::NaN))
+        );
+
+        let sum = lctree.register_updatable_aggregate(
+            |weight, _| weight,
+            |x, y| x + y,
+            |sum, delta, size| sum + delta * size as f64,
+            |weight, _, size| weight * size as f64,
+        );
+        assert_eq!(lctree.try_path_add(nodes[1], nodes[0], 1.0), Ok(true));
+        let max = lctree.register_aggregate(|weight, _| weight, f64::max);
+        assert_eq!(
+            lctree.try_path_add(nodes[1], nodes[0], 1.0),
+            Err(UpdateError::NotUpdatable(max))
+        );
+        assert_eq!(lctree.path(nodes[0], nodes[1]).sum, 8.0);
+        assert_eq!(lctree.path_aggregate(nodes[0], nodes[1], sum), Some(8.0));
+        assert_eq!(
+            lctree.try_path_assign(nodes[0], nodes[1], 0.0),
+            Err(UpdateError::NotUpdatable(max))
+        );
+        assert_eq!(lctree.path(nodes[0], nodes[1]).sum, 8.0);
+    }
+
+    #[test]
+    pub fn weight_validation() {
+        let mut lctree = LinkCutTree::default();
+        assert!(!lctree.weight_validation());
+        let a = lctree.make_tree(f64::NAN); // accepted without the validation
+        let b = lctree.make_tree(1.0);
+        let c = lctree.make_tree(2.0);
+        lctree.link(a, b);
+        lctree.link(b, c);
+        // NaN is ordered above all the other weights:
+        assert_eq!(lctree.path(a, c).idx, a);
+        assert_eq!(lctree.path(b, c).idx, c);
 
-        lctree.link(alice, bob);
-        lctree.link(bob, clay);
+        lctree.set_weight_validation(true);
+        assert_eq!(lctree.try_make_tree(f64::NAN), Err(WeightError::NaN));
+        assert_eq!(
+            lctree.try_make_tree(f64::NEG_INFINITY),
+            Err(WeightError::Infinite)
+        );
+        assert_eq!(lctree.try_make_tree(3.0), Ok(3));
+        assert_eq!(lctree.len(), 4);
+    }
 
-        assert!(lctree.linked(alice, bob));
-        assert!(lctree.linked(bob, clay));
-        // alice and clay are not connected by a link
-        assert!(!lctree.linked(alice, clay));
+    #[test]
+    #[should_panic(expected = "make_tree: the weight is NaN")]
+    pub fn weight_validation_make_tree() {
+        let mut lctree: LinkCutTree<FindMin> =
+            LinkCutTreeBuilder::new().weight_validation(true).build();
+        lctree.make_tree(f64::NAN);
     }
 
     #[test]
-    pub fn findroot() {
+    #[should_panic(expected = "map_weights: the weight is infinite")]
+    pub fn weight_validation_map_weights() {
+        let mut lctree = LinkCutTree::default();
+        lctree.set_weight_validation(true);
+        let _ = lctree.extend_forest([1.0, 0.0]);
+        lctree.map_weights(|_, weight| 1.0 / weight);
+    }
+
+    #[test]
+    pub fn dfs() {
         // We form a link-cut tree from the following rooted tree:
         //     a
         //    / \
@@ -406,52 +4349,46 @@ mod tests {
         let a = lctree.make_tree(0.0);
         let b = lctree.make_tree(0.0);
         let c = lctree.make_tree(0.0);
+        // enabling the tracking collects the existing edges:
+        lctree.link(b, a);
+        lctree.set_adjacency_tracking(true);
         let d = lctree.make_tree(0.0);
         let e = lctree.make_tree(0.0);
         let f = lctree.make_tree(0.0);
-        lctree.link(b, a);
+        let g = lctree.make_tree(0.0);
         lctree.link(c, b);
         lctree.link(d, b);
         lctree.link(e, a);
         lctree.link(f, e);
+        assert!(lctree.adjacency_tracking());
 
-        // Checking findroot:
-        let nodes = [a, b, c, d, e, f];
-        for i in nodes {
-            assert_eq!(lctree.findroot(i), a);
-        }
-
-        // We cut node e from its parent a:
-        lctree.cut(e, a);
-
-        // The forest should now look like this:
-        //     a
-        //    /
-        //   b      e
-        //  / \      \
-        // c   d      f
-
-        // We check findroot again for the two trees:
-        let left_tree = [a, b, c, d];
-        for i in left_tree {
-            assert_eq!(lctree.findroot(i), a);
-        }
+        assert_eq!(lctree.dfs(a).collect::<Vec<_>>(), vec![a, b, c, d, e, f]);
+        assert_eq!(lctree.dfs(e).collect::<Vec<_>>(), vec![e, a, b, c, d, f]);
+        assert_eq!(lctree.dfs(g).collect::<Vec<_>>(), vec![g]);
+        assert_eq!(lctree.neighbors(b).count(), 3);
+        assert_eq!(lctree.neighbors(g).count(), 0);
 
-        let right_tree = [e, f];
-        for i in right_tree {
-            assert_eq!(lctree.findroot(i), e);
+        lctree.cut(b, a);
+        let mut component = lctree.dfs(d).collect::<Vec<_>>();
+        component.sort_unstable();
+        assert_eq!(component, vec![b, c, d]);
+        assert_eq!(lctree.dfs(a).collect::<Vec<_>>(), vec![a, e, f]);
+        assert_eq!(lctree.neighbors(a).collect::<Vec<_>>(), vec![e]);
+        for v in [a, b, c, d, e, f, g] {
+            assert_eq!(lctree.degree(v), lctree.neighbors(v).count());
         }
     }
 
     #[test]
-    pub fn reroot() {
+    pub fn bfs() {
         // We form a link-cut tree from the following rooted tree:
         //     a
         //    / \
         //   b   e
         //  / \   \
         // c   d   f
-        let mut lctree = super::LinkCutTree::default();
+        let mut lctree: LinkCutTree<FindSum> =
+            LinkCutTreeBuilder::new().adjacency_tracking(true).build();
         let a = lctree.make_tree(0.0);
         let b = lctree.make_tree(0.0);
         let c = lctree.make_tree(0.0);
@@ -464,128 +4401,747 @@ mod tests {
         lctree.link(e, a);
         lctree.link(f, e);
 
-        // Checking findroot (which should be a for all nodes):
-        let nodes = [a, b, c, d, e, f];
-        for i in nodes {
-            assert_eq!(lctree.findroot(i), a);
-        }
+        let levels = lctree.bfs(a).collect::<Vec<_>>();
+        assert_eq!(levels, vec![(a, 0), (b, 1), (e, 1), (c, 2), (d, 2), (f, 2)]);
 
-        // we make b the root of the tree:
-        lctree.reroot(b);
+        // rooted at another node:
+        let levels = lctree.bfs(f).collect::<Vec<_>>();
+        assert_eq!(levels, vec![(f, 0), (e, 1), (a, 2), (b, 3), (c, 4), (d, 4)]);
 
-        // The root of the tree should now be b:
-        for i in nodes {
-            assert_eq!(lctree.findroot(i), b);
-        }
+        lctree.cut(e, a);
+        assert_eq!(lctree.bfs(e).collect::<Vec<_>>(), vec![(e, 0), (f, 1)]);
     }
 
     #[test]
-    pub fn findmax() {
-        // We form a link-cut tree from the following rooted tree
-        // (the numbers in parentheses are the weights of the nodes):
-        //         a(0)
-        //        /    \
-        //     b(10)   e(7)
-        //     /   \     \
-        //   c(3)  d(11)  f(2)
-        let mut lctree = super::LinkCutTree::default();
+    pub fn euler_tour() {
+        // We form a link-cut tree from the following rooted tree:
+        //     a
+        //    / \
+        //   b   e
+        //  / \
+        // c   d
+        let mut lctree: LinkCutTree<FindSum> =
+            LinkCutTreeBuilder::new().adjacency_tracking(true).build();
         let a = lctree.make_tree(0.0);
-        let b = lctree.make_tree(10.);
-        let c = lctree.make_tree(3.);
-        let d = lctree.make_tree(11.);
-        let e = lctree.make_tree(7.);
-        let f = lctree.make_tree(2.);
-
+        let b = lctree.make_tree(0.0);
+        let c = lctree.make_tree(0.0);
+        let d = lctree.make_tree(0.0);
+        let e = lctree.make_tree(0.0);
         lctree.link(b, a);
         lctree.link(c, b);
         lctree.link(d, b);
         lctree.link(e, a);
-        lctree.link(f, e);
 
-        // We check the node index with max weight in the path from each node to the root:
-        assert_eq!(lctree.path(c, f).idx, b);
-        assert_eq!(lctree.path(d, f).idx, d);
-        assert_eq!(lctree.path(a, f).idx, e);
-        assert_eq!(lctree.path(a, a).idx, a);
+        let tour = lctree.euler_tour(a);
+        assert_eq!(tour.len(), 10);
+        // the nodes are entered in depth-first order:
+        let entered = tour
+            .iter()
+            .filter_map(|event| match event {
+                TourEvent::Enter(v) => Some(*v),
+                TourEvent::Exit(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(entered, lctree.dfs(a).collect::<Vec<_>>());
+        // the subtree of b is contained between its enter and exit events:
+        let enter_b = tour.iter().position(|&x| x == TourEvent::Enter(b)).unwrap();
+        let exit_b = tour.iter().position(|&x| x == TourEvent::Exit(b)).unwrap();
+        assert_eq!(exit_b - enter_b, 5);
+        assert_eq!(tour.last(), Some(&TourEvent::Exit(a)));
+
+        // a single node:
+        let f = lctree.make_tree(0.0);
+        assert_eq!(
+            lctree.euler_tour(f),
+            vec![TourEvent::Enter(f), TourEvent::Exit(f)]
+        );
     }
 
     #[test]
-    pub fn findmin() {
-        // We form a link-cut tree from the following rooted tree
-        // (the numbers in parentheses are the weights of the nodes):
-        //         a(0)
-        //        /    \
-        //     b(10)   e(7)
-        //     /   \     \
-        //   c(3)  d(11)  f(2)
-        let mut lctree: LinkCutTree<FindMin> = super::LinkCutTree::new();
+    #[should_panic(expected = "dfs: adjacency tracking is disabled")]
+    pub fn dfs_without_tracking() {
+        let mut lctree = LinkCutTree::default();
         let a = lctree.make_tree(0.0);
-        let b = lctree.make_tree(10.);
-        let c = lctree.make_tree(3.);
-        let d = lctree.make_tree(11.);
-        let e = lctree.make_tree(7.);
-        let f = lctree.make_tree(2.);
+        let _ = lctree.dfs(a);
+    }
 
-        lctree.link(b, a);
-        lctree.link(c, b);
-        lctree.link(d, b);
-        lctree.link(e, a);
-        lctree.link(f, e);
+    #[test]
+    pub fn record_and_apply_script() {
+        let buffer = SharedBuffer::default();
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        lctree.record_script(buffer.clone());
+        let nodes = lctree.extend_forest([0.5, 1.0, 1.5, 2.0, 2.5]);
+        lctree.link(nodes[0], nodes[1]);
+        lctree.link(nodes[1], nodes[2]);
+        lctree.link(nodes[3], nodes[2]);
+        lctree.cut(nodes[1], nodes[2]);
+        assert!(lctree.connected(nodes[3], nodes[2]));
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(nodes[2], 0.25);
+        #[cfg(not(feature = "lazy-updates"))]
+        lctree.replace_weight(nodes[2], 1.75);
+        lctree.replace_weight(nodes[3], 1.0);
+        lctree.remove_tree(nodes[4]).unwrap();
+        lctree.stop_recording().unwrap();
+        lctree.link(nodes[0], nodes[2]); // not recorded
 
-        // We check the node index with max weight in the path from each node to the root:
-        assert_eq!(lctree.path(c, f).idx, a);
-        assert_eq!(lctree.path(d, f).idx, a);
-        assert_eq!(lctree.path(a, f).idx, a);
-        assert_eq!(lctree.path(e, f).idx, f);
-        assert_eq!(lctree.path(c, d).idx, c);
+        let script = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(script.lines().count(), 13);
+        assert!(script.contains("connected 3 2\n"));
+
+        // Replaying the script rebuilds the same forest:
+        let mut replayed: LinkCutTree<FindSum> = LinkCutTree::new();
+        assert_eq!(replayed.apply_script(script.as_bytes()).unwrap(), 13);
+        assert_eq!(replayed.len(), 4);
+        assert!(replayed.linked(nodes[0], nodes[1]));
+        assert!(!replayed.connected(nodes[1], nodes[2]));
+        assert_eq!(replayed.path(nodes[2], nodes[3]).sum, 2.75);
+
+        let err = replayed
+            .apply_script("link 0 1\nlink 0".as_bytes())
+            .unwrap_err();
+        assert!(matches!(err, ScriptError::Parse { line: 2, .. }));
     }
 
     #[test]
-    pub fn findsum() {
-        // We form a link-cut tree from the following rooted tree
-        // (the numbers in parentheses are the weights of the nodes):
-        //         a(0)
-        //        /    \
-        //     b(10)   e(7)
-        //     /   \     \
-        //   c(3)  d(11)  f(2)
-        let mut lctree: LinkCutTree<FindSum> = super::LinkCutTree::new();
-        let a = lctree.make_tree(0.0);
-        let b = lctree.make_tree(10.);
-        let c = lctree.make_tree(3.);
-        let d = lctree.make_tree(11.);
-        let e = lctree.make_tree(7.);
-        let f = lctree.make_tree(2.);
+    pub fn write_ahead_log() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let buffer = SharedBuffer::default();
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        lctree.write_ahead_log(buffer.clone());
+        let num_nodes = 30;
+        let _ = lctree.extend_forest(vec![1.0; num_nodes]);
+        for _ in 0..500 {
+            let v = rng.gen_range(0..num_nodes);
+            let w = rng.gen_range(0..num_nodes);
+            match rng.gen_range(0..6) {
+                0 | 1 => {
+                    lctree.link(v, w);
+                }
+                2 => {
+                    lctree.cut(v, w);
+                }
+                #[cfg(feature = "lazy-updates")]
+                3 => lctree.subtree_add(v, f64::from(rng.gen_range(-2..=2))),
+                4 => {
+                    lctree.replace_weight(v, f64::from(rng.gen_range(0..5)));
+                }
+                _ => {
+                    // changes the root without being logged:
+                    lctree.path(v, w);
+                }
+            }
+        }
+        lctree.map_weights(|_, weight| weight * 2.0);
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(!log.contains("path"));
+
+        let mut recovered: LinkCutTree<FindSum> = LinkCutTree::new();
+        recovered.recover(log.as_bytes()).unwrap();
+        assert!(recovered.diff(&lctree).is_empty());
+
+        // a torn last line is skipped:
+        let mut recovered: LinkCutTree<FindSum> = LinkCutTree::new();
+        let torn = "make_tree 1\nmake_tree 2\nlink 0 1";
+        assert_eq!(recovered.recover(torn.as_bytes()).unwrap(), 2);
+        assert_eq!(recovered.edges().count(), 0);
+    }
+
+    #[test]
+    pub fn write_ahead_log_id_reuse() {
+        let buffer = SharedBuffer::default();
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        lctree.set_id_reuse_policy(IdReusePolicy::Fifo);
+        lctree.write_ahead_log(buffer.clone());
+        let nodes = lctree.extend_forest([1.0; 4]);
+        lctree.remove_tree(nodes[1]).unwrap();
+        lctree.remove_tree(nodes[2]).unwrap();
+        assert_eq!(lctree.make_tree(2.0), nodes[1]);
+        lctree.set_id_reuse(false);
+        assert_eq!(lctree.make_tree(3.0), 4);
+
+        // an operation that panics is not logged:
+        let failed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            lctree.link(nodes[0], 10);
+        }));
+        assert!(failed.is_err());
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(log.starts_with("id_reuse true\nid_reuse_policy fifo\n"));
+        assert!(!log.contains("link"));
+
+        let mut recovered: LinkCutTree<FindSum> = LinkCutTree::new();
+        recovered.recover(log.as_bytes()).unwrap();
+        assert!(recovered.diff(&lctree).is_empty());
+        assert!(!recovered.id_reuse());
+        assert_eq!(recovered.id_reuse_policy(), IdReusePolicy::Fifo);
+    }
+
+    #[test]
+    pub fn delete_tree() {
+        let mut lctree = LinkCutTree::default();
+        let alice = lctree.make_tree(0.0);
+        let bob = lctree.make_tree(1.0);
+        let clay = lctree.make_tree(2.0);
+        lctree.link(alice, bob);
+        lctree.link(clay, bob);
+        assert_eq!(
+            lctree.remove_tree(bob),
+            Err(RemoveError::NotSingleton { degree: 2 })
+        );
+        assert!(lctree.connected(alice, clay)); // nothing changed
+
+        lctree.cut(alice, bob);
+        assert_eq!(
+            lctree.remove_tree(bob),
+            Err(RemoveError::NotSingleton { degree: 1 })
+        );
+        assert_eq!(lctree.remove_tree(alice), Ok(()));
+        assert_eq!(lctree.len(), 2);
+    }
+
+    #[test]
+    pub fn link_all() {
+        let mut lctree = LinkCutTree::default();
+        let nodes = lctree.extend_forest([0.0; 5]);
+        let outcomes = lctree.link_all(&[(0, 1), (1, 2), (2, 0), (3, 4), (4, 0), (1, 3)]);
+        assert_eq!(
+            outcomes,
+            vec![
+                LinkOutcome::Linked,
+                LinkOutcome::Linked,
+                LinkOutcome::AlreadyConnected { root: nodes[2] },
+                LinkOutcome::Linked,
+                LinkOutcome::Linked,
+                LinkOutcome::AlreadyConnected { root: nodes[1] },
+            ]
+        );
+        assert_eq!(lctree.edges().count(), 4);
+        assert!(lctree.link_all(&[]).is_empty());
+    }
 
+    #[test]
+    #[cfg(feature = "component-sizes")]
+    pub fn component_ids() {
+        //     a
+        //    / \
+        //   b   c
+        //  /
+        // d
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([0.0; 4]);
+        let (a, b, c, d) = (nodes[0], nodes[1], nodes[2], nodes[3]);
+        assert_eq!(lctree.component_id(c), c);
         lctree.link(b, a);
-        lctree.link(c, b);
+        lctree.link(c, a);
         lctree.link(d, b);
-        lctree.link(e, a);
+        // linking a single node to a larger tree keeps the id of the larger tree:
+        assert!([a, b, c, d].iter().all(|&v| lctree.component_id(v) == a));
+        lctree.findroot(c);
+        lctree.linked(d, a);
+        assert_eq!(lctree.component_id(d), a);
+
+        // the part with the representative keeps the id, the other part gets a new one:
+        lctree.cut(b, a);
+        assert_eq!(lctree.component_id(c), a);
+        assert_eq!(lctree.component_id(d), b);
+
+        // a: {a, c, e} is larger than b: {b, d}
+        let e = lctree.make_tree(0.0);
+        lctree.link(a, e);
+        lctree.link(e, b);
+        assert!([a, b, c, d, e].iter().all(|&v| lctree.component_id(v) == a));
+    }
+
+    #[test]
+    pub fn query_cache() {
+        // a - b - c   d
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTreeBuilder::new().query_cache(true).build();
+        assert!(lctree.query_cache());
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0, 4.0]);
+        let (a, b, c, d) = (nodes[0], nodes[1], nodes[2], nodes[3]);
+        lctree.link(a, b);
+        lctree.link(b, c);
+        assert_eq!(lctree.path(a, c).sum, 6.0);
+        assert!(!lctree.connected(c, d));
+
+        // every kind of change invalidates the results:
+        lctree.link(d, c);
+        assert!(lctree.connected(c, d));
+        assert_eq!(lctree.path(a, c).sum, 6.0);
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(b, 1.0); // the subtree of b under a: b, c and d
+        #[cfg(not(feature = "lazy-updates"))]
+        lctree.set_weights(&[(b, 3.0), (c, 4.0), (d, 5.0)]);
+        assert_eq!(lctree.path(a, c).sum, 8.0);
+        lctree.set_weights(&[(a, 0.0)]);
+        assert_eq!(lctree.path(a, c).sum, 7.0);
+        lctree.map_weights(|_, weight| 2.0 * weight);
+        assert_eq!(lctree.path(a, c).sum, 14.0);
+        lctree.cut(b, c);
+        assert!(lctree.path(a, c).sum.is_infinite());
+        assert!(!lctree.connected(a, c));
+        lctree.cut(c, d);
+        lctree.remove_tree(c).unwrap();
+        assert_eq!(lctree.make_tree(5.0), c);
+        lctree.link(c, a);
+        assert_eq!(lctree.path(c, b).sum, 11.0);
+
+        lctree.set_query_cache(false);
+        assert_eq!(lctree.path(c, b).sum, 11.0);
+    }
+
+    #[test]
+    pub fn dump_state() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0]);
+        lctree.link(nodes[0], nodes[1]);
+        lctree.remove_tree(nodes[2]).unwrap();
+
+        let mut output = Vec::new();
+        lctree.dump_state(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "forest: 2 nodes in 3 slots, free ids: [2]");
+        // the last accessed node is the root of the splay tree of the path:
+        assert!(lines[1]
+            .starts_with("0: parent root, left 1, right -, flipped false, dirty false, weight 1,"));
+        #[cfg(feature = "component-sizes")]
+        assert!(lines[1].contains("tree size 2 (0 virtual)"));
+        assert!(lines[1].ends_with("aggregate FindSum { sum: 3.0 }"));
+        assert!(lines[2].starts_with("1: parent node 0, left -, right -,"));
+        assert_eq!(lines[3], "2: removed");
+    }
+
+    #[test]
+    pub fn node_views() {
+        // a - b - c
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0]);
+        let (a, b, c) = (nodes[0], nodes[1], nodes[2]);
+        lctree.link(a, b);
+        lctree.link(b, c);
+        lctree.path(a, c); // a is the root
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(b, 10.0); // pending in the splay trees
+        #[cfg(not(feature = "lazy-updates"))]
+        lctree.set_weights(&[(b, 12.0), (c, 13.0)]);
+
+        let view = lctree.node(c).unwrap();
+        assert_eq!(view.id, c);
+        assert_eq!(view.weight, 13.0);
+        assert_eq!(view.degree, 1);
+        let view = lctree.node(b).unwrap();
+        assert_eq!(view.weight, 12.0);
+        assert_eq!(view.degree, 2);
+
+        // the root of the splay tree of the path from the root of the tree to c covers the whole path:
+        lctree.path(a, c);
+        let root = lctree.node(lctree.splay_tree(c).root).unwrap();
+        assert_eq!(root.parent, ParentKind::Root);
+        assert_eq!(root.aggregate.sum, 26.0);
+        assert!(lctree.node(3).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "component-sizes")]
+    pub fn component_tracking() {
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([0.0; 4]);
+        lctree.link(nodes[0], nodes[1]);
+        lctree.set_component_tracking(true);
+        assert!(lctree.component_tracking());
+        assert_eq!(
+            lctree.component_size_histogram().collect::<Vec<_>>(),
+            vec![(1, 2), (2, 1)]
+        );
+
+        lctree.remove_tree(nodes[3]).unwrap();
+        let e = lctree.make_tree(0.0);
+        let f = lctree.make_tree(0.0);
         lctree.link(f, e);
+        lctree.link(e, nodes[1]);
+        let mut lctree: LinkCutTree<FindSum> = lctree.into_aggregate();
+        assert_eq!(lctree.max_component_size(), 4);
+        assert_eq!(lctree.component_size(nodes[2]), 1);
 
-        // We check the node index with max weight in the path from each node to the root:
-        assert_eq!(lctree.path(c, f).sum, 22.);
-        assert_eq!(lctree.path(d, f).sum, 30.);
-        assert_eq!(lctree.path(a, f).sum, 9.);
-        assert_eq!(lctree.path(a, a).sum, 0.);
-        assert_eq!(lctree.path(c, d).sum, 24.);
+        lctree.set_component_tracking(false);
+        assert!(!lctree.component_tracking());
     }
 
     #[test]
-    pub fn test_extend_forest() {
-        let weights = vec![1.0, 2.0, 3.0];
+    #[should_panic(expected = "max_component_size: component tracking is disabled")]
+    #[cfg(feature = "component-sizes")]
+    pub fn component_tracking_disabled() {
+        let lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        let _ = lctree.max_component_size();
+    }
+
+    #[test]
+    pub fn cut_many() {
         let mut lctree = LinkCutTree::default();
-        let trees_ids = lctree.extend_forest(&weights);
-        assert_eq!(trees_ids, vec![0, 1, 2]);
+        let nodes = lctree.extend_forest([0.0; 5]);
+        lctree.link_all(&[(1, 0), (2, 0), (3, 2), (4, 2)]);
+        let outcomes = lctree.cut_many(&[(2, 0), (0, 2), (4, 2), (1, 3)]);
+        assert_eq!(
+            outcomes,
+            vec![
+                CutOutcome::Cut {
+                    remaining_roots: (nodes[2], nodes[0])
+                },
+                CutOutcome::NoSuchEdge, // already cut
+                CutOutcome::Cut {
+                    remaining_roots: (nodes[4], nodes[2])
+                },
+                CutOutcome::NoSuchEdge,
+            ]
+        );
+        assert_eq!(lctree.edges().count(), 2);
+        assert!(lctree.connected(nodes[2], nodes[3]));
+    }
+
+    #[test]
+    pub fn cut_from_parent() {
+        // 0 - 1 - 2 - 3 rooted at 0, with a branch 1 - 4:
+        let mut lctree = LinkCutTree::default();
+        let nodes = lctree.extend_forest([0.0; 5]);
+        lctree.link_all(&[(1, 0), (2, 1), (3, 2), (4, 1)]);
+        assert_eq!(lctree.cut_from_parent(nodes[2]), Some(nodes[1]));
+        assert_eq!(lctree.cut_from_parent(nodes[2]), None);
+        assert_eq!(lctree.findroot(nodes[3]), nodes[2]);
+        assert_eq!(lctree.findroot(nodes[4]), nodes[0]);
+        #[cfg(feature = "component-sizes")]
+        assert_eq!(lctree.component_size(nodes[0]), 3);
+
+        // under another root, the parent is on the path to that root:
+        lctree.path(nodes[4], nodes[4]);
+        assert_eq!(lctree.cut_from_parent(nodes[1]), Some(nodes[4]));
+        assert_eq!(lctree.findroot(nodes[0]), nodes[1]);
+        assert_eq!(lctree.edges().count(), 2);
+    }
+
+    #[test]
+    pub fn link_rooted() {
+        // a - b - c rooted at a, and d - e rooted at d:
+        let mut lctree = LinkCutTree::default();
+        let (a, b, c, d, e) = (0, 1, 2, 3, 4);
+        let _ = lctree.extend_forest([0.0; 5]);
+        for (child, parent) in [(b, a), (c, b), (e, d)] {
+            assert_eq!(lctree.link_rooted(child, parent), Ok(LinkOutcome::Linked));
+        }
+        assert_eq!(lctree.findroot(c), a);
+        assert_eq!(lctree.findroot(e), d);
+
+        assert_eq!(
+            lctree.link_rooted(a, c),
+            Ok(LinkOutcome::AlreadyConnected { root: a })
+        );
+        assert_eq!(lctree.link_rooted(d, b), Ok(LinkOutcome::Linked));
+        assert_eq!(lctree.findroot(e), a);
+        assert_eq!(lctree.parent(e), Some(d));
+        assert_eq!(lctree.parent(d), Some(b));
+        #[cfg(feature = "depth-queries")]
+        assert_eq!(lctree.depth(c), 2);
+    }
+
+    #[test]
+    pub fn link_rooted_not_a_root() {
+        let mut lctree = LinkCutTree::default();
+        let _ = lctree.extend_forest([0.0; 3]);
+        assert_eq!(lctree.link_rooted(1, 0), Ok(LinkOutcome::Linked));
+        assert_eq!(lctree.link_rooted(1, 2), Err(NotRoot { root: 0 }));
+        assert_eq!(lctree.findroot(1), 0);
+        assert!(!lctree.connected(1, 2));
+    }
+
+    #[test]
+    pub fn move_subtree() {
+        //     a       e
+        //    / \      |
+        //   b   c     f
+        //  / \
+        // d   g
+        let mut lctree = LinkCutTree::default();
+        let (a, b, c, d, e, f, g) = (0, 1, 2, 3, 4, 5, 6);
+        let _ = lctree.extend_forest([0.0; 7]);
+        lctree.link_all(&[(b, a), (c, a), (d, b), (g, b), (f, e)]);
+
+        assert_eq!(lctree.move_subtree(b, g), Err(MoveError::IntoOwnSubtree));
+        assert_eq!(lctree.move_subtree(b, b), Err(MoveError::IntoOwnSubtree));
+        assert_eq!(lctree.move_subtree(b, 7), Err(MoveError::InvalidNode(7)));
+        assert_eq!(lctree.edges().count(), 5);
+
+        // to another tree:
+        lctree.move_subtree(b, f).unwrap();
+        assert_eq!(lctree.findroot(d), e);
+        #[cfg(feature = "depth-queries")]
+        assert_eq!(lctree.ancestor(g, 3), Some(e));
+        assert_eq!(lctree.findroot(c), a);
+
+        // a root is attached as a whole:
+        lctree.move_subtree(a, d).unwrap();
+        assert_eq!(lctree.findroot(a), e);
+        #[cfg(feature = "depth-queries")]
+        assert_eq!(lctree.ancestor(c, 5), Some(e));
+
+        // within the tree (e - f - b - d - a - c):
+        lctree.move_subtree(g, c).unwrap();
+        assert_eq!(lctree.findroot(g), e);
+        assert_eq!(lctree.parent(g), Some(c));
+        assert_eq!(lctree.lca(g, d), Some(d));
+        assert_eq!(lctree.move_subtree(e, a), Err(MoveError::IntoOwnSubtree));
+    }
+
+    #[test]
+    pub fn path_exceeds() {
+        let mut lctree = LinkCutTree::default();
+        let nodes = lctree.extend_forest([1.0, 5.0, 2.0, 3.0]);
+        lctree.link(nodes[1], nodes[0]);
+        lctree.link(nodes[2], nodes[0]);
+        assert!(lctree.path_exceeds(nodes[1], nodes[2], 4.0));
+        assert!(!lctree.path_exceeds(nodes[1], nodes[2], 5.0)); // only strictly above
+        assert!(!lctree.path_exceeds(nodes[0], nodes[2], 4.0));
+        assert!(lctree.path_exceeds(nodes[1], nodes[1], 4.0));
+        assert!(!lctree.path_exceeds(nodes[0], nodes[3], 0.0)); // not connected
+    }
+
+    #[test]
+    pub fn path_exceeds_with_lazy_aggregates() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let num_nodes = 50;
+        let mut lazy = LinkCutTree::default();
+        lazy.set_lazy_aggregates(true);
+        let mut eager = LinkCutTree::default();
+        for _ in 0..num_nodes {
+            let weight = rng.gen_range(0.0..100.0);
+            lazy.make_tree(weight);
+            eager.make_tree(weight);
+        }
+        for _ in 0..2000 {
+            let v = rng.gen_range(0..num_nodes);
+            let w = rng.gen_range(0..num_nodes);
+            match rng.gen_range(0..4) {
+                0 => {
+                    lazy.link(v, w);
+                    eager.link(v, w);
+                }
+                1 => {
+                    lazy.cut(v, w);
+                    eager.cut(v, w);
+                }
+                2 => {
+                    let weight = rng.gen_range(0.0..100.0);
+                    lazy.replace_weight(v, weight);
+                    eager.replace_weight(v, weight);
+                }
+                _ => {
+                    let threshold = rng.gen_range(0.0..100.0);
+                    let expected = eager.connected(v, w) && eager.path(v, w).weight > threshold;
+                    assert_eq!(lazy.path_exceeds(v, w, threshold), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "depth-queries")]
+    pub fn path_max_position() {
+        // a star with a long arm: 1 - 0 - 2 - 3 - 4
+        let mut lctree = LinkCutTree::default();
+        let nodes = lctree.extend_forest([1.0, 2.0, 0.0, 8.0, 4.0, 9.0]);
+        lctree.link_all(&[(1, 0), (2, 0), (3, 2), (4, 3)]);
+        let position = |lctree: &mut LinkCutTree<FindMax>, v, w| {
+            lctree
+                .path_max_position(v, w)
+                .map(|(max, position)| (max.idx, position))
+        };
+        assert_eq!(
+            position(&mut lctree, nodes[1], nodes[4]),
+            Some((nodes[3], 3))
+        );
+        assert_eq!(
+            position(&mut lctree, nodes[4], nodes[1]),
+            Some((nodes[3], 1))
+        );
+        assert_eq!(
+            position(&mut lctree, nodes[0], nodes[2]),
+            Some((nodes[0], 0))
+        );
+        assert_eq!(
+            position(&mut lctree, nodes[3], nodes[3]),
+            Some((nodes[3], 0))
+        );
+        assert_eq!(position(&mut lctree, nodes[0], nodes[5]), None);
+
+        // the positions follow the pending subtree additions:
+        #[cfg(feature = "lazy-updates")]
+        {
+            lctree.subtree_add(nodes[1], 10.0);
+            assert_eq!(
+                position(&mut lctree, nodes[4], nodes[1]),
+                Some((nodes[1], 4))
+            );
+        }
+    }
+
+    #[test]
+    pub fn for_each_on_path() {
+        // 0 - 1 - 2 - 3 with a branch 1 - 4, and a separate node 5:
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        lctree.link_all(&[(1, 0), (2, 1), (3, 2), (4, 1)]);
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(nodes[2], 10.0); // 2 and 3 under the current root
+        #[cfg(not(feature = "lazy-updates"))]
+        lctree.set_weights(&[(nodes[2], 13.0), (nodes[3], 14.0)]);
+        let count = lctree.register_aggregate(|_, _| 1.0, |a, b| a + b);
+        let mut route = Vec::new();
+        assert!(lctree.for_each_on_path(nodes[4], nodes[3], |v, weight| route.push((v, weight))));
+        assert_eq!(
+            route,
+            vec![
+                (nodes[4], 5.0),
+                (nodes[1], 2.0),
+                (nodes[2], 13.0),
+                (nodes[3], 14.0)
+            ]
+        );
+        assert!(!lctree.for_each_on_path(nodes[0], nodes[5], |_, _| panic!()));
+
+        // folding visits the same nodes in the same order:
+        let folded = lctree.path_fold(nodes[4], nodes[3], Vec::new(), |mut acc, v, weight| {
+            acc.push((v, weight));
+            acc
+        });
+        assert_eq!(folded, Some(route));
+        assert_eq!(
+            lctree.path_fold(nodes[0], nodes[5], 0, |_, _, _| panic!()),
+            None
+        );
+
+        // negate the weights of the inner nodes of the path from 0 to 3:
+        assert!(
+            lctree.for_each_on_path_mut(nodes[0], nodes[3], |v, weight| {
+                if v != nodes[0] && v != nodes[3] {
+                    *weight = -*weight;
+                }
+            })
+        );
+        assert_eq!(lctree.path(nodes[0], nodes[3]).sum, 0.0);
+        assert_eq!(lctree.path(nodes[4], nodes[2]).sum, -10.0);
+        assert_eq!(lctree.path(nodes[3], nodes[4]).sum, 4.0);
+        assert_eq!(lctree.path_aggregate(nodes[4], nodes[3], count), Some(4.0));
+        assert_eq!(lctree.node(nodes[2]).unwrap().weight, -13.0);
+
+        // unchanged weights do not count as changes, and the lazy aggregates are repaired too:
+        let version = lctree.forest.version();
+        lctree.for_each_on_path_mut(nodes[0], nodes[3], |_, _| {});
+        assert_eq!(lctree.forest.version(), version);
+        lctree.set_lazy_aggregates(true);
+        lctree.for_each_on_path_mut(nodes[3], nodes[0], |_, weight| *weight += 1.0);
+        assert_eq!(lctree.path(nodes[0], nodes[3]).sum, 4.0);
+        assert_eq!(lctree.path(nodes[4], nodes[3]).sum, 7.0);
+    }
+
+    #[test]
+    #[cfg(feature = "depth-queries")]
+    pub fn path_range_aggregate() {
+        use crate::TreeGenerator;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let weights: Vec<f64> = (0..40).map(|_| f64::from(rng.gen_range(0..100))).collect();
+        let nodes = TreeGenerator::new(0).uniform_tree(&mut lctree, &weights);
+        #[cfg(feature = "lazy-updates")]
+        lctree.subtree_add(nodes[5], 1.0);
+        for _ in 0..200 {
+            let v = nodes[rng.gen_range(0..nodes.len())];
+            let w = nodes[rng.gen_range(0..nodes.len())];
+            let mut route = Vec::new();
+            lctree.for_each_on_path(v, w, |_, weight| route.push(weight));
+            let start = rng.gen_range(0..=route.len());
+            let end = rng.gen_range(0..=route.len() + 1);
+            let expected = if start < end && end <= route.len() {
+                Some(route[start..end].iter().sum::<f64>())
+            } else {
+                None
+            };
+            let range = lctree.path_range_aggregate(v, w, start..end);
+            assert_eq!(range.map(|path| path.sum), expected);
+        }
+        let other = lctree.make_tree(1.0);
+        assert!(lctree.path_range_aggregate(nodes[0], other, ..).is_none());
+        assert_eq!(
+            lctree.path_range_aggregate(other, other, ..=0).unwrap().sum,
+            1.0
+        );
+        assert!(lctree
+            .path_range_aggregate(other, other, 0..usize::MAX)
+            .is_none());
+        assert!(lctree
+            .path_range_aggregate(other, other, ..=usize::MAX)
+            .is_none());
+    }
+
+    #[test]
+    pub fn checked_ids() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0]);
+        assert_eq!(lctree.try_link(nodes[0], nodes[1]), Ok(LinkOutcome::Linked));
+        assert_eq!(lctree.try_link(nodes[0], 3), Err(InvalidNode(3)));
+        assert_eq!(lctree.try_path(nodes[0], nodes[1]).unwrap().sum, 3.0);
+        assert_eq!(
+            lctree.try_findroot(usize::MAX),
+            Err(InvalidNode(usize::MAX))
+        );
+
+        lctree.remove_tree(nodes[2]).unwrap();
+        assert!(!lctree.contains(nodes[2]));
+        assert_eq!(
+            lctree.try_cut(nodes[2], nodes[0]),
+            Err(InvalidNode(nodes[2]))
+        );
+        assert_eq!(
+            lctree.remove_tree(nodes[2]),
+            Err(RemoveError::InvalidNode(nodes[2]))
+        );
+        assert!(lctree.try_cut(nodes[1], nodes[0]).unwrap().is_cut());
+        assert_eq!(lctree.try_connected(nodes[0], nodes[1]), Ok(false));
     }
 
     #[test]
     #[should_panic]
-    pub fn delete_tree() {
+    pub fn delete_tree_unchecked() {
         let mut lctree = LinkCutTree::default();
         let alice = lctree.make_tree(0.0);
         let bob = lctree.make_tree(1.0);
         lctree.link(alice, bob);
-        lctree.remove_tree(alice); // should panic
+        lctree.remove_tree_unchecked(alice); // should panic
+    }
+
+    #[test]
+    pub fn send_and_sync() {
+        // compile-time checks that the public types can be sent to and shared with other threads:
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<LinkCutTree<FindMax>>();
+        assert_send_sync::<LinkCutTree<FindMin, u16>>();
+        assert_send_sync::<LinkCutTree<FindSum, u32>>();
+        assert_send_sync::<crate::LinkCutTreeFixed<FindMax, 16>>();
+        assert_send_sync::<crate::WeightedLinkCutTree<FindMax, FindSum>>();
+        assert_send_sync::<crate::SlidingWindowConnectivity>();
+        assert_send_sync::<crate::TreeGenerator>();
+        assert_send_sync::<crate::ForestSnapshot>();
+        #[cfg(feature = "depth-queries")]
+        assert_send_sync::<crate::VirtualTree<FindSum>>();
+        assert_send_sync::<crate::Reply<usize>>();
+        #[cfg(feature = "concurrent")]
+        {
+            assert_send_sync::<crate::ConcurrentLinkCutTree<FindMax>>();
+            assert_send_sync::<crate::ForestReader<FindMax>>();
+        }
     }
 }
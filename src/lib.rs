@@ -8,22 +8,237 @@
 //!
 //! This crate implements link-cut tree for unrooted trees, which means all of the above operations
 //! can be performed on any two nodes in the forest.
+//! The operations panic on ids that do not refer to a node of the forest, while their checked
+//! variants (`try_link`, `try_cut`, `try_connected`, `try_path`, `try_findroot` and `try_set_weights`)
+//! return an [`InvalidNode`] error instead.
+//! `link` and `cut` report what happened (see [`LinkOutcome`] and [`CutOutcome`]),
+//! while `link_reporting` / `cut_reporting` also keep and report the roots of the trees
+//! (at the cost of finding the root and rerooting the tree back).
+//! `cut_from_parent(v)` cuts a node from its parent without having to know the parent.
+//! `link_all(edges)` / `cut_many(edges)` apply a batch of edges with the outcome of each one.
+//! `link_rooted(child, parent)` attaches the tree rooted at `child` without rerooting it
+//! (or returns a [`NotRoot`] error if `child` is not a root), and
+//! `move_subtree(v, new_parent)` moves the subtree of `v` under another node in one step
+//! (or returns a [`MoveError`] if that would close a cycle).
+//! `transaction(f)` applies several changes atomically, undoing them if `f` fails (see [`Transaction`]).
+//! The pairwise connectivity of a set of nodes is computed at once by `connectivity_matrix(nodes)`
+//! (see [`BitMatrix`]).
 //!
 //! # Path operations
-//! The most common path aggregates are supported: `FindMax`, `FindMin`, and `FindSum`.
-//! A custom path aggregate function can be implemented by using the [Path] trait.
+//! The most common path aggregates are supported: `FindMax`, `FindMin`, and `FindSum`
+//! (they can be compared and printed, and serialized with the `serde` feature).
+//! For `FindMax`, `path_exceeds(v, w, threshold)` checks if any node on a path is above a threshold,
+//! and `path_max_position(v, w)` also reports how far from `v` the maximum is
+//! (requires the `depth-queries` feature).
+//! `for_each_on_path(v, w, f)` visits the nodes of a path in order, and `for_each_on_path_mut`
+//! lets `f` change their weights, repairing the aggregates of the path afterwards.
+//! `path_fold(v, w, init, f)` folds the nodes of a path with a closure, for one-off aggregations.
+//! `path_range_aggregate(v, w, range)` aggregates a segment of a path by the positions of its nodes
+//! (requires the `depth-queries` feature).
+//! Batches of path queries can be answered with `path_many(pairs)`, which shares the work
+//! between the queries with the same first node.
+//! When the same pairs are queried repeatedly between sparse changes, `set_query_cache(true)`
+//! memoizes the results of `path` and `connected` until the forest changes.
+//! A custom path aggregate function can be implemented by using the [Path] trait
+//! (the aggregates only have to be `Clone`, so they may hold heap-allocated data such as a `Vec`,
+//! and reuse it by implementing [`Path::reset`] and [`Path::aggregate_from`]).
+//! Additional aggregates can also be registered at runtime with `register_aggregate(init, combine)`
+//! (or `register_updatable_aggregate` to support the lazy updates, with the `lazy-updates` feature)
+//! and queried with
+//! `path_aggregate(v, w, id)`.
+//!
+//! When the edges carry weights as well, use the [`WeightedLinkCutTree`], where the path queries
+//! aggregate both the node and the edge weights (see [`NodeEdge`]).
+//! Its edges may also carry a payload of any type (e.g. a capacity, a label or a timestamp).
+//!
+//! # Rooted queries
+//! - `reroot(v)`: makes `v` the root of its tree (as the queries between two nodes,
+//!   such as `path(v, w)`, do for their first node).
+//! - `lca(v, w)` and `lca_many(nodes)`: find the lowest common ancestor of nodes
+//!   under the current root of their tree (see `findroot`).
+//! - `parent(v)`: finds the parent of `v`.
+//!
+//! The following queries rely on the sizes of the splay trees (requires the `depth-queries` feature):
+//! - `ancestor(v, k)`: finds the `k`-th ancestor of `v` (a level ancestor query).
+//! - `depth(v)` and `distance(v, w)`: count the edges from `v` to the root and between two nodes.
+//! - `virtual_tree(nodes)`: builds the compressed tree over a set of nodes and their lcas
+//!   (see `VirtualTree`).
+//! - `steiner_aggregate(nodes)`: aggregates the weights of the smallest subtree connecting the nodes.
+//!
+//! # Subtree updates
+//! The lazy updates below require the `lazy-updates` feature (which enables `depth-queries` too).
+//! - `subtree_add(v, delta)`: adds `delta` to the weights of all nodes in the subtree of `v`
+//!   (under the current root) in `O(logn)` amortized time. The aggregate has to implement
+//!   [`PathAdd`], which the built-in aggregates do.
+//!
+//! # Path updates
+//! - `path_add(v, w, delta)`: adds `delta` to the weights of all nodes on the path between `v` and `w`
+//!   in `O(logn)` amortized time (the aggregate has to implement [`PathAdd`] as well).
+//! - `path_assign(v, w, weight)`: sets the weights of all nodes on the path between `v` and `w`
+//!   in `O(logn)` amortized time (the aggregate has to implement [`PathAssign`]).
+//!
+//! The lazy updates panic if an aggregate registered at runtime cannot follow them,
+//! while their checked variants (`try_subtree_add`, `try_path_add` and `try_path_assign`)
+//! return an `UpdateError`.
+//!
+//! # Weight updates
+//! - `set_weight(v, weight)`: sets the weight of `v`, repairing the aggregates on the way.
+//! - `replace_weight(v, weight)`: replaces the weight of `v` and returns the previous one.
+//! - `set_weights(weights)`: replaces the weights of many nodes, repairing the aggregates in bulk.
+//!
+//! # Bulk updates
+//! - `map_weights(f)`: rewrites every weight and recomputes the aggregates in `O(n)`.
+//! - `rebuild_aggregates()`: recomputes all path aggregates in `O(n)`.
+//! - `set_lazy_aggregates(true)`: defers recomputing the path aggregates until they are queried,
+//!   for link/cut-heavy workloads with few queries.
+//! - `begin_bulk_edit()` / `end_bulk_edit()`: suspends maintaining the path aggregates
+//!   during a burst of changes and recomputes them once at the end in `O(n)`.
+//! - `into_aggregate::<Q>()`: converts the forest to another path aggregate type in `O(n)`.
 //!
 //! # Tree creation and removal
 //! Tree nodes are created and removed using the following operations:
 //! - `make_tree()`: creates a new tree containing a single node.
-//! - `remove_tree(v)`: removes the tree containing a single node `v` from the forest.
+//! - `make_tree_default()`: creates a new tree with the default weight of the path aggregate,
+//!   for when only the connectivity matters.
+//! - `remove_tree(v)`: removes the tree containing a single node `v` from the forest
+//!   (or returns a [`RemoveError`] if `v` is still linked to other nodes).
 //! - `extend_forest(weights)`: useful for creating a forest of trees from a vector of weights.
+//! - `from_edges_parallel(weights, edges)`: builds a forest from an edge list on multiple threads
+//!   (requires the `parallel` feature).
+//!
+//! The [`TreeGenerator`] populates a link-cut tree with random trees for a given seed
+//! (uniform trees, random attachment, paths, stars, caterpillars and forests).
+//!
+//! # Introspection
+//! - `node_ids()`: iterates over the ids of the nodes currently in the forest.
+//! - `node(v)`: returns a read-only view of a node (see [`NodeView`]).
+//! - `degree(v)`: counts the edges connected to `v`.
+//! - `neighbors(v)`: iterates over the nodes linked to `v`
+//!   (requires the adjacency tracking, see `set_adjacency_tracking`).
+//! - `dfs(v)`: iterates over the tree containing `v` in depth-first order
+//!   (requires the adjacency tracking, see `set_adjacency_tracking`).
+//! - `bfs(root)`: iterates over the tree rooted at `root` in level order, along with the depths.
+//! - `euler_tour(root)`: lists the enter/exit events of the tree rooted at `root` (see [`TourEvent`]).
+//! - `edges()`: iterates over the edges currently present in the forest.
+//! - `root_of(v)`: finds the root of the tree containing `v` without restructuring the splay trees,
+//!   for code that only has a shared reference.
+//! - `component_size(v)`: counts the nodes in the tree containing `v`
+//!   (this and the following queries require the `component-sizes` feature).
+//! - `subtree_size(v, root)`: counts the nodes in the subtree of `v` when the tree is rooted at `root`.
+//! - `component_id(v)`: identifies the tree containing `v` by a representative node that does not
+//!   change until the tree is linked or cut (unlike `findroot`, which changes when the tree is rerooted).
+//! - `max_component_size()` and `component_size_histogram()`: report the sizes of the trees
+//!   (requires the component tracking, see `set_component_tracking`).
+//! - `preferred_paths()`: lists the preferred paths stored in the auxiliary splay trees.
+//! - `splay_stats()`: reports the shapes of the auxiliary splay trees (see [`SplayStats`]).
+//! - `splay_tree(v)`: lists the auxiliary splay tree of `v` in order with the depths (see [`SplayTree`]).
+//! - `set_instrumentation(enabled)`: collects the actual costs of the operations (see [`CostCounters`]).
+//! - `potential()`: computes the potential function used in the amortized analysis.
+//! - `dump_state(writer)`: writes the internal state of every node and the free ids for debugging.
+//!
+//! # Snapshots and diffs
+//! - `snapshot()`: saves the nodes, weights and edges of the forest (see [`ForestSnapshot`]).
+//! - `diff(other)`: lists the nodes, edges and weights that differ between two forests
+//!   (see [`ForestDiff`]), e.g. to synchronize replicas.
+//! - `write_snapshot(writer)`: saves the forest in a format that is memory-mapped when loaded,
+//!   answering the queries lazily from the file (see `MappedLinkCutTree`, requires the `mmap` feature).
+//!
+//! # Scripts
+//! - `record_script(writer)`: records the operations in a human-readable format (see [`Operation`]).
+//! - `apply_script(reader)`: replays a recorded script, e.g. to reproduce a bug report.
+//! - `write_ahead_log(writer)` / `recover(reader)`: appends every change to a log
+//!   and reconstructs the forest from it after a crash.
+//!
+//! # Import and export
+//! - `from_dimacs(reader)` / `write_dimacs(writer)`: exchanges the forest as a DIMACS edge list.
+//! - `from_json(json)` / `to_json()`: exchanges the forest as a simple JSON document.
+//! - `to_svg()`: renders the forest as an SVG image (requires the `svg` feature).
+//!
+//! # Configuration
+//! Use the [`LinkCutTreeBuilder`] to configure the link-cut tree in one place
+//! (e.g. the path aggregate, the index type, the initial capacity, the instrumentation,
+//! the weight validation that rejects NaN and infinite weights or the reuse of removed ids
+//! and its order, see [`IdReusePolicy`]).
+//! The nodes can also be kept in a custom storage instead of a `Vec` (see [`NodeStorage`]).
+//!
+//! For read-mostly concurrent use, the `ConcurrentLinkCutTree` publishes versions of the forest
+//! that can be queried from other threads without locks (requires the `concurrent` feature).
+//!
+//! For streams of edges that expire after a time window, the [`SlidingWindowConnectivity`]
+//! answers the connectivity queries over the active edges.
+//! When the whole timeline of edge insertions and deletions is known in advance,
+//! the [`OfflineSpanningForest`] answers the weights of the minimum spanning forests over time.
+//!
+//! For async services, the [`ForestWorker`] owns the forest on a worker thread,
+//! applies the commands in order and answers them with futures.
+//!
+//! For real-time use where allocation is forbidden during operation,
+//! use the fixed-capacity [`LinkCutTreeFixed`] instead.
 //!
 //! For further documentation, see the [`LinkCutTree`] struct.
+mod adjacency;
+mod builder;
+mod cache;
+#[cfg(feature = "component-sizes")]
+mod components;
+#[cfg(feature = "concurrent")]
+mod concurrent;
+mod diff;
+mod dimacs;
+mod error;
+mod fixed;
+mod generate;
 mod index;
+mod json;
 mod lctree;
+mod matrix;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod node;
+mod offline;
+mod outcome;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod path;
+mod registry;
+mod script;
 mod splay;
+mod stats;
+mod storage;
+#[cfg(feature = "svg")]
+mod svg;
+mod transaction;
+#[cfg(feature = "depth-queries")]
+mod virtual_tree;
+mod weighted;
+mod window;
+mod worker;
+pub use crate::adjacency::TourEvent;
+pub use crate::builder::LinkCutTreeBuilder;
+#[cfg(feature = "concurrent")]
+pub use crate::concurrent::{ConcurrentLinkCutTree, ForestReader};
+pub use crate::diff::{ForestDiff, ForestSnapshot};
+#[cfg(feature = "lazy-updates")]
+pub use crate::error::UpdateError;
+pub use crate::error::{ImportError, InvalidNode, MoveError, NotRoot, RemoveError, WeightError};
+pub use crate::fixed::LinkCutTreeFixed;
+pub use crate::generate::TreeGenerator;
+pub use crate::index::{IdReusePolicy, IndexType};
 pub use crate::lctree::LinkCutTree;
-pub use path::{FindMax, FindMin, FindSum, Path};
+pub use crate::matrix::BitMatrix;
+#[cfg(feature = "mmap")]
+pub use crate::mmap::MappedLinkCutTree;
+pub use crate::node::{NodeSlot, NodeView, ParentKind};
+pub use crate::offline::OfflineSpanningForest;
+pub use crate::outcome::{CutOutcome, LinkOutcome};
+pub use path::{FindMax, FindMin, FindSum, Path, PathAdd, PathAssign};
+pub use registry::AggregateId;
+pub use script::{Operation, ScriptError};
+pub use stats::{CostCounters, SplayStats, SplayTree};
+pub use storage::NodeStorage;
+pub use transaction::Transaction;
+#[cfg(feature = "depth-queries")]
+pub use virtual_tree::{VirtualEdge, VirtualTree};
+pub use weighted::{NodeEdge, WeightedLinkCutTree};
+pub use window::SlidingWindowConnectivity;
+pub use worker::{ForestHandle, ForestWorker, Reply, ReplyError};
@@ -0,0 +1,119 @@
+use std::fmt;
+
+/// A square matrix of bits, e.g. the pairwise connectivity of a set of nodes
+/// returned by [`LinkCutTree::connectivity_matrix`](crate::LinkCutTree::connectivity_matrix),
+/// stored row by row in 64-bit words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitMatrix {
+    size: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitMatrix {
+    /// Creates a `size` x `size` matrix with all bits unset.
+    #[must_use]
+    pub fn new(size: usize) -> Self {
+        let words_per_row = (size + 63) / 64;
+        Self {
+            size,
+            words_per_row,
+            words: vec![0; size * words_per_row],
+        }
+    }
+
+    /// Returns the number of rows (and columns) of the matrix.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Returns `true` if the matrix has no rows.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Returns the bit at row `i` and column `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    #[must_use]
+    pub fn get(&self, i: usize, j: usize) -> bool {
+        assert!(i < self.size && j < self.size, "get: index out of bounds");
+        self.words[i * self.words_per_row + j / 64] >> (j % 64) & 1 == 1
+    }
+
+    /// Sets the bit at row `i` and column `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn set(&mut self, i: usize, j: usize) {
+        assert!(i < self.size && j < self.size, "set: index out of bounds");
+        self.words[i * self.words_per_row + j / 64] |= 1 << (j % 64);
+    }
+
+    /// Iterates over the columns of the set bits in row `i` in increasing order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn row(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        assert!(i < self.size, "row: index out of bounds");
+        let words = &self.words[i * self.words_per_row..(i + 1) * self.words_per_row];
+        words.iter().enumerate().flat_map(|(k, &word)| {
+            (0..64)
+                .filter(move |bit| word >> bit & 1 == 1)
+                .map(move |bit| 64 * k + bit)
+        })
+    }
+
+    /// Returns the number of set bits.
+    #[must_use]
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+/// Prints the matrix as rows of `0`s and `1`s.
+impl fmt::Display for BitMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for i in 0..self.size {
+            for j in 0..self.size {
+                write!(f, "{}", u8::from(self.get(i, j)))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitMatrix;
+
+    #[test]
+    pub fn bits() {
+        let mut matrix = BitMatrix::new(70);
+        assert_eq!(matrix.len(), 70);
+        matrix.set(1, 2);
+        matrix.set(1, 69);
+        matrix.set(69, 0);
+        assert!(matrix.get(1, 69));
+        assert!(!matrix.get(2, 1));
+        assert_eq!(matrix.row(1).collect::<Vec<_>>(), vec![2, 69]);
+        assert_eq!(matrix.row(0).count(), 0);
+        assert_eq!(matrix.count_ones(), 3);
+
+        let mut matrix = BitMatrix::new(2);
+        matrix.set(0, 0);
+        matrix.set(1, 0);
+        assert_eq!(matrix.to_string(), "10\n10\n");
+        assert!(BitMatrix::new(0).is_empty());
+    }
+}
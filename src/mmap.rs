@@ -0,0 +1,481 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, Write},
+    path::Path as FilePath,
+};
+
+use memmap2::Mmap;
+
+use crate::{
+    index::IndexType,
+    node::{Node, NodeSlot},
+    path::Path,
+    splay::Forest,
+    storage::NodeStorage,
+    LinkCutTree,
+};
+
+// The layout of a snapshot file (all numbers are little-endian):
+// - the magic bytes and the number of node slots `n` (as a `u64`),
+// - `n` records of the parent (as a `u64`, or one of the markers below) and the weight of every node
+//   (as an `f64`), where the edges point towards the roots of the trees.
+const MAGIC: &[u8; 8] = b"LCTSNAP1";
+const HEADER_LEN: usize = 16;
+const RECORD_LEN: usize = 16;
+const ROOT: u64 = u64::MAX;
+const REMOVED: u64 = u64::MAX - 1;
+
+/// # Memory-mapped snapshots
+/// Huge forests can be saved in a format that is loaded by mapping the file into memory
+/// (with the `mmap` feature, see [`MappedLinkCutTree`]).
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+    /// Writes the forest in the snapshot format of [`MappedLinkCutTree`] in `O(n)`
+    /// (the nodes keep their ids, and the weights include the pending subtree additions).
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the writer.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// lctree.extend_forest(&[1.0, 2.0, 3.0]);
+    /// lctree.link(0, 1);
+    ///
+    /// let mut snapshot = Vec::new();
+    /// lctree.write_snapshot(&mut snapshot).unwrap();
+    /// assert_eq!(snapshot.len(), 16 + 3 * 16);
+    /// ```
+    pub fn write_snapshot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let weights = self.effective_weights();
+        let num_slots = weights.len();
+        let mut neighbors = vec![Vec::new(); num_slots];
+        for (v, w) in self.edges() {
+            neighbors[v].push(w);
+            neighbors[w].push(v);
+        }
+
+        // orient the edges towards the first node of every tree:
+        let mut parents = vec![REMOVED; num_slots];
+        for root in self.node_ids() {
+            if parents[root] != REMOVED {
+                continue;
+            }
+            parents[root] = ROOT;
+            let mut stack = vec![root];
+            while let Some(v) = stack.pop() {
+                for &w in &neighbors[v] {
+                    if parents[w] == REMOVED {
+                        parents[w] = v as u64;
+                        stack.push(w);
+                    }
+                }
+            }
+        }
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(num_slots as u64).to_le_bytes())?;
+        for (parent, weight) in parents.into_iter().zip(weights) {
+            writer.write_all(&parent.to_le_bytes())?;
+            writer.write_all(&weight.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+}
+
+/// A link-cut tree loaded from a snapshot file (see [`LinkCutTree::write_snapshot`])
+/// by mapping the file into memory, so opening it takes `O(1)` regardless of the size
+/// of the forest and the operating system pages in only the parts that are queried.
+///
+/// The read-only queries are answered directly from the mapping by following the parents
+/// of the nodes, which takes time proportional to the depths of the nodes in the snapshot.
+/// The first mutation (see [`MappedLinkCutTree::lctree_mut`]) copies the snapshot
+/// into a [`LinkCutTree`] in `O(n)`, and from then on the queries are answered by the link-cut tree
+/// in `O(logn)` amortized time.
+///
+/// # Examples
+/// ```
+/// use lctree::{FindSum, LinkCutTree, MappedLinkCutTree};
+///
+/// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+/// lctree.extend_forest(&[1.0, 2.0, 3.0, 4.0]);
+/// lctree.link(0, 1);
+/// lctree.link(1, 2);
+///
+/// let file = std::env::temp_dir().join("lctree-mapped-doctest.snapshot");
+/// lctree.write_snapshot(std::fs::File::create(&file).unwrap()).unwrap();
+///
+/// // Safety: the file is not modified while it is mapped.
+/// let mut mapped: MappedLinkCutTree<FindSum> = unsafe { MappedLinkCutTree::open(&file) }.unwrap();
+/// assert_eq!(mapped.path(0, 2).sum, 6.0);
+/// assert!(!mapped.connected(2, 3));
+/// assert!(mapped.is_mapped());
+///
+/// // The first mutation copies the snapshot:
+/// mapped.lctree_mut().link(2, 3);
+/// assert!(!mapped.is_mapped());
+/// assert_eq!(mapped.path(0, 3).sum, 10.0);
+/// # std::fs::remove_file(&file).unwrap();
+/// ```
+pub struct MappedLinkCutTree<P: Path, Ix: IndexType = usize> {
+    state: State<P, Ix>,
+}
+
+enum State<P: Path, Ix: IndexType> {
+    Mapped(Mmap),
+    Owned(Box<LinkCutTree<P, Ix>>),
+}
+
+impl<P: Path, Ix: IndexType> MappedLinkCutTree<P, Ix> {
+    /// Maps the snapshot file at `path` into memory, checking only its header and length.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of opening or mapping the file, or an error of kind
+    /// [`io::ErrorKind::InvalidData`] if the file is not a snapshot.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified (by this or another process) while it is mapped,
+    /// which is until the first mutation or until this is dropped,
+    /// and its contents must have been written by [`LinkCutTree::write_snapshot`].
+    pub unsafe fn open<F: AsRef<FilePath>>(path: F) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let mmap = Mmap::map(&file)?;
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        if mmap.len() < HEADER_LEN || &mmap[..MAGIC.len()] != MAGIC {
+            return Err(invalid("not a link-cut tree snapshot"));
+        }
+        let num_slots = usize::try_from(read_u64(&mmap, MAGIC.len()))
+            .map_err(|_| invalid("the snapshot is too large"))?;
+        if num_slots
+            .checked_mul(RECORD_LEN)
+            .and_then(|len| len.checked_add(HEADER_LEN))
+            != Some(mmap.len())
+        {
+            return Err(invalid(
+                "the length of the snapshot does not match its header",
+            ));
+        }
+        Ok(Self {
+            state: State::Mapped(mmap),
+        })
+    }
+
+    /// Returns `true` until the first mutation, while the queries are answered from the mapping.
+    #[must_use]
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.state, State::Mapped(_))
+    }
+
+    /// Returns the number of nodes in the forest (which scans the snapshot in `O(n)` while it is mapped).
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match &self.state {
+            State::Mapped(mmap) => (0..num_slots(mmap))
+                .filter(|&v| parent_of(mmap, v) != REMOVED)
+                .count(),
+            State::Owned(lctree) => lctree.len(),
+        }
+    }
+
+    /// Returns `true` if the forest has no nodes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if `v` is the id of a node in the forest.
+    #[must_use]
+    pub fn contains(&self, v: usize) -> bool {
+        match &self.state {
+            State::Mapped(mmap) => v < num_slots(mmap) && parent_of(mmap, v) != REMOVED,
+            State::Owned(lctree) => lctree.contains(v),
+        }
+    }
+
+    /// Returns the weight of node `v`, or `None` if there is no such node.
+    #[must_use]
+    pub fn weight(&self, v: usize) -> Option<f64> {
+        match &self.state {
+            State::Mapped(mmap) => self.contains(v).then(|| weight_of(mmap, v)),
+            State::Owned(lctree) => lctree.node(v).map(|node| node.weight),
+        }
+    }
+
+    /// Returns the root of the tree containing `v` (see [`LinkCutTree::findroot`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `v` is not a node of the forest.
+    pub fn findroot(&mut self, v: usize) -> usize {
+        match &mut self.state {
+            State::Mapped(mmap) => ancestors(mmap, v).last().unwrap_or(v),
+            State::Owned(lctree) => lctree.findroot(v),
+        }
+    }
+
+    /// Returns `true` if `v` and `w` are in the same tree (see [`LinkCutTree::connected`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `v` or `w` is not a node of the forest.
+    pub fn connected(&mut self, v: usize, w: usize) -> bool {
+        match &mut self.state {
+            State::Mapped(mmap) => ancestors(mmap, v).last() == ancestors(mmap, w).last(),
+            State::Owned(lctree) => lctree.connected(v, w),
+        }
+    }
+
+    /// Aggregates the weights on the path from `v` to `w` (see [`LinkCutTree::path`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `v` or `w` is not a node of the forest.
+    pub fn path(&mut self, v: usize, w: usize) -> P {
+        let mmap = match &mut self.state {
+            State::Mapped(mmap) => mmap,
+            State::Owned(lctree) => return lctree.path(v, w),
+        };
+        let mut from_v: Vec<usize> = ancestors(mmap, v).collect();
+        let mut from_w: Vec<usize> = ancestors(mmap, w).collect();
+        if from_v.last() != from_w.last() {
+            return P::default(f64::INFINITY, usize::MAX);
+        }
+        // drop the common ancestors except for the lowest one (the last node of `from_v`):
+        while from_v.len() >= 2
+            && from_w.len() >= 2
+            && from_v[from_v.len() - 2] == from_w[from_w.len() - 2]
+        {
+            from_v.pop();
+            from_w.pop();
+        }
+        from_w.pop();
+        let mut nodes = from_v.into_iter().chain(from_w.into_iter().rev());
+        let first = nodes.next().unwrap_or(v);
+        let mut path = P::default(weight_of(mmap, first), first);
+        for node in nodes {
+            path.aggregate(P::default(weight_of(mmap, node), node));
+        }
+        path
+    }
+
+    /// Returns the link-cut tree for the mutations, copying the snapshot on the first call in `O(n)`.
+    /// The snapshot file is unmapped afterwards.
+    pub fn lctree_mut(&mut self) -> &mut LinkCutTree<P, Ix> {
+        if let State::Mapped(mmap) = &self.state {
+            self.state = State::Owned(Box::new(materialize(mmap)));
+        }
+        match &mut self.state {
+            State::Owned(lctree) => lctree,
+            State::Mapped(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the link-cut tree, copying the snapshot if there were no mutations yet.
+    #[must_use]
+    pub fn into_lctree(mut self) -> LinkCutTree<P, Ix> {
+        self.lctree_mut();
+        match self.state {
+            State::Owned(lctree) => *lctree,
+            State::Mapped(_) => unreachable!(),
+        }
+    }
+}
+
+fn read_u64(mmap: &Mmap, offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&mmap[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn num_slots(mmap: &Mmap) -> usize {
+    (mmap.len() - HEADER_LEN) / RECORD_LEN
+}
+
+fn parent_of(mmap: &Mmap, v: usize) -> u64 {
+    read_u64(mmap, HEADER_LEN + v * RECORD_LEN)
+}
+
+fn weight_of(mmap: &Mmap, v: usize) -> f64 {
+    f64::from_bits(read_u64(mmap, HEADER_LEN + v * RECORD_LEN + 8))
+}
+
+// Iterates over `v` and its ancestors in the snapshot, ending with the root of its tree.
+fn ancestors(mmap: &Mmap, v: usize) -> impl Iterator<Item = usize> + '_ {
+    assert!(
+        v < num_slots(mmap) && parent_of(mmap, v) != REMOVED,
+        "the snapshot has no node {v}"
+    );
+    let mut steps = 0;
+    std::iter::successors(Some(v), move |&v| {
+        steps += 1;
+        assert!(steps <= num_slots(mmap), "the snapshot is corrupted");
+        match parent_of(mmap, v) {
+            ROOT => None,
+            parent => usize::try_from(parent).ok(),
+        }
+    })
+}
+
+// Builds a link-cut tree from the snapshot, with every node alone in its splay tree
+// and hanging off its parent by a path-parent pointer (as in `from_edges_parallel`).
+fn materialize<P: Path, Ix: IndexType>(mmap: &Mmap) -> LinkCutTree<P, Ix> {
+    let num_slots = num_slots(mmap);
+    let parents: Vec<Option<usize>> = (0..num_slots)
+        .map(|v| match parent_of(mmap, v) {
+            ROOT | REMOVED => None,
+            parent => usize::try_from(parent).ok(),
+        })
+        .collect();
+
+    // count the sizes of the subtrees from the leaves up:
+    let mut children = vec![0; num_slots];
+    for &parent in parents.iter().flatten() {
+        children[parent] += 1;
+    }
+    let mut pending = children.clone();
+    let mut sizes = vec![1; num_slots];
+    let mut leaves: VecDeque<usize> = (0..num_slots).filter(|&v| pending[v] == 0).collect();
+    while let Some(v) = leaves.pop_front() {
+        if let Some(parent) = parents[v] {
+            sizes[parent] += sizes[v];
+            pending[parent] -= 1;
+            if pending[parent] == 0 {
+                leaves.push_back(parent);
+            }
+        }
+    }
+    assert!(pending.iter().all(|&n| n == 0), "the snapshot is corrupted");
+
+    let nodes = (0..num_slots)
+        .map(|v| {
+            let degree = children[v] + usize::from(parents[v].is_some());
+            Node::rooted(v, weight_of(mmap, v), parents[v], degree, sizes[v])
+        })
+        .collect();
+    let mut lctree = LinkCutTree::from_forest(Forest::from_nodes(nodes));
+    for v in (0..num_slots).filter(|&v| parent_of(mmap, v) == REMOVED) {
+        lctree.remove_tree_unchecked(v);
+    }
+    lctree
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io};
+
+    use super::MappedLinkCutTree;
+    use crate::{FindMax, FindSum, LinkCutTree};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    pub fn same_as_original() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let num_nodes = 300;
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        lctree.extend((0..num_nodes).map(|_| rng.gen_range(0.0..100.0)));
+        for v in 1..num_nodes {
+            if v % 7 != 0 {
+                lctree.link(rng.gen_range(0..v), v);
+            }
+        }
+        lctree.subtree_add(5, 1.5);
+        let edges: Vec<(usize, usize)> = lctree
+            .edges()
+            .filter(|&(v, w)| v == 13 || w == 13)
+            .collect();
+        lctree.cut_many(&edges);
+        lctree.remove_tree(13).unwrap();
+
+        let file = std::env::temp_dir().join("lctree-same-as-original.snapshot");
+        lctree
+            .write_snapshot(fs::File::create(&file).unwrap())
+            .unwrap();
+        let mut mapped: MappedLinkCutTree<FindMax> =
+            unsafe { MappedLinkCutTree::open(&file) }.unwrap();
+        assert_eq!(mapped.len(), num_nodes - 1);
+        assert!(!mapped.contains(13));
+        assert_eq!(mapped.weight(13), None);
+
+        let mut pairs = Vec::new();
+        for _ in 0..200 {
+            let v = rng.gen_range(0..num_nodes);
+            let w = rng.gen_range(0..num_nodes);
+            if v != 13 && w != 13 {
+                pairs.push((v, w));
+            }
+        }
+        for &(v, w) in &pairs {
+            assert_eq!(mapped.weight(v), lctree.node(v).map(|node| node.weight));
+            assert_eq!(mapped.connected(v, w), lctree.connected(v, w));
+            let (expected, actual) = (lctree.path(v, w), mapped.path(v, w));
+            assert_eq!((actual.idx, actual.weight), (expected.idx, expected.weight));
+        }
+        assert!(mapped.is_mapped());
+
+        // the copy supports the same queries and the mutations:
+        mapped.lctree_mut();
+        assert!(!mapped.is_mapped());
+        assert_eq!(mapped.len(), num_nodes - 1);
+        for &(v, w) in &pairs {
+            assert_eq!(mapped.connected(v, w), lctree.connected(v, w));
+            let (expected, actual) = (lctree.path(v, w), mapped.path(v, w));
+            assert_eq!((actual.idx, actual.weight), (expected.idx, expected.weight));
+        }
+        let mut copy = mapped.into_lctree();
+        assert_eq!(copy.snapshot(), lctree.snapshot());
+        assert_eq!(copy.make_tree(0.0), 13);
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    pub fn path_order() {
+        // the path aggregates are combined from `v` to `w` as in the link-cut tree:
+        #[derive(Clone)]
+        struct Nodes(Vec<usize>);
+        impl crate::Path for Nodes {
+            fn default(_: f64, index: usize) -> Self {
+                Nodes(vec![index])
+            }
+            fn aggregate(&mut self, other: Self) {
+                self.0.extend(other.0);
+            }
+        }
+        let mut lctree: LinkCutTree<Nodes> = LinkCutTree::new();
+        lctree.extend([0.0; 6]);
+        lctree.link_all(&[(1, 0), (2, 1), (3, 1), (4, 0), (5, 4)]);
+        let mut snapshot = Vec::new();
+        lctree.write_snapshot(&mut snapshot).unwrap();
+        let file = std::env::temp_dir().join("lctree-path-order.snapshot");
+        fs::write(&file, snapshot).unwrap();
+
+        let mut mapped: MappedLinkCutTree<Nodes> =
+            unsafe { MappedLinkCutTree::open(&file) }.unwrap();
+        assert_eq!(mapped.path(2, 5).0, vec![2, 1, 0, 4, 5]);
+        assert_eq!(mapped.path(0, 3).0, vec![0, 1, 3]);
+        assert_eq!(mapped.path(3, 2).0, vec![3, 1, 2]);
+        assert_eq!(mapped.path(4, 4).0, vec![4]);
+        assert_eq!(mapped.findroot(5), 0);
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    pub fn invalid_files() {
+        let file = std::env::temp_dir().join("lctree-invalid-files.snapshot");
+        for contents in [
+            &b"LCTSNAP0\0\0\0\0\0\0\0\0"[..],
+            b"LCTSNAP1\x01\0\0\0\0\0\0\0",
+        ] {
+            fs::write(&file, contents).unwrap();
+            let err = unsafe { MappedLinkCutTree::<FindSum>::open(&file) }
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+        fs::remove_file(&file).unwrap();
+    }
+}
@@ -1,54 +1,580 @@
-use crate::path::Path;
+use crate::{index::IndexType, path::Path};
 
 #[derive(Copy, Clone)]
-pub enum Parent {
+pub(crate) enum Parent {
     Node(usize), // parent node in the tree
     Path(usize), // path to the root in the forest
     Root,        // root of the tree
 }
 
-pub struct Node<T: Path> {
-    pub idx: usize,
-    pub left: Option<usize>,
-    pub right: Option<usize>,
-    pub parent: Parent,
-    pub flipped: bool,
-    // for path aggregation:
-    pub weight: f64,
-    pub path: T,
+// The kind of the parent pointer is stored separately from the parent's index (so that
+// the index can be packed into the index type), in a flags byte shared with the `flipped`,
+// `anchor`, `dirty` and `assigned` flags:
+const PARENT_NODE: u8 = 0b00;
+const PARENT_PATH: u8 = 0b01;
+const PARENT_ROOT: u8 = 0b10;
+const PARENT_KIND: u8 = 0b11;
+const FLIPPED: u8 = 0b100;
+#[cfg(feature = "component-sizes")]
+const ANCHOR: u8 = 0b1000;
+const DIRTY: u8 = 0b1_0000;
+#[cfg(feature = "lazy-updates")]
+const ASSIGNED: u8 = 0b10_0000;
+
+// Children and parents are stored as raw indices, where the largest value of the index type
+// is reserved as a sentinel for "no node" (instead of `Option` which would need extra padding).
+// The id of a node is its position in the storage, so it is not stored in the node itself.
+pub(crate) struct Node<T: Path, Ix: IndexType> {
+    left: Ix,
+    right: Ix,
+    parent: Ix,
+    flags: u8, // the parent kind and the `flipped`, `anchor`, `dirty` and `assigned` flags (see above)
+    // for path aggregation: `weight` is the only copy of the node's own weight, while `path`
+    // aggregates the whole splay subtree (e.g. the `weight` of `FindMax` is the subtree maximum),
+    // so the node's weight cannot be recovered from it in general:
+    pub(crate) weight: f64,
+    pub(crate) path: T,
     // for deletion (the number of edges connected to this node):
-    pub degree: usize,
+    #[cfg(feature = "degree-tracking")]
+    degree: Ix,
+    // for depths and subtree updates (the number of nodes in the splay subtree):
+    #[cfg(feature = "depth-queries")]
+    size: Ix,
+    // for subtree and path updates:
+    #[cfg(feature = "lazy-updates")]
+    pub(crate) delta: f64, // pending addition to the weights of the splay subtree (excluding this node)
+    #[cfg(feature = "lazy-updates")]
+    pub(crate) path_delta: f64, // the same, but not added to the trees hanging off the splay subtree
+    #[cfg(feature = "lazy-updates")]
+    assigned: f64, // pending weight of the splay subtree (excluding this node) if `ASSIGNED` is set
+    #[cfg(feature = "lazy-updates")]
+    pub(crate) virtual_delta: Sum, // total addition to the subtrees hanging off this node by path-parent pointers
+    #[cfg(feature = "lazy-updates")]
+    pub(crate) virtual_seen: Sum, // the `virtual_delta` of the path-parent that was already applied
+    // for component sizes:
+    #[cfg(feature = "component-sizes")]
+    tree_size: Ix, // the number of nodes in the splay subtree and the trees hanging off it
+    #[cfg(feature = "component-sizes")]
+    virtual_size: Ix, // the number of nodes in the trees hanging off this node by path-parent pointers
+    // for component ids (every tree has exactly one anchor, whose id + 1 is summed up like the sizes):
+    #[cfg(feature = "component-sizes")]
+    tree_anchor: Ix,
+    #[cfg(feature = "component-sizes")]
+    virtual_anchor: Ix,
+}
+
+// A sum of additions along with its rounding error, so that the additions made since an earlier
+// value of the sum can be recovered without losing the small ones to the rounding of a large sum.
+#[cfg(feature = "lazy-updates")]
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct Sum {
+    total: f64,
+    error: f64,
+}
+
+#[cfg(feature = "lazy-updates")]
+impl Sum {
+    pub(crate) fn add(&mut self, delta: f64) {
+        // the rounding error of the addition (Knuth's two-sum):
+        let total = self.total + delta;
+        let rounded = total - self.total;
+        self.error += (self.total - (total - rounded)) + (delta - rounded);
+        self.total = total;
+    }
+
+    // The sum of the additions made since the sum was `earlier`.
+    pub(crate) fn since(self, earlier: Sum) -> f64 {
+        (self.total - earlier.total) + (self.error - earlier.error)
+    }
+}
+
+#[inline]
+fn pack<Ix: IndexType>(idx: Option<usize>) -> Ix {
+    idx.map_or_else(<Ix as IndexType>::max, Ix::new)
+}
+
+#[inline]
+fn unpack<Ix: IndexType>(idx: Ix) -> Option<usize> {
+    if idx == <Ix as IndexType>::max() {
+        None
+    } else {
+        Some(idx.index())
+    }
 }
 
-impl<T: Path> Node<T> {
-    pub fn new(idx: usize, weight: f64) -> Self {
+impl<T: Path, Ix: IndexType> Node<T, Ix> {
+    pub(crate) fn new(idx: usize, weight: f64) -> Self {
         Node {
-            idx,
-            left: None,
-            right: None,
-            parent: Parent::Root,
-            flipped: false,
+            left: <Ix as IndexType>::max(),
+            right: <Ix as IndexType>::max(),
+            parent: <Ix as IndexType>::max(),
+            #[cfg(feature = "component-sizes")]
+            flags: PARENT_ROOT | ANCHOR,
+            #[cfg(not(feature = "component-sizes"))]
+            flags: PARENT_ROOT,
             weight,
             path: T::default(weight, idx),
-            degree: 0,
+            #[cfg(feature = "degree-tracking")]
+            degree: Ix::new(0),
+            #[cfg(feature = "depth-queries")]
+            size: Ix::new(1),
+            #[cfg(feature = "lazy-updates")]
+            delta: 0.0,
+            #[cfg(feature = "lazy-updates")]
+            path_delta: 0.0,
+            #[cfg(feature = "lazy-updates")]
+            assigned: 0.0,
+            #[cfg(feature = "lazy-updates")]
+            virtual_delta: Sum::default(),
+            #[cfg(feature = "lazy-updates")]
+            virtual_seen: Sum::default(),
+            #[cfg(feature = "component-sizes")]
+            tree_size: Ix::new(1),
+            #[cfg(feature = "component-sizes")]
+            virtual_size: Ix::new(0),
+            #[cfg(feature = "component-sizes")]
+            tree_anchor: Ix::new(idx + 1),
+            #[cfg(feature = "component-sizes")]
+            virtual_anchor: Ix::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn left(&self) -> Option<usize> {
+        unpack(self.left)
+    }
+
+    #[inline]
+    pub(crate) fn set_left(&mut self, left: Option<usize>) {
+        self.left = pack(left);
+    }
+
+    #[inline]
+    pub(crate) fn right(&self) -> Option<usize> {
+        unpack(self.right)
+    }
+
+    #[inline]
+    pub(crate) fn set_right(&mut self, right: Option<usize>) {
+        self.right = pack(right);
+    }
+
+    #[inline]
+    pub(crate) fn parent(&self) -> Parent {
+        match self.flags & PARENT_KIND {
+            PARENT_NODE => Parent::Node(self.parent.index()),
+            PARENT_PATH => Parent::Path(self.parent.index()),
+            _ => Parent::Root,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_parent(&mut self, parent: Parent) {
+        let (kind, idx) = match parent {
+            Parent::Node(idx) => (PARENT_NODE, Ix::new(idx)),
+            Parent::Path(idx) => (PARENT_PATH, Ix::new(idx)),
+            Parent::Root => (PARENT_ROOT, <Ix as IndexType>::max()),
+        };
+        self.flags = (self.flags & !PARENT_KIND) | kind;
+        self.parent = idx;
+    }
+
+    // Whether the children of this node have to be swapped (lazily reversing its subtree).
+    #[inline]
+    pub(crate) fn flipped(&self) -> bool {
+        self.flags & FLIPPED != 0
+    }
+
+    #[inline]
+    pub(crate) fn set_flipped(&mut self, flipped: bool) {
+        if flipped {
+            self.flags |= FLIPPED;
+        } else {
+            self.flags &= !FLIPPED;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn toggle_flipped(&mut self) {
+        self.flags ^= FLIPPED;
+    }
+
+    // Whether this node is the anchor of its represented tree (see `LinkCutTree::component_id`).
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn anchor(&self) -> bool {
+        self.flags & ANCHOR != 0
+    }
+
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn set_anchor(&mut self, anchor: bool) {
+        if anchor {
+            self.flags |= ANCHOR;
+        } else {
+            self.flags &= !ANCHOR;
+        }
+    }
+
+    // Whether the path aggregate of this node is out of date (see `Forest::set_lazy_aggregates`).
+    #[inline]
+    pub(crate) fn dirty(&self) -> bool {
+        self.flags & DIRTY != 0
+    }
+
+    #[inline]
+    pub(crate) fn set_dirty(&mut self, dirty: bool) {
+        if dirty {
+            self.flags |= DIRTY;
+        } else {
+            self.flags &= !DIRTY;
+        }
+    }
+
+    // The weight assigned to the splay subtree (excluding this node) that was not pushed down yet.
+    // It is applied after the pending `delta` and before the pending `path_delta`.
+    #[inline]
+    #[cfg(feature = "lazy-updates")]
+    pub(crate) fn assigned(&self) -> Option<f64> {
+        if self.flags & ASSIGNED != 0 {
+            Some(self.assigned)
+        } else {
+            None
         }
     }
 
-    pub fn flip_children(&mut self) {
+    #[inline]
+    #[cfg(feature = "lazy-updates")]
+    pub(crate) fn set_assigned(&mut self, assigned: Option<f64>) {
+        match assigned {
+            Some(weight) => {
+                self.flags |= ASSIGNED;
+                self.assigned = weight;
+            }
+            None => self.flags &= !ASSIGNED,
+        }
+    }
+
+    #[inline]
+    #[cfg(feature = "degree-tracking")]
+    pub(crate) fn degree(&self) -> usize {
+        self.degree.index()
+    }
+
+    #[inline]
+    #[cfg(feature = "degree-tracking")]
+    pub(crate) fn set_degree(&mut self, degree: usize) {
+        self.degree = Ix::new(degree);
+    }
+
+    #[inline]
+    #[cfg(feature = "depth-queries")]
+    pub(crate) fn size(&self) -> usize {
+        self.size.index()
+    }
+
+    #[inline]
+    #[cfg(feature = "depth-queries")]
+    pub(crate) fn set_size(&mut self, size: usize) {
+        self.size = Ix::new(size);
+    }
+
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn tree_size(&self) -> usize {
+        self.tree_size.index()
+    }
+
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn set_tree_size(&mut self, tree_size: usize) {
+        self.tree_size = Ix::new(tree_size);
+    }
+
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn virtual_size(&self) -> usize {
+        self.virtual_size.index()
+    }
+
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn set_virtual_size(&mut self, virtual_size: usize) {
+        self.virtual_size = Ix::new(virtual_size);
+    }
+
+    // The id + 1 of the anchor in the splay subtree and the trees hanging off it (0 if none).
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn tree_anchor(&self) -> usize {
+        self.tree_anchor.index()
+    }
+
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn set_tree_anchor(&mut self, tree_anchor: usize) {
+        self.tree_anchor = Ix::new(tree_anchor);
+    }
+
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn virtual_anchor(&self) -> usize {
+        self.virtual_anchor.index()
+    }
+
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub(crate) fn set_virtual_anchor(&mut self, virtual_anchor: usize) {
+        self.virtual_anchor = Ix::new(virtual_anchor);
+    }
+
+    // Creates a node that is alone in its splay tree and hangs off its parent in the represented
+    // tree by a path-parent pointer, with `size` nodes in its represented subtree (a forest where
+    // every node is built like this is valid; the roots are the anchors of their trees).
+    #[cfg(any(feature = "parallel", feature = "mmap"))]
+    pub(crate) fn rooted(
+        idx: usize,
+        weight: f64,
+        parent: Option<usize>,
+        degree: usize,
+        size: usize,
+    ) -> Self {
+        let mut node = Node::new(idx, weight);
+        if let Some(parent) = parent {
+            node.set_parent(Parent::Path(parent));
+            #[cfg(feature = "component-sizes")]
+            {
+                node.set_anchor(false);
+                node.set_tree_anchor(0);
+            }
+        }
+        #[cfg(feature = "degree-tracking")]
+        node.set_degree(degree);
+        #[cfg(not(feature = "degree-tracking"))]
+        let _ = degree;
+        #[cfg(feature = "component-sizes")]
+        {
+            node.set_tree_size(size);
+            node.set_virtual_size(size - 1);
+        }
+        #[cfg(not(feature = "component-sizes"))]
+        let _ = size;
+        node
+    }
+
+    // Converts the node `idx` to another path aggregate type, preserving its links and weight.
+    // The aggregate is reset to the node's own weight and has to be recomputed.
+    pub(crate) fn to_aggregate<Q: Path>(&self, idx: usize) -> Node<Q, Ix> {
+        Node {
+            left: self.left,
+            right: self.right,
+            parent: self.parent,
+            flags: self.flags,
+            weight: self.weight,
+            path: Q::default(self.weight, idx),
+            #[cfg(feature = "degree-tracking")]
+            degree: self.degree,
+            #[cfg(feature = "depth-queries")]
+            size: self.size,
+            #[cfg(feature = "lazy-updates")]
+            delta: self.delta,
+            #[cfg(feature = "lazy-updates")]
+            path_delta: self.path_delta,
+            #[cfg(feature = "lazy-updates")]
+            assigned: self.assigned,
+            #[cfg(feature = "lazy-updates")]
+            virtual_delta: self.virtual_delta,
+            #[cfg(feature = "lazy-updates")]
+            virtual_seen: self.virtual_seen,
+            #[cfg(feature = "component-sizes")]
+            tree_size: self.tree_size,
+            #[cfg(feature = "component-sizes")]
+            virtual_size: self.virtual_size,
+            #[cfg(feature = "component-sizes")]
+            tree_anchor: self.tree_anchor,
+            #[cfg(feature = "component-sizes")]
+            virtual_anchor: self.virtual_anchor,
+        }
+    }
+
+    pub(crate) fn flip_children(&mut self) {
         std::mem::swap(&mut self.left, &mut self.right);
     }
 
     #[allow(dead_code)]
     #[cfg(not(tarpaulin_include))]
-    pub fn to_str(&self) -> String {
-        let parent = match self.parent {
+    pub(crate) fn to_str(&self) -> String {
+        let parent = match self.parent() {
             Parent::Node(idx) => format!("Node({idx})"),
             Parent::Path(idx) => format!("Path({idx})"),
             Parent::Root => "Root".to_string(),
         };
         format!(
-            "Node {{ idx: {}, left: {:?}, right: {:?}, parent: {parent:?}}}",
-            self.idx, self.left, self.right
+            "Node {{ left: {:?}, right: {:?}, parent: {parent:?}}}",
+            self.left(),
+            self.right()
         )
     }
 }
+
+/// A slot of a [`NodeStorage`](crate::NodeStorage), which holds a node of a link-cut tree.
+/// Its contents are private to the link-cut tree, so a storage can only keep and move the slots.
+pub struct NodeSlot<T: Path, Ix: IndexType>(pub(crate) Node<T, Ix>);
+
+/// A read-only view of a node of the forest, returned by [`LinkCutTree::node`](crate::LinkCutTree::node).
+///
+/// Besides the node's own data, it shows where the node currently is in the auxiliary splay trees,
+/// which changes with every operation (see [`LinkCutTree::splay_tree`](crate::LinkCutTree::splay_tree)).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeView<P: Path> {
+    pub id: usize,
+    /// The weight of the node, including the pending additions of `subtree_add`.
+    pub weight: f64,
+    /// The number of edges connected to the node in the represented tree
+    /// (counted in `O(n)` without the `degree-tracking` feature).
+    pub degree: usize,
+    /// The parent of the node in the auxiliary splay trees.
+    pub parent: ParentKind,
+    /// The aggregate of the node's splay subtree (i.e. of a part of its preferred path).
+    pub aggregate: P,
+}
+
+/// The kind of the parent pointer of a node in the auxiliary splay trees (see [`NodeView`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParentKind {
+    /// The parent of the node in its splay tree.
+    Node(usize),
+    /// The node is the root of its splay tree, whose path hangs off the given node
+    /// of the represented tree (the path-parent pointer).
+    Path(usize),
+    /// The node is the root of the splay tree that holds the path to the root of its tree.
+    Root,
+}
+
+impl From<Parent> for ParentKind {
+    fn from(parent: Parent) -> Self {
+        match parent {
+            Parent::Node(idx) => ParentKind::Node(idx),
+            Parent::Path(idx) => ParentKind::Path(idx),
+            Parent::Root => ParentKind::Root,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Node, Parent};
+    use crate::path::FindSum;
+
+    #[test]
+    pub fn packed_links() {
+        let mut node: Node<FindSum, u16> = Node::new(3, 1.0);
+        assert_eq!(node.left(), None);
+        assert_eq!(node.right(), None);
+        assert!(matches!(node.parent(), Parent::Root));
+
+        node.set_left(Some(0));
+        node.set_right(Some(65534));
+        node.set_parent(Parent::Path(7));
+        assert_eq!(node.left(), Some(0));
+        assert_eq!(node.right(), Some(65534));
+        assert!(matches!(node.parent(), Parent::Path(7)));
+
+        node.flip_children();
+        assert_eq!(node.left(), Some(65534));
+        assert_eq!(node.right(), Some(0));
+    }
+
+    #[test]
+    pub fn packed_flags() {
+        // the parent kind and the flipped flag share a byte without overwriting each other:
+        let mut node: Node<FindSum, u16> = Node::new(3, 1.0);
+        assert!(!node.flipped());
+        node.toggle_flipped();
+        node.set_parent(Parent::Node(5));
+        assert!(node.flipped());
+        assert!(matches!(node.parent(), Parent::Node(5)));
+
+        node.set_parent(Parent::Root);
+        assert!(node.flipped());
+        assert!(matches!(node.parent(), Parent::Root));
+        node.set_flipped(false);
+        node.set_parent(Parent::Path(2));
+        assert!(!node.flipped());
+        assert!(matches!(node.parent(), Parent::Path(2)));
+
+        #[cfg(feature = "component-sizes")]
+        {
+            assert!(node.anchor());
+            node.set_anchor(false);
+            node.toggle_flipped();
+            assert!(!node.anchor());
+            assert!(node.flipped());
+            node.toggle_flipped();
+        }
+
+        #[cfg(feature = "lazy-updates")]
+        {
+            assert_eq!(node.assigned(), None);
+            node.set_assigned(Some(0.0));
+            assert_eq!(node.assigned(), Some(0.0));
+            assert!(!node.flipped() && matches!(node.parent(), Parent::Path(2)));
+            node.set_assigned(None);
+            assert_eq!(node.assigned(), None);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "lazy-updates")]
+    pub fn exact_sums() {
+        let mut sum = super::Sum::default();
+        sum.add(1e17);
+        let earlier = sum;
+        sum.add(1.0); // lost to the rounding of the total
+        sum.add(2.0);
+        assert_eq!(sum.since(earlier), 3.0);
+        sum.add(-1e17);
+        assert_eq!(sum.since(super::Sum::default()), 3.0);
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "degree-tracking",
+        not(feature = "depth-queries"),
+        not(feature = "component-sizes")
+    ))]
+    pub fn node_size() {
+        use std::mem::size_of;
+
+        // 3 ids + degree, the flags, the weight and the aggregate:
+        assert_eq!(size_of::<Node<FindSum, u32>>(), 40);
+        assert_eq!(size_of::<Node<FindSum, usize>>(), 56);
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "degree-tracking",
+        feature = "lazy-updates",
+        feature = "component-sizes"
+    ))]
+    pub fn node_size_with_all_features() {
+        use std::mem::size_of;
+
+        // and the splay subtree size, the update tags, 2 component sizes and 2 anchors:
+        assert_eq!(size_of::<Node<FindSum, u32>>(), 112);
+        assert_eq!(size_of::<Node<FindSum, usize>>(), 152);
+    }
+
+    #[test]
+    #[cfg(not(any(
+        feature = "degree-tracking",
+        feature = "depth-queries",
+        feature = "component-sizes"
+    )))]
+    pub fn node_size_without_degrees() {
+        use std::mem::size_of;
+
+        assert_eq!(size_of::<Node<FindSum, u32>>(), 32);
+        assert_eq!(size_of::<Node<FindSum, usize>>(), 48);
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use crate::{path::FindMax, LinkCutTree};
+
+/// Answers the weight of the minimum spanning forest of a graph after a timeline
+/// of edge insertions and deletions, where all the operations are known in advance.
+///
+/// The timeline is recorded with `insert_edge`, `delete_edge` and `query`, and then
+/// `solve` divides the queries over time: every edge is added to the `O(logq)` ranges of queries
+/// during which it is active, and the ranges are visited depth-first while a link-cut tree
+/// maintains the minimum spanning forest of the edges of the enclosing ranges, undoing
+/// the changes of each range on the way back. So solving takes `O(mlogqlogn)` amortized time
+/// for `m` edges and `q` queries.
+///
+/// # Examples
+/// ```
+/// use lctree::OfflineSpanningForest;
+///
+/// let mut timeline = OfflineSpanningForest::new(3);
+/// timeline.insert_edge(0, 1, 4.0);
+/// timeline.insert_edge(1, 2, 2.0);
+/// timeline.query();
+/// timeline.insert_edge(0, 2, 1.0); // replaces (0, 1) in the spanning forest
+/// timeline.query();
+/// timeline.delete_edge(1, 2);
+/// timeline.query();
+/// assert_eq!(timeline.solve(), vec![6.0, 3.0, 5.0]);
+/// ```
+pub struct OfflineSpanningForest {
+    num_vertices: usize,
+    // The endpoints and weights of the edges in insertion order, along with the range of
+    // queries during which they are active (the end is `usize::MAX` until they are deleted):
+    edges: Vec<(usize, usize, f64, usize, usize)>,
+    // The active edges between each pair of vertices (the last one is deleted first):
+    active: HashMap<(usize, usize), Vec<usize>>,
+    num_queries: usize,
+}
+
+impl OfflineSpanningForest {
+    /// Creates an empty timeline over `num_vertices` vertices (with ids `0..num_vertices`).
+    #[must_use]
+    pub fn new(num_vertices: usize) -> Self {
+        Self {
+            num_vertices,
+            edges: Vec::new(),
+            active: HashMap::new(),
+            num_queries: 0,
+        }
+    }
+
+    /// Inserts an edge between vertices `u` and `v` with the given weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex does not exist or if the weight is NaN or infinite.
+    pub fn insert_edge(&mut self, u: usize, v: usize, weight: f64) {
+        assert!(
+            u < self.num_vertices && v < self.num_vertices,
+            "insert_edge: no such vertex"
+        );
+        assert!(weight.is_finite(), "insert_edge: the weight must be finite");
+        let edge = self.edges.len();
+        self.edges
+            .push((u, v, weight, self.num_queries, usize::MAX));
+        self.active
+            .entry((u.min(v), u.max(v)))
+            .or_default()
+            .push(edge);
+    }
+
+    /// Deletes the most recently inserted edge between vertices `u` and `v`.
+    /// Returns `false` if there is no such edge.
+    #[allow(clippy::must_use_candidate)]
+    pub fn delete_edge(&mut self, u: usize, v: usize) -> bool {
+        let edges = match self.active.get_mut(&(u.min(v), u.max(v))) {
+            Some(edges) => edges,
+            None => return false,
+        };
+        let edge = edges.pop();
+        if edges.is_empty() {
+            self.active.remove(&(u.min(v), u.max(v)));
+        }
+        match edge {
+            Some(edge) => {
+                self.edges[edge].4 = self.num_queries;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Asks for the weight of the minimum spanning forest of the edges inserted and not deleted
+    /// so far, and returns the index of the query in the results of `solve`.
+    #[allow(clippy::must_use_candidate)]
+    pub fn query(&mut self) -> usize {
+        self.num_queries += 1;
+        self.num_queries - 1
+    }
+
+    /// Returns the number of queries in the timeline.
+    #[must_use]
+    pub fn num_queries(&self) -> usize {
+        self.num_queries
+    }
+
+    /// Computes the answers to the queries in order.
+    #[must_use]
+    pub fn solve(&self) -> Vec<f64> {
+        let num_queries = self.num_queries;
+        if num_queries == 0 {
+            return Vec::new();
+        }
+        let mut ranges = vec![Vec::new(); 4 * num_queries];
+        for (edge, &(_, _, _, start, end)) in self.edges.iter().enumerate() {
+            let end = end.min(num_queries);
+            if start < end {
+                add_to_ranges(&mut ranges, 1, 0, num_queries, start, end, edge);
+            }
+        }
+
+        // The vertices are the first nodes of the link-cut tree, followed by a node for every edge
+        // weighted by the weight of the edge (so the vertices never win the path maximum):
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        for _ in 0..self.num_vertices {
+            lctree.make_tree(f64::NEG_INFINITY);
+        }
+        for &(_, _, weight, _, _) in &self.edges {
+            lctree.make_tree(weight);
+        }
+        let mut solver = Solver {
+            lctree,
+            edges: &self.edges,
+            num_vertices: self.num_vertices,
+            ranges,
+            total: 0.0,
+            changes: Vec::new(),
+            answers: vec![0.0; num_queries],
+        };
+        solver.visit(1, 0, num_queries);
+        solver.answers
+    }
+}
+
+// Adds an edge to the ranges of the segment tree covering the queries `start..end`.
+fn add_to_ranges(
+    ranges: &mut [Vec<usize>],
+    range: usize,
+    lo: usize,
+    hi: usize,
+    start: usize,
+    end: usize,
+    edge: usize,
+) {
+    if start <= lo && hi <= end {
+        ranges[range].push(edge);
+        return;
+    }
+    let mid = (lo + hi) / 2;
+    if start < mid {
+        add_to_ranges(ranges, 2 * range, lo, mid, start, end, edge);
+    }
+    if mid < end {
+        add_to_ranges(ranges, 2 * range + 1, mid, hi, start, end, edge);
+    }
+}
+
+// A change to the spanning forest, which is undone by the inverse link and cut operations.
+enum Change {
+    Added(usize),           // the edge was added
+    Replaced(usize, usize), // the edge replaced the heaviest edge on the cycle it closed
+}
+
+struct Solver<'a> {
+    lctree: LinkCutTree<FindMax>,
+    edges: &'a [(usize, usize, f64, usize, usize)],
+    num_vertices: usize,
+    ranges: Vec<Vec<usize>>,
+    total: f64,
+    changes: Vec<Change>,
+    answers: Vec<f64>,
+}
+
+impl Solver<'_> {
+    fn visit(&mut self, range: usize, lo: usize, hi: usize) {
+        let (changes, total) = (self.changes.len(), self.total);
+        for i in 0..self.ranges[range].len() {
+            let edge = self.ranges[range][i];
+            self.add(edge);
+        }
+        if hi - lo == 1 {
+            self.answers[lo] = self.total;
+        } else {
+            let mid = (lo + hi) / 2;
+            self.visit(2 * range, lo, mid);
+            self.visit(2 * range + 1, mid, hi);
+        }
+        while self.changes.len() > changes {
+            match self.changes.pop() {
+                Some(Change::Added(edge)) => self.unlink(edge),
+                Some(Change::Replaced(edge, heaviest)) => {
+                    self.unlink(edge);
+                    self.link(heaviest);
+                }
+                None => break,
+            }
+        }
+        // restoring the total instead of subtracting keeps the answers exact:
+        self.total = total;
+    }
+
+    fn add(&mut self, edge: usize) {
+        let (u, v, weight, _, _) = self.edges[edge];
+        if u == v {
+            return;
+        }
+        if !self.lctree.connected(u, v) {
+            self.link(edge);
+            self.total += weight;
+            self.changes.push(Change::Added(edge));
+            return;
+        }
+        let heaviest = self.lctree.path(u, v);
+        if heaviest.weight > weight {
+            let heaviest_edge = heaviest.idx - self.num_vertices;
+            self.unlink(heaviest_edge);
+            self.link(edge);
+            self.total += weight - heaviest.weight;
+            self.changes.push(Change::Replaced(edge, heaviest_edge));
+        }
+    }
+
+    fn link(&mut self, edge: usize) {
+        let (u, v, _, _, _) = self.edges[edge];
+        let node = self.num_vertices + edge;
+        self.lctree.link(node, u);
+        self.lctree.link(node, v);
+    }
+
+    fn unlink(&mut self, edge: usize) {
+        let (u, v, _, _, _) = self.edges[edge];
+        let node = self.num_vertices + edge;
+        self.lctree.cut(node, u);
+        self.lctree.cut(node, v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OfflineSpanningForest;
+
+    #[test]
+    pub fn parallel_edges_and_loops() {
+        let mut timeline = OfflineSpanningForest::new(2);
+        timeline.query();
+        timeline.insert_edge(0, 1, 3.0);
+        timeline.insert_edge(1, 0, 5.0);
+        timeline.insert_edge(1, 1, -1.0);
+        timeline.query();
+        assert!(timeline.delete_edge(0, 1)); // deletes the last one (5.0)
+        timeline.query();
+        assert!(timeline.delete_edge(0, 1));
+        assert!(!timeline.delete_edge(0, 1));
+        timeline.query();
+        assert_eq!(timeline.num_queries(), 4);
+        assert_eq!(timeline.solve(), vec![0.0, 3.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    pub fn no_queries() {
+        let mut timeline = OfflineSpanningForest::new(2);
+        timeline.insert_edge(0, 1, 1.0);
+        assert!(timeline.solve().is_empty());
+    }
+}
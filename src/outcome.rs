@@ -0,0 +1,34 @@
+/// The outcome of [`LinkCutTree::link`](crate::LinkCutTree::link).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkOutcome {
+    /// The trees were merged into a single tree.
+    Linked,
+    /// The nodes were already in the same tree (rooted at `root`), so nothing changed.
+    AlreadyConnected { root: usize },
+}
+
+impl LinkOutcome {
+    /// Returns `true` if the trees were merged.
+    #[must_use]
+    pub fn is_linked(&self) -> bool {
+        matches!(self, LinkOutcome::Linked)
+    }
+}
+
+/// The outcome of [`LinkCutTree::cut`](crate::LinkCutTree::cut).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CutOutcome {
+    /// The edge was removed, splitting its tree into two trees:
+    /// `remaining_roots` are the roots of the trees containing `v` and `w` respectively.
+    Cut { remaining_roots: (usize, usize) },
+    /// There was no edge between the nodes, so nothing changed.
+    NoSuchEdge,
+}
+
+impl CutOutcome {
+    /// Returns `true` if the edge was removed.
+    #[must_use]
+    pub fn is_cut(&self) -> bool {
+        matches!(self, CutOutcome::Cut { .. })
+    }
+}
@@ -0,0 +1,240 @@
+use rayon::prelude::*;
+
+use crate::{
+    error::ImportError, index::IndexType, node::Node, path::Path, splay::Forest, LinkCutTree,
+};
+
+/// # Parallel construction
+/// Large forests can be built from edge lists on multiple threads (with the `parallel` feature).
+impl<P: Path + Send, Ix: IndexType> LinkCutTree<P, Ix> {
+    /// Builds a link-cut tree with the nodes `0..weights.len()` and the given edges,
+    /// which is much faster than linking the edges one by one for large initial loads.
+    ///
+    /// The edges are checked for cycles and partitioned into trees, then the trees are built
+    /// in parallel and stitched together: every node starts on a preferred path of its own,
+    /// hanging off its parent by a path-parent pointer (the first node of each tree is its root).
+    /// This takes `O(n + m)` work, and the first operations on the forest pay for the splaying.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ImportError::Cycle`] with the first edge that closes a cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an edge refers to a node that is not in `0..weights.len()`,
+    /// or if the index type cannot hold all of the nodes.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// let weights = [1.0, 2.0, 3.0, 4.0];
+    /// let edges = [(0, 1), (1, 2)];
+    /// let mut lctree: LinkCutTree<FindSum> =
+    ///     LinkCutTree::from_edges_parallel(&weights, &edges).unwrap();
+    /// assert_eq!(lctree.path(0, 2).sum, 6.0);
+    /// assert!(!lctree.connected(0, 3));
+    ///
+    /// assert!(LinkCutTree::<FindSum>::from_edges_parallel(&weights, &[(0, 1), (1, 0)]).is_err());
+    /// ```
+    pub fn from_edges_parallel(
+        weights: &[f64],
+        edges: &[(usize, usize)],
+    ) -> Result<Self, ImportError> {
+        let num_nodes = weights.len();
+        let trees = partition(num_nodes, edges)?;
+        let neighbors = Neighbors::new(num_nodes, edges);
+        let built: Vec<Vec<(usize, Node<P, Ix>)>> = trees
+            .par_iter()
+            .map(|tree| build_tree(tree[0], tree.len(), weights, &neighbors))
+            .collect();
+
+        // stitch the trees together in the order of the ids:
+        let mut slots: Vec<Option<Node<P, Ix>>> = (0..num_nodes).map(|_| None).collect();
+        for (idx, node) in built.into_iter().flatten() {
+            slots[idx] = Some(node);
+        }
+        let nodes = slots.into_iter().flatten().collect();
+        Ok(LinkCutTree::from_forest(Forest::from_nodes(nodes)))
+    }
+}
+
+// Groups the nodes into the trees of the forest (in increasing order of ids within each tree),
+// or returns the first edge that closes a cycle.
+fn partition(num_nodes: usize, edges: &[(usize, usize)]) -> Result<Vec<Vec<usize>>, ImportError> {
+    let mut parents: Vec<usize> = (0..num_nodes).collect();
+    let find = |parents: &mut Vec<usize>, mut v: usize| {
+        while parents[v] != v {
+            parents[v] = parents[parents[v]];
+            v = parents[v];
+        }
+        v
+    };
+    for &(v, w) in edges {
+        assert!(
+            v < num_nodes && w < num_nodes,
+            "from_edges_parallel: the edge ({v}, {w}) refers to a missing node"
+        );
+        let (root_v, root_w) = (find(&mut parents, v), find(&mut parents, w));
+        if root_v == root_w {
+            return Err(ImportError::Cycle(v, w));
+        }
+        parents[root_v] = root_w;
+    }
+    let mut tree_of = vec![usize::MAX; num_nodes];
+    let mut trees: Vec<Vec<usize>> = Vec::new();
+    for v in 0..num_nodes {
+        let root = find(&mut parents, v);
+        if tree_of[root] == usize::MAX {
+            tree_of[root] = trees.len();
+            trees.push(Vec::new());
+        }
+        trees[tree_of[root]].push(v);
+    }
+    Ok(trees)
+}
+
+// The adjacency lists of the forest in a single array.
+struct Neighbors {
+    offsets: Vec<usize>, // the neighbors of `v` are `targets[offsets[v]..offsets[v + 1]]`
+    targets: Vec<usize>,
+}
+
+impl Neighbors {
+    fn new(num_nodes: usize, edges: &[(usize, usize)]) -> Self {
+        let mut offsets = vec![0; num_nodes + 1];
+        for &(v, w) in edges {
+            offsets[v + 1] += 1;
+            offsets[w + 1] += 1;
+        }
+        for v in 0..num_nodes {
+            offsets[v + 1] += offsets[v];
+        }
+        let mut next = offsets.clone();
+        let mut targets = vec![0; 2 * edges.len()];
+        for &(v, w) in edges {
+            targets[next[v]] = w;
+            next[v] += 1;
+            targets[next[w]] = v;
+            next[w] += 1;
+        }
+        Self { offsets, targets }
+    }
+
+    fn of(&self, v: usize) -> &[usize] {
+        &self.targets[self.offsets[v]..self.offsets[v + 1]]
+    }
+}
+
+// Builds the nodes of the tree rooted at `root` (along with their ids) in breadth-first order,
+// with every node alone in its splay tree and hanging off its parent by a path-parent pointer.
+fn build_tree<P: Path, Ix: IndexType>(
+    root: usize,
+    num_nodes: usize,
+    weights: &[f64],
+    neighbors: &Neighbors,
+) -> Vec<(usize, Node<P, Ix>)> {
+    // the nodes along with the positions of their parents in the order:
+    let mut order = Vec::with_capacity(num_nodes);
+    order.push((root, usize::MAX));
+    let mut head = 0;
+    while head < order.len() {
+        let (v, parent_pos) = order[head];
+        let parent = order.get(parent_pos).map(|&(parent, _)| parent);
+        for &w in neighbors.of(v) {
+            // the input is a tree, so only the parent was visited before:
+            if Some(w) != parent {
+                order.push((w, head));
+            }
+        }
+        head += 1;
+    }
+
+    // count the sizes of the subtrees bottom-up:
+    let mut sizes = vec![1; order.len()];
+    for pos in (1..order.len()).rev() {
+        sizes[order[pos].1] += sizes[pos];
+    }
+
+    order
+        .iter()
+        .zip(sizes)
+        .map(|(&(v, parent_pos), size)| {
+            let parent = order.get(parent_pos).map(|&(parent, _)| parent);
+            (
+                v,
+                Node::rooted(v, weights[v], parent, neighbors.of(v).len(), size),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FindMax, FindSum, ImportError, LinkCutTree};
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    #[test]
+    pub fn same_as_linking() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let num_nodes = 500;
+        let weights: Vec<f64> = (0..num_nodes).map(|_| rng.gen_range(0.0..100.0)).collect();
+        // a random forest with the edges in random order:
+        let mut edges: Vec<(usize, usize)> = (1..num_nodes)
+            .map(|v| (rng.gen_range(0..v), v))
+            .filter(|&(v, _)| v % 10 != 0)
+            .collect();
+        edges.shuffle(&mut rng);
+
+        let mut parallel: LinkCutTree<FindMax> =
+            LinkCutTree::from_edges_parallel(&weights, &edges).unwrap();
+        let mut sequential: LinkCutTree<FindMax> = LinkCutTree::new();
+        sequential.extend(weights.iter().copied());
+        sequential.link_all(&edges);
+        parallel.set_component_tracking(true);
+        sequential.set_component_tracking(true);
+        assert_eq!(parallel.len(), num_nodes);
+        assert!(parallel
+            .component_size_histogram()
+            .eq(sequential.component_size_histogram()));
+
+        for _ in 0..1000 {
+            let v = rng.gen_range(0..num_nodes);
+            let w = rng.gen_range(0..num_nodes);
+            let connected = sequential.connected(v, w);
+            assert_eq!(parallel.connected(v, w), connected);
+            assert_eq!(parallel.component_size(v), sequential.component_size(v));
+            assert_eq!(
+                parallel.component_id(v) == parallel.component_id(w),
+                connected
+            );
+            if connected {
+                assert_eq!(parallel.path(v, w).idx, sequential.path(v, w).idx);
+            }
+            if rng.gen_bool(0.1) {
+                // the built forest supports the structural changes as well:
+                if let Some(&(v, w)) = edges.choose(&mut rng) {
+                    parallel.cut(v, w);
+                    sequential.cut(v, w);
+                    parallel.link(v, w);
+                    sequential.link(v, w);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn cycles() {
+        let weights = [0.0; 3];
+        assert!(matches!(
+            LinkCutTree::<FindSum>::from_edges_parallel(&weights, &[(0, 1), (1, 2), (2, 0)]),
+            Err(ImportError::Cycle(2, 0))
+        ));
+        assert!(matches!(
+            LinkCutTree::<FindSum>::from_edges_parallel(&weights, &[(1, 1)]),
+            Err(ImportError::Cycle(1, 1))
+        ));
+        let lctree = LinkCutTree::<FindSum>::from_edges_parallel(&[], &[]).unwrap();
+        assert!(lctree.is_empty());
+    }
+}
@@ -1,9 +1,45 @@
-pub trait Path: Copy + Clone {
+pub trait Path: Clone {
+    /// The weight of the nodes created without a weight (see `LinkCutTree::make_tree_default`).
+    const DEFAULT_WEIGHT: f64 = 0.0;
+
     fn default(weight: f64, index: usize) -> Self;
     fn aggregate(&mut self, other: Self);
+
+    /// Resets the aggregate to the path of a single node, as `default(weight, index)` would.
+    /// Aggregates that own heap-allocated data can override it to reuse their allocations,
+    /// since the aggregate of a node is reset every time its splay tree changes.
+    fn reset(&mut self, weight: f64, index: usize) {
+        *self = Self::default(weight, index);
+    }
+
+    /// Aggregates a path without taking ownership of it, as `aggregate(other.clone())` would.
+    /// Aggregates that are expensive to clone can override it to read only what they need.
+    fn aggregate_from(&mut self, other: &Self) {
+        self.aggregate(other.clone());
+    }
+}
+
+/// A path aggregate that can be updated after a delta was added to the weights it aggregates,
+/// as needed by the subtree and path additions (see `LinkCutTree::subtree_add`
+/// and `LinkCutTree::path_add`).
+pub trait PathAdd: Path {
+    /// Updates the aggregate of `size` nodes after `delta` was added to each of their weights.
+    fn add_delta(&mut self, delta: f64, size: usize);
 }
 
-#[derive(Copy, Clone)]
+/// A path aggregate that can be replaced by the aggregate of nodes that all have the same weight,
+/// as needed by the path assignments (see `LinkCutTree::path_assign`).
+pub trait PathAssign: Path {
+    /// Replaces the aggregate of `size` nodes (a splay subtree rooted at node `index`)
+    /// by the aggregate of as many nodes that all have the given weight.
+    ///
+    /// When several nodes qualify (e.g. for `FindMax`), the aggregate should report `index`,
+    /// as `aggregate` keeps the first one of equal weights.
+    fn assign(&mut self, weight: f64, index: usize, size: usize);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FindMax {
     pub idx: usize,
     pub weight: f64,
@@ -15,14 +51,27 @@ impl Path for FindMax {
     }
 
     fn aggregate(&mut self, other: Self) {
-        if other.weight > self.weight {
+        if other.weight.total_cmp(&self.weight).is_gt() {
             self.weight = other.weight;
             self.idx = other.idx;
         }
     }
 }
 
-#[derive(Copy, Clone)]
+impl PathAdd for FindMax {
+    fn add_delta(&mut self, delta: f64, _size: usize) {
+        self.weight += delta;
+    }
+}
+
+impl PathAssign for FindMax {
+    fn assign(&mut self, weight: f64, index: usize, _size: usize) {
+        *self = Self::default(weight, index);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FindMin {
     pub idx: usize,
     pub weight: f64,
@@ -34,14 +83,27 @@ impl Path for FindMin {
     }
 
     fn aggregate(&mut self, other: Self) {
-        if other.weight < self.weight {
+        if other.weight.total_cmp(&self.weight).is_lt() {
             self.weight = other.weight;
             self.idx = other.idx;
         }
     }
 }
 
-#[derive(Copy, Clone)]
+impl PathAdd for FindMin {
+    fn add_delta(&mut self, delta: f64, _size: usize) {
+        self.weight += delta;
+    }
+}
+
+impl PathAssign for FindMin {
+    fn assign(&mut self, weight: f64, index: usize, _size: usize) {
+        *self = Self::default(weight, index);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FindSum {
     pub sum: f64,
 }
@@ -55,3 +117,17 @@ impl Path for FindSum {
         self.sum += other.sum;
     }
 }
+
+impl PathAdd for FindSum {
+    #[allow(clippy::cast_precision_loss)]
+    fn add_delta(&mut self, delta: f64, size: usize) {
+        self.sum += delta * size as f64;
+    }
+}
+
+impl PathAssign for FindSum {
+    #[allow(clippy::cast_precision_loss)]
+    fn assign(&mut self, weight: f64, _index: usize, size: usize) {
+        self.sum = weight * size as f64;
+    }
+}
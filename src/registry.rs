@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+/// Identifies an aggregate registered at runtime with
+/// [`LinkCutTree::register_aggregate`](crate::LinkCutTree::register_aggregate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AggregateId(pub(crate) usize);
+
+type InitFn = dyn Fn(f64, usize) -> f64 + Send + Sync;
+type CombineFn = dyn Fn(f64, f64) -> f64 + Send + Sync;
+#[cfg(feature = "lazy-updates")]
+type AddFn = dyn Fn(f64, f64, usize) -> f64 + Send + Sync;
+#[cfg(feature = "lazy-updates")]
+type AssignFn = dyn Fn(f64, usize, usize) -> f64 + Send + Sync;
+
+// Recomputes the value of a whole splay subtree when a lazy tag is applied to it
+// (the counterparts of `PathAdd::add_delta` and `PathAssign::assign`):
+#[cfg(feature = "lazy-updates")]
+#[derive(Clone)]
+struct LazyUpdates {
+    add: Arc<AddFn>,
+    assign: Arc<AssignFn>,
+}
+
+// A type-erased path aggregate: the aggregated value of every node's splay subtree
+// is stored in `values` (indexed by the node ids).
+#[derive(Clone)]
+pub struct RuntimeAggregate {
+    init: Arc<InitFn>,
+    combine: Arc<CombineFn>,
+    #[cfg(feature = "lazy-updates")]
+    lazy: Option<LazyUpdates>, // `None` if the aggregate cannot be updated lazily
+    values: Vec<f64>,
+}
+
+impl RuntimeAggregate {
+    pub fn new<I, C>(init: I, combine: C) -> Self
+    where
+        I: Fn(f64, usize) -> f64 + Send + Sync + 'static,
+        C: Fn(f64, f64) -> f64 + Send + Sync + 'static,
+    {
+        Self {
+            init: Arc::new(init),
+            combine: Arc::new(combine),
+            #[cfg(feature = "lazy-updates")]
+            lazy: None,
+            values: Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "lazy-updates")]
+    pub fn updatable<I, C, A, S>(init: I, combine: C, add: A, assign: S) -> Self
+    where
+        I: Fn(f64, usize) -> f64 + Send + Sync + 'static,
+        C: Fn(f64, f64) -> f64 + Send + Sync + 'static,
+        A: Fn(f64, f64, usize) -> f64 + Send + Sync + 'static,
+        S: Fn(f64, usize, usize) -> f64 + Send + Sync + 'static,
+    {
+        Self {
+            lazy: Some(LazyUpdates {
+                add: Arc::new(add),
+                assign: Arc::new(assign),
+            }),
+            ..Self::new(init, combine)
+        }
+    }
+
+    #[cfg(feature = "lazy-updates")]
+    pub fn is_updatable(&self) -> bool {
+        self.lazy.is_some()
+    }
+
+    #[inline]
+    pub fn value(&self, node_idx: usize) -> f64 {
+        self.values[node_idx]
+    }
+
+    // Recomputes the aggregated value of a node from its weight and its children's values.
+    #[inline]
+    pub fn update(
+        &mut self,
+        node_idx: usize,
+        weight: f64,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) {
+        if self.values.len() <= node_idx {
+            self.values.resize(node_idx + 1, 0.0);
+        }
+        let mut value = (self.init)(weight, node_idx);
+        if let Some(left_child) = left {
+            value = (self.combine)(value, self.values[left_child]);
+        }
+        if let Some(right_child) = right {
+            value = (self.combine)(value, self.values[right_child]);
+        }
+        self.values[node_idx] = value;
+    }
+
+    // Adds `delta` to the weights of the `size` nodes in the splay subtree of a node
+    // (does nothing if the aggregate cannot be updated lazily).
+    #[cfg(feature = "lazy-updates")]
+    #[inline]
+    pub fn add_delta(&mut self, node_idx: usize, delta: f64, size: usize) {
+        if let Some(lazy) = &self.lazy {
+            self.values[node_idx] = (lazy.add)(self.values[node_idx], delta, size);
+        }
+    }
+
+    // Sets the weights of the `size` nodes in the splay subtree of a node
+    // (does nothing if the aggregate cannot be updated lazily).
+    #[cfg(feature = "lazy-updates")]
+    #[inline]
+    pub fn assign(&mut self, node_idx: usize, weight: f64, size: usize) {
+        if let Some(lazy) = &self.lazy {
+            self.values[node_idx] = (lazy.assign)(weight, node_idx, size);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use crate::index::IdReusePolicy;
+
+/// An operation of a link-cut tree, as recorded in a script
+/// (see [`LinkCutTree::record_script`](crate::LinkCutTree::record_script)).
+///
+/// Every operation is written as a single line: its name followed by its arguments,
+/// separated by whitespace (e.g. `link 0 1` or `make_tree 2.5`).
+/// The weights are written so that they are parsed back exactly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    MakeTree(f64),
+    RemoveTree(usize),
+    Link(usize, usize),
+    Cut(usize, usize),
+    Linked(usize, usize),
+    Connected(usize, usize),
+    Path(usize, usize),
+    FindRoot(usize),
+    SubtreeAdd(usize, f64),
+    ReplaceWeight(usize, f64),
+    PathAdd(usize, usize, f64),
+    PathAssign(usize, usize, f64),
+    /// Makes a node the root of its tree (also recorded by the write-ahead log
+    /// before a `subtree_add`, which depends on the root).
+    Reroot(usize),
+    /// Enables or disables reusing the ids of removed nodes (see `set_id_reuse`),
+    /// which decides the ids of the nodes created later.
+    IdReuse(bool),
+    /// Changes the order in which the ids of removed nodes are reused (see `set_id_reuse_policy`).
+    IdReusePolicy(IdReusePolicy),
+}
+
+impl Operation {
+    // Whether the operation changes the forest (the queries may still change the roots).
+    fn changes_forest(&self) -> bool {
+        !matches!(
+            self,
+            Operation::Linked(..)
+                | Operation::Connected(..)
+                | Operation::Path(..)
+                | Operation::FindRoot(..)
+        )
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::MakeTree(weight) => write!(f, "make_tree {weight}"),
+            Operation::RemoveTree(v) => write!(f, "remove_tree {v}"),
+            Operation::Link(v, w) => write!(f, "link {v} {w}"),
+            Operation::Cut(v, w) => write!(f, "cut {v} {w}"),
+            Operation::Linked(v, w) => write!(f, "linked {v} {w}"),
+            Operation::Connected(v, w) => write!(f, "connected {v} {w}"),
+            Operation::Path(v, w) => write!(f, "path {v} {w}"),
+            Operation::FindRoot(v) => write!(f, "findroot {v}"),
+            Operation::SubtreeAdd(v, delta) => write!(f, "subtree_add {v} {delta}"),
+            Operation::ReplaceWeight(v, weight) => write!(f, "replace_weight {v} {weight}"),
+            Operation::PathAdd(v, w, delta) => write!(f, "path_add {v} {w} {delta}"),
+            Operation::PathAssign(v, w, weight) => write!(f, "path_assign {v} {w} {weight}"),
+            Operation::Reroot(v) => write!(f, "reroot {v}"),
+            Operation::IdReuse(enabled) => write!(f, "id_reuse {enabled}"),
+            Operation::IdReusePolicy(policy) => {
+                let policy = match policy {
+                    IdReusePolicy::Lifo => "lifo",
+                    IdReusePolicy::Fifo => "fifo",
+                    IdReusePolicy::SmallestFirst => "smallest_first",
+                };
+                write!(f, "id_reuse_policy {policy}")
+            }
+        }
+    }
+}
+
+impl FromStr for Operation {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut tokens = line.split_whitespace();
+        let name = tokens.next().ok_or("empty operation")?;
+        let mut node = || -> Result<usize, String> {
+            let token = tokens.next().ok_or("missing argument")?;
+            token
+                .parse()
+                .map_err(|_| format!("invalid node id `{token}`"))
+        };
+        let operation = match name {
+            "make_tree" => Operation::MakeTree(parse_weight(tokens.next())?),
+            "remove_tree" => Operation::RemoveTree(node()?),
+            "link" => Operation::Link(node()?, node()?),
+            "cut" => Operation::Cut(node()?, node()?),
+            "linked" => Operation::Linked(node()?, node()?),
+            "connected" => Operation::Connected(node()?, node()?),
+            "path" => Operation::Path(node()?, node()?),
+            "findroot" => Operation::FindRoot(node()?),
+            "subtree_add" => {
+                let v = node()?;
+                Operation::SubtreeAdd(v, parse_weight(tokens.next())?)
+            }
+            "replace_weight" => {
+                let v = node()?;
+                Operation::ReplaceWeight(v, parse_weight(tokens.next())?)
+            }
+            "path_add" => {
+                let (v, w) = (node()?, node()?);
+                Operation::PathAdd(v, w, parse_weight(tokens.next())?)
+            }
+            "path_assign" => {
+                let (v, w) = (node()?, node()?);
+                Operation::PathAssign(v, w, parse_weight(tokens.next())?)
+            }
+            "reroot" => Operation::Reroot(node()?),
+            "id_reuse" => {
+                let token = tokens.next().ok_or("missing argument")?;
+                let enabled = token
+                    .parse()
+                    .map_err(|_| format!("invalid flag `{token}`"))?;
+                Operation::IdReuse(enabled)
+            }
+            "id_reuse_policy" => {
+                let policy = match tokens.next().ok_or("missing argument")? {
+                    "lifo" => IdReusePolicy::Lifo,
+                    "fifo" => IdReusePolicy::Fifo,
+                    "smallest_first" => IdReusePolicy::SmallestFirst,
+                    token => return Err(format!("invalid id reuse policy `{token}`")),
+                };
+                Operation::IdReusePolicy(policy)
+            }
+            _ => return Err(format!("unknown operation `{name}`")),
+        };
+        if let Some(token) = tokens.next() {
+            return Err(format!("unexpected argument `{token}`"));
+        }
+        Ok(operation)
+    }
+}
+
+fn parse_weight(token: Option<&str>) -> Result<f64, String> {
+    let token = token.ok_or("missing argument")?;
+    token
+        .parse()
+        .map_err(|_| format!("invalid weight `{token}`"))
+}
+
+/// The error returned by [`LinkCutTree::apply_script`](crate::LinkCutTree::apply_script).
+#[derive(Debug)]
+pub enum ScriptError {
+    /// Reading the script failed.
+    Io(io::Error),
+    /// A line of the script (numbered from 1) is not a valid operation.
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io(err) => write!(f, "failed to read the script: {err}"),
+            ScriptError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ScriptError::Io(err) => Some(err),
+            ScriptError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ScriptError {
+    fn from(err: io::Error) -> Self {
+        ScriptError::Io(err)
+    }
+}
+
+// Writes the recorded operations to a writer, keeping the first error (if any).
+pub struct Recorder {
+    writer: Box<dyn Write + Send + Sync>,
+    error: Option<io::Error>,
+    log: bool, // a write-ahead log only records the changes, and flushes every one of them
+}
+
+impl Recorder {
+    pub fn new(writer: Box<dyn Write + Send + Sync>) -> Self {
+        Self {
+            writer,
+            error: None,
+            log: false,
+        }
+    }
+
+    pub fn log(writer: Box<dyn Write + Send + Sync>) -> Self {
+        Self {
+            writer,
+            error: None,
+            log: true,
+        }
+    }
+
+    pub fn is_log(&self) -> bool {
+        self.log
+    }
+
+    pub fn record(&mut self, operation: Operation) {
+        if self.error.is_some() || (self.log && !operation.changes_forest()) {
+            return;
+        }
+        let mut result = writeln!(self.writer, "{operation}");
+        if self.log {
+            result = result.and_then(|()| self.writer.flush());
+        }
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error {
+            Some(err) => Err(err),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+// Parses the lines of a script, skipping empty lines and comments (starting with `#`).
+// The lazy updates cannot be replayed without the `lazy-updates` feature.
+pub fn parse_line(line: &str, line_number: usize) -> Result<Option<Operation>, ScriptError> {
+    let line = line.split('#').next().unwrap_or_default().trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let error = |message| ScriptError::Parse {
+        line: line_number,
+        message,
+    };
+    let operation: Operation = line.parse().map_err(error)?;
+    #[cfg(not(feature = "lazy-updates"))]
+    if let Operation::SubtreeAdd(..) | Operation::PathAdd(..) | Operation::PathAssign(..) =
+        operation
+    {
+        let name = line.split_whitespace().next().unwrap_or_default();
+        return Err(error(format!(
+            "`{name}` requires the `lazy-updates` feature"
+        )));
+    }
+    Ok(Some(operation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_line, Operation};
+    use crate::IdReusePolicy;
+
+    #[test]
+    pub fn round_trip() {
+        let operations = [
+            Operation::MakeTree(0.1),
+            Operation::MakeTree(-1e300),
+            Operation::MakeTree(f64::INFINITY),
+            Operation::RemoveTree(3),
+            Operation::Link(0, 1),
+            Operation::Cut(1, 0),
+            Operation::Linked(2, 3),
+            Operation::Connected(4, 5),
+            Operation::Path(6, 7),
+            Operation::FindRoot(8),
+            Operation::SubtreeAdd(9, 2.5),
+            Operation::ReplaceWeight(10, -0.5),
+            Operation::PathAdd(12, 13, -0.5),
+            Operation::PathAssign(13, 12, 0.1),
+            Operation::Reroot(11),
+            Operation::IdReuse(false),
+            Operation::IdReusePolicy(IdReusePolicy::SmallestFirst),
+            Operation::IdReusePolicy(IdReusePolicy::Fifo),
+        ];
+        for operation in operations {
+            assert_eq!(operation.to_string().parse(), Ok(operation));
+        }
+    }
+
+    #[test]
+    pub fn invalid_lines() {
+        assert!(parse_line("  # a comment", 1).unwrap().is_none());
+        assert_eq!(
+            parse_line("link 0 1 # a comment", 1).unwrap(),
+            Some(Operation::Link(0, 1))
+        );
+        let errors = [
+            "jump 1",
+            "link 0",
+            "link 0 x",
+            "cut 0 1 2",
+            "make_tree",
+            "id_reuse 1",
+            "id_reuse_policy random",
+        ];
+        for line in errors {
+            assert!(parse_line(line, 1).is_err(), "{line}");
+        }
+        let message = parse_line("link a b", 7).unwrap_err().to_string();
+        assert_eq!(message, "line 7: invalid node id `a`");
+        #[cfg(not(feature = "lazy-updates"))]
+        assert_eq!(
+            parse_line("path_add 0 1 2", 3).unwrap_err().to_string(),
+            "line 3: `path_add` requires the `lazy-updates` feature"
+        );
+    }
+}
@@ -1,35 +1,141 @@
+use std::{
+    fmt::Debug,
+    io::{self, Write},
+    marker::PhantomData,
+};
+
 use crate::{
-    index::Index,
-    node::{Node, Parent},
-    path::Path,
+    index::{IdReusePolicy, Index, IndexType},
+    node::{Node, NodeSlot, NodeView, Parent},
+    path::{FindMax, Path},
+    registry::RuntimeAggregate,
+    stats::{CostCounters, SplayStats, SplayTree},
+    storage::NodeStorage,
 };
 
-pub struct Forest<P: Path> {
-    nodes: Vec<Node<P>>,
+pub struct Forest<P: Path, Ix: IndexType = usize, S = Vec<NodeSlot<P, Ix>>> {
+    nodes: S,
     index: Index,
+    max_splay_depth: usize,
+    counters: Option<CostCounters>, // only collected if instrumentation is enabled
+    aggregates: Vec<RuntimeAggregate>, // registered at runtime
+    version: u64,                   // changes whenever the represented forest or the weights change
+    lazy_aggregates: bool,          // the path aggregates are only recomputed when they are queried
+    scratch: P,                     // the spare aggregate that `update_path` computes in
+    // how to push the pending updates down to the aggregates (see `LazyOps`):
+    #[cfg(feature = "lazy-updates")]
+    lazy_ops: LazyOps<P>,
+    node: PhantomData<NodeSlot<P, Ix>>, // the type of the nodes in the storage
 }
 
-impl<P: Path> Forest<P> {
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> Forest<P, Ix, S> {
     pub fn new() -> Self {
         Self {
-            nodes: Vec::new(),
+            nodes: S::default(),
             index: Index::new(),
+            max_splay_depth: 0,
+            counters: None,
+            aggregates: Vec::new(),
+            version: 0,
+            lazy_aggregates: false,
+            scratch: P::default(0.0, 0),
+            #[cfg(feature = "lazy-updates")]
+            lazy_ops: LazyOps::new(),
+            node: PhantomData,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: S::with_capacity(capacity),
+            index: Index::with_capacity(capacity),
+            max_splay_depth: 0,
+            counters: None,
+            aggregates: Vec::new(),
+            version: 0,
+            lazy_aggregates: false,
+            scratch: P::default(0.0, 0),
+            #[cfg(feature = "lazy-updates")]
+            lazy_ops: LazyOps::new(),
+            node: PhantomData,
+        }
+    }
+
+    // Builds a forest of the given nodes with the ids `0..nodes.len()`,
+    // whose links, sizes and anchors have to be consistent already.
+    #[cfg(any(feature = "parallel", feature = "mmap"))]
+    pub fn from_nodes(nodes: Vec<Node<P, Ix>>) -> Self {
+        assert!(
+            nodes.len() <= <Ix as IndexType>::max().index(),
+            "from_nodes: the index type cannot hold {} nodes",
+            nodes.len()
+        );
+        let mut index = Index::with_capacity(nodes.len());
+        let mut storage = S::with_capacity(nodes.len());
+        for node in nodes {
+            index.insert();
+            storage.push(NodeSlot(node));
+        }
+        Self {
+            nodes: storage,
+            index,
+            max_splay_depth: 0,
+            counters: None,
+            aggregates: Vec::new(),
+            version: 0,
+            lazy_aggregates: false,
+            scratch: P::default(0.0, 0),
+            #[cfg(feature = "lazy-updates")]
+            lazy_ops: LazyOps::new(),
+            node: PhantomData,
         }
     }
 
+    // The version of the forest, which changes with every link, cut, creation or removal
+    // of a node and weight update (but not with the splaying).
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn contains(&self, node_idx: usize) -> bool {
+        self.index.contains(node_idx)
+    }
+
+    // Iterates over the ids of the nodes that are currently allocated.
+    pub fn node_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|&idx| self.index.contains(idx))
+    }
+
     pub fn create_node(&mut self, weight: f64) -> usize {
+        self.version += 1;
         let idx = self.index.insert();
+        assert!(
+            idx < <Ix as IndexType>::max().index(),
+            "create_node: the index type cannot hold more nodes"
+        );
         if idx < self.nodes.len() {
-            self.nodes[idx] = Node::new(idx, weight);
-            return idx;
+            self.nodes[idx].0 = Node::new(idx, weight);
+        } else {
+            self.nodes.push(NodeSlot(Node::new(idx, weight)));
+        }
+        for aggregate in &mut self.aggregates {
+            aggregate.update(idx, weight, None, None);
         }
-        self.nodes.push(Node::new(idx, weight));
         idx
     }
 
     pub fn delete_node(&mut self, node_idx: usize) {
+        self.version += 1;
         assert!(
-            self.nodes[node_idx].degree == 0,
+            self.is_singleton(node_idx),
             "Invalid deletion: tree contains more than one node."
         );
         self.index.delete(node_idx);
@@ -38,41 +144,65 @@ impl<P: Path> Forest<P> {
     #[inline]
     pub fn set_right(&mut self, node_idx: usize, right_idx: usize) {
         assert!(
-            self.nodes[node_idx].right.is_none(),
+            self.nodes[node_idx].0.right().is_none(),
             "set_right: node_idx already has a right child"
         );
-        self.nodes[node_idx].right = Some(right_idx);
-        self.nodes[right_idx].parent = Parent::Node(node_idx);
+        self.nodes[node_idx].0.set_right(Some(right_idx));
+        self.nodes[right_idx].0.set_parent(Parent::Node(node_idx));
     }
 
     #[inline]
     pub fn set_left(&mut self, node_idx: usize, left_idx: usize) {
+        self.version += 1;
         assert!(
-            self.nodes[node_idx].left.is_none(),
+            self.nodes[node_idx].0.left().is_none(),
             "set_left: node_idx already has a left child"
         );
-        self.nodes[node_idx].left = Some(left_idx);
-        self.nodes[left_idx].parent = Parent::Node(node_idx);
-        self.nodes[node_idx].degree += 1;
-        self.nodes[left_idx].degree += 1;
+        self.nodes[node_idx].0.set_left(Some(left_idx));
+        self.nodes[left_idx].0.set_parent(Parent::Node(node_idx));
+        #[cfg(feature = "degree-tracking")]
+        {
+            self.increment_degree(node_idx);
+            self.increment_degree(left_idx);
+        }
+        self.update(node_idx);
     }
 
     #[inline]
     pub fn cut_left(&mut self, node_idx: usize) {
+        self.version += 1;
         assert!(
-            self.nodes[node_idx].left.is_some(),
+            self.nodes[node_idx].0.left().is_some(),
             "cut_left: node_idx does not have a left child"
         );
-        let left = self.nodes[node_idx].left.unwrap();
-        self.nodes[node_idx].left = None;
-        self.nodes[left].parent = Parent::Root;
-        self.nodes[node_idx].degree -= 1;
-        self.nodes[left].degree -= 1;
+        let left = self.nodes[node_idx].0.left().unwrap();
+        self.nodes[node_idx].0.set_left(None);
+        self.nodes[left].0.set_parent(Parent::Root);
+        #[cfg(feature = "degree-tracking")]
+        {
+            self.decrement_degree(node_idx);
+            self.decrement_degree(left);
+        }
+        self.update(node_idx);
+    }
+
+    #[inline]
+    #[cfg(feature = "degree-tracking")]
+    fn increment_degree(&mut self, node_idx: usize) {
+        let degree = self.nodes[node_idx].0.degree();
+        self.nodes[node_idx].0.set_degree(degree + 1);
+    }
+
+    #[inline]
+    #[cfg(feature = "degree-tracking")]
+    fn decrement_degree(&mut self, node_idx: usize) {
+        let degree = self.nodes[node_idx].0.degree();
+        self.nodes[node_idx].0.set_degree(degree - 1);
     }
 
     #[inline]
     pub fn parent_of(&self, node_idx: usize) -> Option<usize> {
-        if let Parent::Node(parent_idx) = self.nodes[node_idx].parent {
+        if let Parent::Node(parent_idx) = self.nodes[node_idx].0.parent() {
             Some(parent_idx)
         } else {
             None
@@ -81,7 +211,7 @@ impl<P: Path> Forest<P> {
 
     #[inline]
     pub fn path_parent_of(&self, node_idx: usize) -> Option<usize> {
-        if let Parent::Path(parent_idx) = self.nodes[node_idx].parent {
+        if let Parent::Path(parent_idx) = self.nodes[node_idx].0.parent() {
             Some(parent_idx)
         } else {
             None
@@ -90,57 +220,691 @@ impl<P: Path> Forest<P> {
 
     #[inline]
     pub fn left_of(&self, node_idx: usize) -> Option<usize> {
-        self.nodes[node_idx].left
+        self.nodes[node_idx].0.left()
     }
 
     #[inline]
     pub fn right_of(&self, node_idx: usize) -> Option<usize> {
-        self.nodes[node_idx].right
+        self.nodes[node_idx].0.right()
+    }
+
+    // The weight of a node (pending additions from its ancestors are not included).
+    #[inline]
+    pub fn weight_of(&self, node_idx: usize) -> f64 {
+        self.nodes[node_idx].0.weight
+    }
+
+    // The number of nodes in the splay subtree of a node and in the trees hanging off it,
+    // e.g. the size of the whole represented tree if the node was just accessed.
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub fn tree_size_of(&self, node_idx: usize) -> usize {
+        self.nodes[node_idx].0.tree_size()
+    }
+
+    // The number of nodes in the trees hanging off a node by path-parent pointers,
+    // e.g. the size of its subtree without itself if the node was just accessed.
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub fn virtual_size_of(&self, node_idx: usize) -> usize {
+        self.nodes[node_idx].0.virtual_size()
     }
 
+    // The id + 1 of the anchor among the nodes counted by `tree_size_of` (0 if there is none).
     #[inline]
-    pub fn aggregated_path_of(&self, node_idx: usize) -> P {
-        self.nodes[node_idx].path
+    #[cfg(feature = "component-sizes")]
+    pub fn tree_anchor_of(&self, node_idx: usize) -> usize {
+        self.nodes[node_idx].0.tree_anchor()
+    }
+
+    // Makes a node the anchor of its represented tree (or not),
+    // the node must be the root of its splay tree.
+    #[cfg(feature = "component-sizes")]
+    pub fn set_anchor(&mut self, node_idx: usize, anchor: bool) {
+        self.nodes[node_idx].0.set_anchor(anchor);
+        self.update(node_idx);
+    }
+
+    // The number of edges connected to a node in the represented tree.
+    #[inline]
+    #[cfg(feature = "degree-tracking")]
+    pub fn degree_of(&self, node_idx: usize) -> usize {
+        self.nodes[node_idx].0.degree()
+    }
+
+    // Without the degree tracking, the degree is counted in `O(n)` from the splay trees:
+    // the neighbors of the node on its preferred path, the path-parent of the path if the node
+    // is its top-most node, and the top-most nodes of the paths hanging off the node.
+    #[cfg(not(feature = "degree-tracking"))]
+    pub fn degree_of(&self, node_idx: usize) -> usize {
+        // the pending flips of the node and its ancestors decide which children come first:
+        let mut flipped = self.nodes[node_idx].0.flipped();
+        let mut root = node_idx;
+        while let Parent::Node(parent_idx) = self.nodes[root].0.parent() {
+            flipped ^= self.nodes[parent_idx].0.flipped();
+            root = parent_idx;
+        }
+        let node = &self.nodes[node_idx].0;
+        let (mut before, mut after) = if flipped {
+            (node.right().is_some(), node.left().is_some())
+        } else {
+            (node.left().is_some(), node.right().is_some())
+        };
+        // the node follows the ancestors whose right subtree contains it (and vice versa):
+        let mut child = node_idx;
+        while let Parent::Node(parent_idx) = self.nodes[child].0.parent() {
+            flipped ^= self.nodes[child].0.flipped();
+            let is_right = (self.nodes[parent_idx].0.right() == Some(child)) ^ flipped;
+            before |= is_right;
+            after |= !is_right;
+            child = parent_idx;
+        }
+        let on_path = usize::from(before) + usize::from(after);
+        let above = usize::from(!before && self.path_parent_of(root).is_some());
+        let below = (0..self.nodes.len())
+            .filter(|&idx| matches!(self.nodes[idx].0.parent(), Parent::Path(parent) if parent == node_idx))
+            .count();
+        on_path + above + below
+    }
+
+    // Checks if the node is the only node of its tree, i.e. it is alone in a splay tree that
+    // does not hang off another node and no other trees hang off it.
+    #[inline]
+    #[cfg(feature = "component-sizes")]
+    pub fn is_singleton(&self, node_idx: usize) -> bool {
+        let node = &self.nodes[node_idx].0;
+        matches!(node.parent(), Parent::Root) && node.tree_size() == 1
+    }
+
+    // Without the tree sizes, a node is a singleton if it has no edges.
+    #[inline]
+    #[cfg(all(not(feature = "component-sizes"), feature = "degree-tracking"))]
+    pub fn is_singleton(&self, node_idx: usize) -> bool {
+        self.degree_of(node_idx) == 0
+    }
+
+    // Without the degrees either, the node has to be alone in its splay tree, which does not hang
+    // off another node, and no other paths may hang off it (checked in `O(n)` without allocating).
+    #[cfg(not(any(feature = "component-sizes", feature = "degree-tracking")))]
+    pub fn is_singleton(&self, node_idx: usize) -> bool {
+        matches!(self.nodes[node_idx].0.parent(), Parent::Root)
+            && self.left_of(node_idx).is_none()
+            && self.right_of(node_idx).is_none()
+            && !(0..self.nodes.len()).any(|idx| {
+                matches!(self.nodes[idx].0.parent(), Parent::Path(parent) if parent == node_idx)
+            })
+    }
+
+    // The number of nodes in the splay subtree rooted at `node_idx`.
+    #[inline]
+    #[cfg(feature = "depth-queries")]
+    pub fn size_of(&self, node_idx: usize) -> usize {
+        self.nodes[node_idx].0.size()
+    }
+
+    #[inline]
+    pub fn aggregated_path_of(&mut self, node_idx: usize) -> P {
+        if self.nodes[node_idx].0.dirty() {
+            self.refresh_path(node_idx);
+        }
+        self.nodes[node_idx].0.path.clone()
+    }
+
+    // Defers recomputing the path aggregates on the structural changes and weight updates:
+    // the nodes are only marked as dirty by `update`, and the aggregate of a node is recomputed
+    // from its dirty descendants when it is queried (see `refresh_path`).
+    // Disabling it recomputes the aggregates of the whole forest in `O(n)`.
+    pub fn set_lazy_aggregates(&mut self, lazy: bool) {
+        let was_lazy = self.lazy_aggregates;
+        self.lazy_aggregates = lazy;
+        if was_lazy && !lazy {
+            self.rebuild_aggregates();
+        }
+    }
+
+    pub fn lazy_aggregates(&self) -> bool {
+        self.lazy_aggregates
+    }
+
+    // Recomputes the path aggregate of the splay subtree rooted at `node_idx`, descending only
+    // into the dirty nodes (every dirty node has dirty ancestors up to the root of its splay tree,
+    // as `update` is called bottom-up, so the clean subtrees are up to date).
+    fn refresh_path(&mut self, node_idx: usize) {
+        let mut stack = vec![(node_idx, false)];
+        while let Some((idx, visited)) = stack.pop() {
+            if visited {
+                self.update_path(idx);
+                continue;
+            }
+            // the pending flips and additions are pushed down first, as in the splaying:
+            self.normalize(idx);
+            stack.push((idx, true));
+            for child in [self.nodes[idx].0.left(), self.nodes[idx].0.right()]
+                .into_iter()
+                .flatten()
+            {
+                if self.nodes[child].0.dirty() {
+                    stack.push((child, false));
+                }
+            }
+        }
+    }
+
+    // Lists the nodes of the splay tree rooted at `node_idx` in order, pushing the pending flips
+    // and additions down on the way (so that the weights of the listed nodes are up to date).
+    pub fn normalized_splay_tree(&mut self, node_idx: usize) -> Vec<usize> {
+        let mut nodes = Vec::new();
+        let mut stack = Vec::new();
+        let mut current = Some(node_idx);
+        loop {
+            while let Some(idx) = current {
+                self.normalize(idx);
+                stack.push(idx);
+                current = self.nodes[idx].0.left();
+            }
+            let idx = match stack.pop() {
+                Some(idx) => idx,
+                None => break,
+            };
+            nodes.push(idx);
+            current = self.nodes[idx].0.right();
+        }
+        nodes
+    }
+
+    // Replaces the weights of nodes in the splay tree rooted at `node_idx`, which was normalized
+    // by `normalized_splay_tree`, and recomputes the aggregates of the splay tree bottom-up.
+    pub fn set_splay_tree_weights(&mut self, node_idx: usize, weights: &[(usize, f64)]) {
+        self.version += 1;
+        for &(idx, weight) in weights {
+            self.nodes[idx].0.weight = weight;
+        }
+        // every child comes after its parent in the pre-order, so the reverse is bottom-up:
+        let mut order = vec![node_idx];
+        let mut i = 0;
+        while i < order.len() {
+            let idx = order[i];
+            i += 1;
+            order.extend(
+                [self.nodes[idx].0.left(), self.nodes[idx].0.right()]
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+        for &idx in order.iter().rev() {
+            self.update(idx);
+        }
+    }
+
+    // Recomputes the path aggregate of a node from its weight and its children.
+    // The aggregate of the node is swapped out into `scratch`, so that the children can be
+    // aggregated into it in place while they are borrowed from the storage.
+    #[inline]
+    fn update_path(&mut self, node_idx: usize) {
+        let weight = self.nodes[node_idx].0.weight;
+        std::mem::swap(&mut self.scratch, &mut self.nodes[node_idx].0.path);
+        self.scratch.reset(weight, node_idx);
+        if let Some(left_child) = self.nodes[node_idx].0.left() {
+            self.scratch.aggregate_from(&self.nodes[left_child].0.path);
+        }
+        if let Some(right_child) = self.nodes[node_idx].0.right() {
+            self.scratch.aggregate_from(&self.nodes[right_child].0.path);
+        }
+        std::mem::swap(&mut self.scratch, &mut self.nodes[node_idx].0.path);
+        self.nodes[node_idx].0.set_dirty(false);
     }
 
     // Unflips the subtree rooted at `node_idx`, swapping the left and right children.
     // The children's `flipped` flag is also toggled to propogate the change down the tree.
+    // The pending weight addition (if any) is also pushed down to the children.
+    #[inline]
     pub fn normalize(&mut self, node_idx: usize) {
-        if self.nodes[node_idx].flipped {
-            self.nodes[node_idx].flip_children();
-            self.nodes[node_idx].flipped = false;
-            if let Some(left_child) = self.nodes[node_idx].left {
-                self.nodes[left_child].flipped ^= true;
+        if self.nodes[node_idx].0.flipped() {
+            self.nodes[node_idx].0.flip_children();
+            self.nodes[node_idx].0.set_flipped(false);
+            if let Some(left_child) = self.nodes[node_idx].0.left() {
+                self.nodes[left_child].0.toggle_flipped();
+            }
+            if let Some(right_child) = self.nodes[node_idx].0.right() {
+                self.nodes[right_child].0.toggle_flipped();
+            }
+        }
+        #[cfg(feature = "lazy-updates")]
+        self.push_update_tags(node_idx);
+    }
+
+    // Pushes the pending additions and assignment of a node down to its children.
+    #[cfg(feature = "lazy-updates")]
+    fn push_update_tags(&mut self, node_idx: usize) {
+        let delta = self.nodes[node_idx].0.delta;
+        if delta != 0.0 {
+            self.nodes[node_idx].0.delta = 0.0;
+            if let Some(left_child) = self.nodes[node_idx].0.left() {
+                self.add_delta(left_child, delta);
+            }
+            if let Some(right_child) = self.nodes[node_idx].0.right() {
+                self.add_delta(right_child, delta);
+            }
+        }
+        if let Some(weight) = self.nodes[node_idx].0.assigned() {
+            self.nodes[node_idx].0.set_assigned(None);
+            if let Some(left_child) = self.nodes[node_idx].0.left() {
+                self.assign_weight(left_child, weight);
+            }
+            if let Some(right_child) = self.nodes[node_idx].0.right() {
+                self.assign_weight(right_child, weight);
+            }
+        }
+        let path_delta = self.nodes[node_idx].0.path_delta;
+        if path_delta != 0.0 {
+            self.nodes[node_idx].0.path_delta = 0.0;
+            if let Some(left_child) = self.nodes[node_idx].0.left() {
+                self.add_path_delta(left_child, path_delta);
+            }
+            if let Some(right_child) = self.nodes[node_idx].0.right() {
+                self.add_path_delta(right_child, path_delta);
+            }
+        }
+    }
+
+    // Adds `delta` to the weights of all nodes in the subtree rooted at `node_idx`
+    // (lazily for its descendants), but not to the subtrees hanging off them.
+    #[cfg(feature = "lazy-updates")]
+    fn add_path_delta(&mut self, node_idx: usize, delta: f64) {
+        let node = &mut self.nodes[node_idx].0;
+        let size = node.size();
+        node.weight += delta;
+        self.lazy_ops.add(&mut node.path, delta, size);
+        node.path_delta += delta;
+        for aggregate in &mut self.aggregates {
+            aggregate.add_delta(node_idx, delta, size);
+        }
+    }
+
+    // Sets the weights of all nodes in the subtree rooted at `node_idx` (lazily for its descendants),
+    // overriding the pending path additions but not the additions to the subtrees hanging off them.
+    #[cfg(feature = "lazy-updates")]
+    fn assign_weight(&mut self, node_idx: usize, weight: f64) {
+        let node = &mut self.nodes[node_idx].0;
+        let size = node.size();
+        node.weight = weight;
+        self.lazy_ops.assign(&mut node.path, weight, node_idx, size);
+        node.set_assigned(Some(weight));
+        node.path_delta = 0.0;
+        for aggregate in &mut self.aggregates {
+            aggregate.assign(node_idx, weight, size);
+        }
+    }
+
+    // Adds `delta` to the weights of the nodes in the splay tree rooted at `node_idx`,
+    // i.e. to a whole preferred path.
+    #[cfg(feature = "lazy-updates")]
+    pub fn add_to_path(&mut self, node_idx: usize, delta: f64)
+    where
+        P: crate::path::PathAdd,
+    {
+        self.version += 1;
+        self.lazy_ops.add = Some(P::add_delta);
+        self.add_path_delta(node_idx, delta);
+    }
+
+    // Sets the weights of the nodes in the splay tree rooted at `node_idx` (a whole preferred path).
+    #[cfg(feature = "lazy-updates")]
+    pub fn assign_path(&mut self, node_idx: usize, weight: f64)
+    where
+        P: crate::path::PathAssign,
+    {
+        self.version += 1;
+        self.lazy_ops.assign = Some(P::assign);
+        self.assign_weight(node_idx, weight);
+    }
+
+    // Adds `delta` to the weights of all nodes in the subtree rooted at `node_idx`
+    // (lazily for its descendants) and to all subtrees hanging off them by path-parent pointers.
+    #[cfg(feature = "lazy-updates")]
+    fn add_delta(&mut self, node_idx: usize, delta: f64) {
+        let node = &mut self.nodes[node_idx].0;
+        let size = node.size();
+        node.weight += delta;
+        self.lazy_ops.add(&mut node.path, delta, size);
+        node.delta += delta;
+        node.virtual_delta.add(delta);
+        if node.assigned().is_some() {
+            // the pending assignment would override the addition to the weights below:
+            node.path_delta += delta;
+        }
+        for aggregate in &mut self.aggregates {
+            aggregate.add_delta(node_idx, delta, size);
+        }
+    }
+
+    // Adds `delta` to the weights of a node and all the subtrees hanging off it
+    // by path-parent pointers (the node must be the root of its splay tree).
+    #[cfg(feature = "lazy-updates")]
+    pub fn add_to_subtree(&mut self, node_idx: usize, delta: f64)
+    where
+        P: crate::path::PathAdd,
+    {
+        self.version += 1;
+        self.lazy_ops.add = Some(P::add_delta);
+        self.nodes[node_idx].0.weight += delta;
+        self.nodes[node_idx].0.virtual_delta.add(delta);
+        self.update(node_idx);
+    }
+
+    // Replaces the weight of a node (the node must be the root of its splay tree).
+    pub fn set_weight(&mut self, node_idx: usize, weight: f64) {
+        self.version += 1;
+        self.nodes[node_idx].0.weight = weight;
+        self.update(node_idx);
+    }
+
+    // Applies the additions made to the path-parent's hanging subtrees
+    // since the splay tree rooted at `node_idx` was detached from its path.
+    #[cfg(feature = "lazy-updates")]
+    pub fn pull_virtual_delta(&mut self, node_idx: usize) {
+        if let Parent::Path(parent_idx) = self.nodes[node_idx].0.parent() {
+            let seen = self.nodes[parent_idx].0.virtual_delta;
+            let delta = seen.since(self.nodes[node_idx].0.virtual_seen);
+            if delta != 0.0 {
+                self.add_delta(node_idx, delta);
+            }
+            self.nodes[node_idx].0.virtual_seen = seen;
+        }
+    }
+
+    // Lists the roots of the represented trees, and the splay trees hanging off each node
+    // by path-parent pointers (as linked lists, where `usize::MAX` marks the end).
+    fn path_children(&self) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+        let mut roots = Vec::new();
+        let mut first_path_child = vec![usize::MAX; self.nodes.len()];
+        let mut next_path_child = vec![usize::MAX; self.nodes.len()];
+        for idx in self.node_ids() {
+            match self.nodes[idx].0.parent() {
+                Parent::Path(parent_idx) => {
+                    next_path_child[idx] = first_path_child[parent_idx];
+                    first_path_child[parent_idx] = idx;
+                }
+                Parent::Root => roots.push(idx),
+                Parent::Node(_) => {}
+            }
+        }
+        (roots, first_path_child, next_path_child)
+    }
+
+    // Pushes all the pending flips and additions down to the nodes in O(n), visiting
+    // the nodes top-down (splay parents and path-parents before their children).
+    pub fn push_lazy_updates(&mut self) -> Vec<usize> {
+        let (mut order, first_path_child, next_path_child) = self.path_children();
+        order.reserve(self.len());
+        let mut i = 0;
+        while i < order.len() {
+            let idx = order[i];
+            i += 1;
+            self.normalize(idx);
+            if let Some(left_child) = self.nodes[idx].0.left() {
+                order.push(left_child);
+            }
+            if let Some(right_child) = self.nodes[idx].0.right() {
+                order.push(right_child);
+            }
+            let mut path_child = first_path_child[idx];
+            while path_child != usize::MAX {
+                #[cfg(feature = "lazy-updates")]
+                self.pull_virtual_delta(path_child);
+                order.push(path_child);
+                path_child = next_path_child[path_child];
+            }
+        }
+        order
+    }
+
+    // Computes the pending changes of the ancestors of a node in the splay trees to its weight
+    // (see `effective_weights`), without pushing them down to the node.
+    #[cfg(feature = "lazy-updates")]
+    fn pending_weight_of(&self, node_idx: usize) -> PendingWeight {
+        let mut pending = PendingWeight::default();
+        let mut on_path = true; // the path updates stop at the first path-parent pointer
+        let mut idx = node_idx;
+        loop {
+            match self.nodes[idx].0.parent() {
+                Parent::Node(parent_idx) => {
+                    let parent = &self.nodes[parent_idx].0;
+                    pending = pending.then(if on_path {
+                        PendingWeight::of_node(parent)
+                    } else {
+                        PendingWeight::added(parent.delta)
+                    });
+                    idx = parent_idx;
+                }
+                Parent::Path(parent_idx) => {
+                    let delta = (self.nodes[parent_idx].0.virtual_delta)
+                        .since(self.nodes[idx].0.virtual_seen);
+                    pending = pending.then(PendingWeight::added(delta));
+                    on_path = false;
+                    idx = parent_idx;
+                }
+                Parent::Root => return pending,
+            }
+        }
+    }
+
+    // The weight and the aggregate of a node including the pending changes of its ancestors.
+    #[cfg(feature = "lazy-updates")]
+    fn pending_view_of(&self, node_idx: usize) -> (f64, P) {
+        let node = &self.nodes[node_idx].0;
+        let pending = self.pending_weight_of(node_idx);
+        let mut aggregate = node.path.clone();
+        if let Some(weight) = pending.assigned {
+            self.lazy_ops
+                .assign(&mut aggregate, weight, node_idx, node.size());
+        }
+        if pending.added != 0.0 {
+            self.lazy_ops
+                .add(&mut aggregate, pending.added, node.size());
+        }
+        (pending.apply(node.weight), aggregate)
+    }
+
+    pub fn view_of(&self, node_idx: usize) -> NodeView<P> {
+        let node = &self.nodes[node_idx].0;
+        #[cfg(feature = "lazy-updates")]
+        let (weight, aggregate) = self.pending_view_of(node_idx);
+        #[cfg(not(feature = "lazy-updates"))]
+        let (weight, aggregate) = (node.weight, node.path.clone());
+        NodeView {
+            id: node_idx,
+            weight,
+            degree: self.degree_of(node_idx),
+            parent: node.parent().into(),
+            aggregate,
+        }
+    }
+
+    // Computes the weights of all nodes (indexed by id) including the pending additions,
+    // without pushing them down to the nodes.
+    #[cfg(feature = "lazy-updates")]
+    pub fn effective_weights(&self) -> Vec<f64> {
+        let (roots, first_path_child, next_path_child) = self.path_children();
+        // the pending additions to the virtual deltas of each node,
+        // and the pending changes to its weight:
+        let mut pending = vec![0.0; self.nodes.len()];
+        let mut pending_weights = vec![PendingWeight::default(); self.nodes.len()];
+        let mut stack = roots;
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx].0;
+            for child in [node.left(), node.right()].into_iter().flatten() {
+                pending[child] = pending[idx] + node.delta;
+                // the changes of the ancestors were made after the ones of the node:
+                pending_weights[child] = PendingWeight::of_node(node).then(pending_weights[idx]);
+                stack.push(child);
             }
-            if let Some(right_child) = self.nodes[node_idx].right {
-                self.nodes[right_child].flipped ^= true;
+            let mut path_child = first_path_child[idx];
+            while path_child != usize::MAX {
+                let seen = self.nodes[path_child].0.virtual_seen;
+                pending[path_child] = node.virtual_delta.since(seen) + pending[idx];
+                pending_weights[path_child] = PendingWeight::added(pending[path_child]);
+                stack.push(path_child);
+                path_child = next_path_child[path_child];
             }
         }
+        pending_weights
+            .into_iter()
+            .enumerate()
+            .map(|(idx, pending)| pending.apply(self.nodes[idx].0.weight))
+            .collect()
+    }
+
+    // Without the lazy updates, the weights are always up to date.
+    #[cfg(not(feature = "lazy-updates"))]
+    pub fn effective_weights(&self) -> Vec<f64> {
+        (0..self.nodes.len())
+            .map(|idx| self.nodes[idx].0.weight)
+            .collect()
     }
 
     // Updates the path aggregate information for the subtree rooted at `node_idx`.
+    #[inline]
     pub fn update(&mut self, node_idx: usize) {
-        self.nodes[node_idx].path = P::default(self.nodes[node_idx].weight, node_idx);
-        if let Some(left_child) = self.nodes[node_idx].left {
-            let left_path = self.nodes[left_child].path;
-            self.nodes[node_idx].path.aggregate(left_path);
+        if let Some(counters) = &mut self.counters {
+            counters.updates += 1;
         }
-        if let Some(right_child) = self.nodes[node_idx].right {
-            let right_path = self.nodes[right_child].path;
-            self.nodes[node_idx].path.aggregate(right_path);
+        if self.lazy_aggregates {
+            self.nodes[node_idx].0.set_dirty(true);
+        } else {
+            self.update_path(node_idx);
+        }
+        #[cfg(feature = "depth-queries")]
+        self.update_size(node_idx);
+        #[cfg(feature = "component-sizes")]
+        self.update_tree_size(node_idx);
+        if !self.aggregates.is_empty() {
+            self.update_runtime_aggregates(node_idx);
+        }
+    }
+
+    // Kept out of `update`, which is called on every rotation, as most forests register none.
+    #[cold]
+    fn update_runtime_aggregates(&mut self, node_idx: usize) {
+        for aggregate in &mut self.aggregates {
+            let node = &self.nodes[node_idx].0;
+            aggregate.update(node_idx, node.weight, node.left(), node.right());
+        }
+    }
+
+    // Recomputes the number of nodes in the splay subtree rooted at `node_idx`.
+    #[cfg(feature = "depth-queries")]
+    fn update_size(&mut self, node_idx: usize) {
+        let mut size = 1;
+        if let Some(left_child) = self.nodes[node_idx].0.left() {
+            size += self.nodes[left_child].0.size();
+        }
+        if let Some(right_child) = self.nodes[node_idx].0.right() {
+            size += self.nodes[right_child].0.size();
+        }
+        self.nodes[node_idx].0.set_size(size);
+    }
+
+    // Recomputes the tree size and the anchor of the subtree rooted at `node_idx`.
+    #[cfg(feature = "component-sizes")]
+    fn update_tree_size(&mut self, node_idx: usize) {
+        let mut tree_size = 1 + self.nodes[node_idx].0.virtual_size();
+        let mut tree_anchor = self.nodes[node_idx].0.virtual_anchor();
+        if self.nodes[node_idx].0.anchor() {
+            tree_anchor += node_idx + 1;
+        }
+        if let Some(left_child) = self.nodes[node_idx].0.left() {
+            tree_size += self.nodes[left_child].0.tree_size();
+            tree_anchor += self.nodes[left_child].0.tree_anchor();
+        }
+        if let Some(right_child) = self.nodes[node_idx].0.right() {
+            tree_size += self.nodes[right_child].0.tree_size();
+            tree_anchor += self.nodes[right_child].0.tree_anchor();
+        }
+        self.nodes[node_idx].0.set_tree_size(tree_size);
+        self.nodes[node_idx].0.set_tree_anchor(tree_anchor);
+    }
+
+    // Registers a new runtime aggregate and computes its values for the whole forest.
+    pub fn register_aggregate(&mut self, aggregate: RuntimeAggregate) -> usize {
+        self.aggregates.push(aggregate);
+        self.rebuild_aggregates();
+        self.aggregates.len() - 1
+    }
+
+    // Returns the first runtime aggregate that cannot be updated lazily (if any).
+    #[cfg(feature = "lazy-updates")]
+    pub fn fixed_aggregate(&self) -> Option<usize> {
+        self.aggregates
+            .iter()
+            .position(|aggregate| !aggregate.is_updatable())
+    }
+
+    #[inline]
+    pub fn runtime_aggregate_of(&self, node_idx: usize, aggregate_idx: usize) -> f64 {
+        self.aggregates[aggregate_idx].value(node_idx)
+    }
+
+    // Turns the right child of `node_idx` (if any) into a path child, without recomputing
+    // the aggregate of `node_idx` (its tree size does not change, as the right child
+    // is only moved to the virtual ones). Returns `false` if there was no right child.
+    fn detach_preferred_child(&mut self, node_idx: usize) -> bool {
+        if let Some(right_idx) = self.nodes[node_idx].0.right() {
+            if let Some(counters) = &mut self.counters {
+                counters.preferred_child_changes += 1;
+            }
+            self.nodes[node_idx].0.set_right(None);
+            self.nodes[right_idx].0.set_parent(Parent::Path(node_idx));
+            #[cfg(feature = "lazy-updates")]
+            {
+                self.nodes[right_idx].0.virtual_seen = self.nodes[node_idx].0.virtual_delta;
+            }
+            #[cfg(feature = "component-sizes")]
+            {
+                let virtual_size =
+                    self.nodes[node_idx].0.virtual_size() + self.nodes[right_idx].0.tree_size();
+                self.nodes[node_idx].0.set_virtual_size(virtual_size);
+                let virtual_anchor =
+                    self.nodes[node_idx].0.virtual_anchor() + self.nodes[right_idx].0.tree_anchor();
+                self.nodes[node_idx].0.set_virtual_anchor(virtual_anchor);
+            }
+            true
+        } else {
+            false
         }
     }
 
-    pub fn remove_preferred_child(&mut self, node_idx: usize) {
-        if let Some(right_idx) = self.nodes[node_idx].right {
-            self.nodes[node_idx].right = None;
-            self.nodes[right_idx].parent = Parent::Path(node_idx);
+    // Splays `node_idx` and removes its preferred child, recomputing its aggregate only once
+    // (and not at all if it was already the root of its splay tree without a right child).
+    pub fn splay_and_remove_preferred_child(&mut self, node_idx: usize) {
+        let rotated = self.splay_rotations(node_idx);
+        if self.detach_preferred_child(node_idx) || rotated {
             self.update(node_idx);
         }
     }
 
+    // Splays `node_idx` and makes `child_idx` (the root of a splay tree hanging off `node_idx`
+    // by its path-parent pointer) its preferred child. The aggregate of `node_idx` is left stale,
+    // as the caller splays `child_idx` right after, whose rotation recomputes it.
+    pub fn splay_and_switch_preferred_child(&mut self, node_idx: usize, child_idx: usize) {
+        self.splay_rotations(node_idx);
+        self.detach_preferred_child(node_idx);
+        #[cfg(feature = "lazy-updates")]
+        self.pull_virtual_delta(child_idx);
+        #[cfg(feature = "component-sizes")]
+        {
+            let virtual_size =
+                self.nodes[node_idx].0.virtual_size() - self.nodes[child_idx].0.tree_size();
+            self.nodes[node_idx].0.set_virtual_size(virtual_size);
+            let virtual_anchor =
+                self.nodes[node_idx].0.virtual_anchor() - self.nodes[child_idx].0.tree_anchor();
+            self.nodes[node_idx].0.set_virtual_anchor(virtual_anchor);
+        }
+        self.set_right(node_idx, child_idx);
+    }
+
     pub fn flip(&mut self, node_idx: usize) {
-        self.nodes[node_idx].flipped ^= true;
+        self.nodes[node_idx].0.toggle_flipped();
         self.normalize(node_idx);
     }
 
@@ -153,26 +917,34 @@ impl<P: Path> Forest<P> {
     //         3   4        1   3
     fn rotate_left(&mut self, node_idx: usize) {
         assert!(
-            self.nodes[node_idx].right.is_some(),
+            self.nodes[node_idx].0.right().is_some(),
             "rotate_left: node_idx does not have a right child"
         );
 
-        let right_child = self.nodes[node_idx].right.unwrap();
-        if let Parent::Node(parent_idx) = self.nodes[node_idx].parent {
-            if self.nodes[parent_idx].left == Some(node_idx) {
-                self.nodes[parent_idx].left = Some(right_child);
+        let right_child = self.nodes[node_idx].0.right().unwrap();
+        if let Parent::Node(parent_idx) = self.nodes[node_idx].0.parent() {
+            if self.nodes[parent_idx].0.left() == Some(node_idx) {
+                self.nodes[parent_idx].0.set_left(Some(right_child));
             } else {
-                self.nodes[parent_idx].right = Some(right_child);
+                self.nodes[parent_idx].0.set_right(Some(right_child));
             }
         }
 
-        self.nodes[node_idx].right = self.nodes[right_child].left;
-        self.nodes[right_child].left = Some(node_idx);
-        self.nodes[right_child].parent = self.nodes[node_idx].parent;
-        self.nodes[node_idx].parent = Parent::Node(right_child);
+        let new_right_child = self.nodes[right_child].0.left();
+        self.nodes[node_idx].0.set_right(new_right_child);
+        self.nodes[right_child].0.set_left(Some(node_idx));
+        let parent = self.nodes[node_idx].0.parent();
+        self.nodes[right_child].0.set_parent(parent);
+        #[cfg(feature = "lazy-updates")]
+        {
+            self.nodes[right_child].0.virtual_seen = self.nodes[node_idx].0.virtual_seen;
+        }
+        self.nodes[node_idx].0.set_parent(Parent::Node(right_child));
 
-        if let Some(new_right_child) = self.nodes[node_idx].right {
-            self.nodes[new_right_child].parent = Parent::Node(node_idx);
+        if let Some(new_right_child) = self.nodes[node_idx].0.right() {
+            self.nodes[new_right_child]
+                .0
+                .set_parent(Parent::Node(node_idx));
         }
     }
 
@@ -185,40 +957,51 @@ impl<P: Path> Forest<P> {
     //     2   3                3   4
     fn rotate_right(&mut self, node_idx: usize) {
         assert!(
-            self.nodes[node_idx].left.is_some(),
+            self.nodes[node_idx].0.left().is_some(),
             "rotate_right: node_idx does not have a left child"
         );
 
-        let left_child = self.nodes[node_idx].left.unwrap();
-        if let Parent::Node(parent_idx) = self.nodes[node_idx].parent {
-            if self.nodes[parent_idx].left == Some(node_idx) {
-                self.nodes[parent_idx].left = Some(left_child);
+        let left_child = self.nodes[node_idx].0.left().unwrap();
+        if let Parent::Node(parent_idx) = self.nodes[node_idx].0.parent() {
+            if self.nodes[parent_idx].0.left() == Some(node_idx) {
+                self.nodes[parent_idx].0.set_left(Some(left_child));
             } else {
-                self.nodes[parent_idx].right = Some(left_child);
+                self.nodes[parent_idx].0.set_right(Some(left_child));
             }
         }
 
-        self.nodes[node_idx].left = self.nodes[left_child].right;
-        self.nodes[left_child].right = Some(node_idx);
-        self.nodes[left_child].parent = self.nodes[node_idx].parent;
-        self.nodes[node_idx].parent = Parent::Node(left_child);
+        let new_left_child = self.nodes[left_child].0.right();
+        self.nodes[node_idx].0.set_left(new_left_child);
+        self.nodes[left_child].0.set_right(Some(node_idx));
+        let parent = self.nodes[node_idx].0.parent();
+        self.nodes[left_child].0.set_parent(parent);
+        #[cfg(feature = "lazy-updates")]
+        {
+            self.nodes[left_child].0.virtual_seen = self.nodes[node_idx].0.virtual_seen;
+        }
+        self.nodes[node_idx].0.set_parent(Parent::Node(left_child));
 
-        if let Some(new_left_child) = self.nodes[node_idx].left {
-            self.nodes[new_left_child].parent = Parent::Node(node_idx);
+        if let Some(new_left_child) = self.nodes[node_idx].0.left() {
+            self.nodes[new_left_child]
+                .0
+                .set_parent(Parent::Node(node_idx));
         }
     }
 
     // Rotates the parent of `node_idx` to the right or left, depending on the relationship between.
     fn rotate(&mut self, node_idx: usize) {
         assert!(
-            matches!(self.nodes[node_idx].parent, Parent::Node(_)),
+            matches!(self.nodes[node_idx].0.parent(), Parent::Node(_)),
             "rotate: node_idx does not have a parent"
         );
+        if let Some(counters) = &mut self.counters {
+            counters.rotations += 1;
+        }
 
-        if let Parent::Node(parent_idx) = self.nodes[node_idx].parent {
+        if let Parent::Node(parent_idx) = self.nodes[node_idx].0.parent() {
             self.normalize(parent_idx);
             self.normalize(node_idx);
-            if self.nodes[parent_idx].left == Some(node_idx) {
+            if self.nodes[parent_idx].0.left() == Some(node_idx) {
                 self.rotate_right(parent_idx);
             } else {
                 self.rotate_left(parent_idx);
@@ -235,10 +1018,20 @@ impl<P: Path> Forest<P> {
     //    /
     //   2
     pub fn splay(&mut self, node_idx: usize) {
-        while let Parent::Node(parent_idx) = self.nodes[node_idx].parent {
-            if let Parent::Node(grandparent_idx) = self.nodes[parent_idx].parent {
-                if (self.nodes[grandparent_idx].left == Some(parent_idx))
-                    == (self.nodes[parent_idx].left == Some(node_idx))
+        // the aggregate of a splay root is up to date, so it is only recomputed after rotations:
+        if self.splay_rotations(node_idx) {
+            self.update(node_idx);
+        }
+    }
+
+    // Performs the rotations of `splay(node_idx)`, updating the aggregates of the nodes
+    // that moved down but not of `node_idx` itself. Returns `false` if no rotation was needed.
+    fn splay_rotations(&mut self, node_idx: usize) -> bool {
+        let mut depth = 0;
+        while let Parent::Node(parent_idx) = self.nodes[node_idx].0.parent() {
+            if let Parent::Node(grandparent_idx) = self.nodes[parent_idx].0.parent() {
+                if (self.nodes[grandparent_idx].0.left() == Some(parent_idx))
+                    == (self.nodes[parent_idx].0.left() == Some(node_idx))
                 {
                     // zig-zig (same direction):
                     self.rotate(parent_idx);
@@ -246,19 +1039,474 @@ impl<P: Path> Forest<P> {
                     // zig-zag:
                     self.rotate(node_idx);
                 }
+                depth += 1;
             }
             // zig
             self.rotate(node_idx);
+            depth += 1;
+        }
+        self.max_splay_depth = self.max_splay_depth.max(depth);
+        if let Some(counters) = &mut self.counters {
+            counters.splays += 1;
         }
         self.normalize(node_idx);
-        self.update(node_idx);
+        depth > 0
+    }
+
+    // Rewrites the weight of every node and recomputes the path aggregates in O(n).
+    pub fn map_weights<F: FnMut(usize, f64) -> f64>(&mut self, mut f: F) {
+        self.version += 1;
+        let order = self.push_lazy_updates();
+        for idx in 0..self.nodes.len() {
+            if self.index.contains(idx) {
+                self.nodes[idx].0.weight = f(idx, self.nodes[idx].0.weight);
+            }
+        }
+        for idx in order.into_iter().rev() {
+            self.update(idx);
+        }
+    }
+
+    // Replaces the weights of the given nodes and recomputes the path aggregates in O(n).
+    pub fn set_weights(&mut self, weights: &[(usize, f64)]) {
+        self.version += 1;
+        let order = self.push_lazy_updates();
+        for &(idx, weight) in weights {
+            self.nodes[idx].0.weight = weight;
+        }
+        for idx in order.into_iter().rev() {
+            self.update(idx);
+        }
+    }
+
+    // Recomputes the path aggregates of all splay trees bottom-up (children before parents).
+    pub fn rebuild_aggregates(&mut self) {
+        self.version += 1; // the aggregates may change, so the memoized queries are stale
+        let lazy = std::mem::replace(&mut self.lazy_aggregates, false);
+        let order = self.push_lazy_updates();
+        for idx in order.into_iter().rev() {
+            self.update(idx);
+        }
+        self.lazy_aggregates = lazy;
+    }
+
+    // Converts the forest to another path aggregate type, preserving its structure and weights.
+    pub fn into_aggregate<Q: Path>(mut self) -> Forest<Q, Ix> {
+        self.push_lazy_updates();
+        let mut forest: Forest<Q, Ix> = Forest {
+            nodes: (0..self.nodes.len())
+                .map(|idx| NodeSlot(self.nodes[idx].0.to_aggregate(idx)))
+                .collect(),
+            index: self.index,
+            max_splay_depth: self.max_splay_depth,
+            counters: self.counters,
+            aggregates: self.aggregates,
+            version: self.version,
+            lazy_aggregates: self.lazy_aggregates,
+            scratch: Q::default(0.0, 0),
+            // the pending updates were pushed down:
+            #[cfg(feature = "lazy-updates")]
+            lazy_ops: LazyOps::new(),
+            node: PhantomData,
+        };
+        forest.rebuild_aggregates();
+        forest
+    }
+
+    // Finds the root of the represented tree containing a node without restructuring the splay trees:
+    // the root is the first node of the path at the top, taking the pending flips into account.
+    pub fn root_of(&self, node_idx: usize) -> usize {
+        let mut top = node_idx;
+        while let Parent::Node(parent_idx) | Parent::Path(parent_idx) = self.nodes[top].0.parent() {
+            top = parent_idx;
+        }
+        let mut root = top;
+        let mut flipped = false;
+        loop {
+            flipped ^= self.nodes[root].0.flipped();
+            let first = if flipped {
+                self.nodes[root].0.right()
+            } else {
+                self.nodes[root].0.left()
+            };
+            match first {
+                Some(idx) => root = idx,
+                None => return root,
+            }
+        }
+    }
+
+    // Lists the nodes of the splay tree containing `node_idx` in order along with their depths,
+    // taking the pending flips into account (as in `in_order`).
+    pub fn splay_tree(&self, node_idx: usize) -> SplayTree {
+        let mut root = node_idx;
+        while let Parent::Node(parent_idx) = self.nodes[root].0.parent() {
+            root = parent_idx;
+        }
+        let path_parent = self.path_parent_of(root);
+        let mut nodes = Vec::new();
+        let mut stack = Vec::new();
+        let mut current = Some((root, 0, false));
+        loop {
+            while let Some((idx, depth, flipped)) = current {
+                let flipped = flipped ^ self.nodes[idx].0.flipped();
+                stack.push((idx, depth, flipped));
+                let left = if flipped {
+                    self.nodes[idx].0.right()
+                } else {
+                    self.nodes[idx].0.left()
+                };
+                current = left.map(|left| (left, depth + 1, flipped));
+            }
+            let (idx, depth, flipped) = match stack.pop() {
+                Some(top) => top,
+                None => break,
+            };
+            nodes.push((idx, depth));
+            let right = if flipped {
+                self.nodes[idx].0.left()
+            } else {
+                self.nodes[idx].0.right()
+            };
+            current = right.map(|right| (right, depth + 1, flipped));
+        }
+        SplayTree {
+            nodes,
+            root,
+            path_parent,
+        }
+    }
+
+    // Lists the nodes of the splay tree rooted at `root_idx` in order, taking the pending flips
+    // into account (i.e. the preferred path from its top-most node to its bottom-most node).
+    pub fn in_order(&self, root_idx: usize) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut stack = Vec::new();
+        let mut current = Some((root_idx, false));
+        loop {
+            // go down to the left-most node, collecting the flips on the way:
+            while let Some((idx, flipped)) = current {
+                let flipped = flipped ^ self.nodes[idx].0.flipped();
+                stack.push((idx, flipped));
+                let left = if flipped {
+                    self.nodes[idx].0.right()
+                } else {
+                    self.nodes[idx].0.left()
+                };
+                current = left.map(|left| (left, flipped));
+            }
+            let (idx, flipped) = match stack.pop() {
+                Some(top) => top,
+                None => break,
+            };
+            path.push(idx);
+            let right = if flipped {
+                self.nodes[idx].0.left()
+            } else {
+                self.nodes[idx].0.right()
+            };
+            current = right.map(|right| (right, flipped));
+        }
+        path
+    }
+
+    // Lists the preferred paths (one per splay tree, from the top-most node to the bottom-most).
+    pub fn preferred_paths(&self) -> Vec<Vec<usize>> {
+        self.node_ids()
+            .filter(|&idx| !matches!(self.nodes[idx].0.parent(), Parent::Node(_)))
+            .map(|root_idx| self.in_order(root_idx))
+            .collect()
+    }
+
+    // Lists the edges of the represented forest as (child, parent) pairs.
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = Vec::new();
+        for root_idx in self.node_ids() {
+            if let Parent::Node(_) = self.nodes[root_idx].0.parent() {
+                continue;
+            }
+            let path = self.in_order(root_idx);
+            for pair in path.windows(2) {
+                edges.push((pair[1], pair[0]));
+            }
+            if let Parent::Path(parent_idx) = self.nodes[root_idx].0.parent() {
+                edges.push((path[0], parent_idx));
+            }
+        }
+        edges
+    }
+
+    // Collects the depth of each node in its splay tree and the size of each splay tree.
+    pub fn splay_stats(&self) -> SplayStats {
+        let mut path_sizes = Vec::new();
+        let mut depth_histogram = Vec::new();
+        let mut stack = Vec::new();
+        for root_idx in self.node_ids() {
+            if let Parent::Node(_) = self.nodes[root_idx].0.parent() {
+                continue;
+            }
+            // walk down the splay tree, visiting each node once:
+            let mut size = 0;
+            stack.push((root_idx, 0));
+            while let Some((idx, depth)) = stack.pop() {
+                if depth_histogram.len() <= depth {
+                    depth_histogram.resize(depth + 1, 0);
+                }
+                depth_histogram[depth] += 1;
+                size += 1;
+                let node = &self.nodes[idx].0;
+                for child in [node.left(), node.right()].into_iter().flatten() {
+                    stack.push((child, depth + 1));
+                }
+            }
+            path_sizes.push(size);
+        }
+        SplayStats {
+            depth_histogram,
+            path_sizes,
+            max_splay_depth: self.max_splay_depth,
+        }
+    }
+
+    pub fn id_reuse(&self) -> bool {
+        self.index.reuse()
+    }
+
+    pub fn set_id_reuse(&mut self, enabled: bool) {
+        self.index.set_reuse(enabled);
+    }
+
+    pub fn id_reuse_policy(&self) -> IdReusePolicy {
+        self.index.policy()
+    }
+
+    pub fn set_id_reuse_policy(&mut self, policy: IdReusePolicy) {
+        self.index.set_policy(policy);
+    }
+
+    pub fn set_instrumentation(&mut self, enabled: bool) {
+        self.counters = if enabled {
+            Some(CostCounters::default())
+        } else {
+            None
+        };
+    }
+
+    pub fn cost_counters(&self) -> Option<CostCounters> {
+        self.counters
+    }
+
+    pub fn count_access(&mut self) {
+        if let Some(counters) = &mut self.counters {
+            counters.accesses += 1;
+        }
+    }
+
+    // Computes the potential function of the forest: the sum of log2(size(v)) over all nodes,
+    // where size(v) is the number of descendants of v in the virtual tree
+    // (i.e. the splay trees connected by their path-parent pointers).
+    #[allow(clippy::cast_precision_loss)]
+    pub fn potential(&self) -> f64 {
+        let virtual_parent = |idx: usize| match self.nodes[idx].0.parent() {
+            Parent::Node(parent_idx) | Parent::Path(parent_idx) => Some(parent_idx),
+            Parent::Root => None,
+        };
+        // order the nodes by their depth in the virtual tree (deepest first):
+        let mut depths: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        for node_idx in self.node_ids() {
+            let mut stack = vec![node_idx];
+            while let Some(parent_idx) = virtual_parent(*stack.last().unwrap()) {
+                if depths[parent_idx].is_some() {
+                    break;
+                }
+                stack.push(parent_idx);
+            }
+            while let Some(idx) = stack.pop() {
+                depths[idx] = Some(virtual_parent(idx).map_or(0, |p| depths[p].unwrap() + 1));
+            }
+        }
+        let mut order = (0..self.nodes.len())
+            .filter(|&idx| depths[idx].is_some())
+            .collect::<Vec<_>>();
+        order.sort_unstable_by_key(|&idx| std::cmp::Reverse(depths[idx]));
+
+        // accumulate the sizes bottom-up:
+        let mut sizes = vec![1_usize; self.nodes.len()];
+        let mut potential = 0.0;
+        for idx in order {
+            potential += (sizes[idx] as f64).log2();
+            if let Some(parent_idx) = virtual_parent(idx) {
+                sizes[parent_idx] += sizes[idx];
+            }
+        }
+        potential
+    }
+}
+
+impl<P: Path + Debug, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> Forest<P, Ix, S> {
+    // Writes the internal state of every node slot, one line each (see `LinkCutTree::dump_state`).
+    pub fn dump_state<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "forest: {} nodes in {} slots, free ids: {:?}",
+            self.len(),
+            self.nodes.len(),
+            self.index.free_ids()
+        )?;
+        for idx in 0..self.nodes.len() {
+            let node = &self.nodes[idx].0;
+            if !self.contains(idx) {
+                writeln!(writer, "{idx}: removed")?;
+                continue;
+            }
+            let parent = match node.parent() {
+                Parent::Node(idx) => format!("node {idx}"),
+                Parent::Path(idx) => format!("path {idx}"),
+                Parent::Root => "root".to_string(),
+            };
+            let child = |child: Option<usize>| child.map_or("-".to_string(), |c| c.to_string());
+            write!(
+                writer,
+                "{idx}: parent {parent}, left {}, right {}, flipped {}, dirty {}, weight {}, ",
+                child(node.left()),
+                child(node.right()),
+                node.flipped(),
+                node.dirty(),
+                node.weight,
+            )?;
+            #[cfg(feature = "degree-tracking")]
+            write!(writer, "degree {}, ", node.degree())?;
+            #[cfg(feature = "depth-queries")]
+            write!(writer, "size {}, ", node.size())?;
+            #[cfg(feature = "component-sizes")]
+            write!(
+                writer,
+                "tree size {} ({} virtual), anchor {}, ",
+                node.tree_size(),
+                node.virtual_size(),
+                node.anchor(),
+            )?;
+            #[cfg(feature = "lazy-updates")]
+            write!(
+                writer,
+                "delta {}, assigned {:?}, path delta {}, virtual delta {:?} ({:?} seen), ",
+                node.delta,
+                node.assigned(),
+                node.path_delta,
+                node.virtual_delta,
+                node.virtual_seen,
+            )?;
+            writeln!(writer, "aggregate {:?}", node.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Ix: IndexType, S: NodeStorage<NodeSlot<FindMax, Ix>>> Forest<FindMax, Ix, S> {
+    // Checks if a node in the splay subtree rooted at `node_idx` has a weight above `threshold`.
+    // Only the dirty nodes are descended into (see `refresh_path`), and their aggregates are not
+    // recomputed: the clean subtrees are decided by their maximum, and the search stops
+    // at the first node above the threshold.
+    pub fn exceeds(&mut self, node_idx: usize, threshold: f64) -> bool {
+        let mut stack = vec![node_idx];
+        while let Some(idx) = stack.pop() {
+            if !self.nodes[idx].0.dirty() {
+                if self.nodes[idx].0.path.weight > threshold {
+                    return true;
+                }
+                continue;
+            }
+            // the pending flips and additions are pushed down first, as in the splaying:
+            self.normalize(idx);
+            let node = &self.nodes[idx].0;
+            if node.weight > threshold {
+                return true;
+            }
+            stack.extend([node.left(), node.right()].into_iter().flatten());
+        }
+        false
+    }
+}
+
+// `PathAdd::add_delta` and `PathAssign::assign` of the aggregate, taken from the trait bounds
+// of the updates that leave additions or assignments pending. The splaying pushes them down
+// for any aggregate, but there can only be pending ones once such an update has set them.
+#[cfg(feature = "lazy-updates")]
+struct LazyOps<P> {
+    add: Option<fn(&mut P, f64, usize)>,
+    assign: Option<fn(&mut P, f64, usize, usize)>,
+}
+
+#[cfg(feature = "lazy-updates")]
+impl<P> LazyOps<P> {
+    fn new() -> Self {
+        LazyOps {
+            add: None,
+            assign: None,
+        }
+    }
+
+    fn add(&self, aggregate: &mut P, delta: f64, size: usize) {
+        let add = self.add.expect("a pending addition requires `PathAdd`");
+        add(aggregate, delta, size);
+    }
+
+    fn assign(&self, aggregate: &mut P, weight: f64, index: usize, size: usize) {
+        let assign = self
+            .assign
+            .expect("a pending assignment requires `PathAssign`");
+        assign(aggregate, weight, index, size);
+    }
+}
+
+// The pending changes to a weight: it is replaced by `assigned` (if any) and then `added` to.
+#[cfg(feature = "lazy-updates")]
+#[derive(Clone, Copy, Default)]
+struct PendingWeight {
+    assigned: Option<f64>,
+    added: f64,
+}
+
+#[cfg(feature = "lazy-updates")]
+impl PendingWeight {
+    fn added(added: f64) -> Self {
+        PendingWeight {
+            assigned: None,
+            added,
+        }
+    }
+
+    // The changes a node has not pushed down to its splay children yet, in the order they apply.
+    fn of_node<P: Path, Ix: IndexType>(node: &Node<P, Ix>) -> Self {
+        match node.assigned() {
+            Some(weight) => PendingWeight {
+                assigned: Some(weight),
+                added: node.path_delta,
+            },
+            None => PendingWeight::added(node.delta + node.path_delta),
+        }
+    }
+
+    // The changes of `self` followed by the ones of `later`.
+    fn then(self, later: Self) -> Self {
+        match later.assigned {
+            Some(_) => later,
+            None => PendingWeight {
+                assigned: self.assigned,
+                added: self.added + later.added,
+            },
+        }
+    }
+
+    fn apply(self, weight: f64) -> f64 {
+        self.assigned.unwrap_or(weight) + self.added
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Forest;
-    use crate::path::FindMax;
+    use crate::path::{FindMax, FindSum};
 
     #[test]
     pub fn create_node() {
@@ -512,6 +1760,23 @@ mod tests {
         assert!(forest.right_of(c).is_none());
     }
 
+    #[test]
+    pub fn splay_root_keeps_aggregate() {
+        let mut forest: Forest<FindSum> = super::Forest::new();
+        let a = forest.create_node(1.0);
+        let b = forest.create_node(2.0);
+        forest.set_left(a, b);
+        forest.set_instrumentation(true);
+        forest.splay(a); // already the root, nothing to recompute
+        assert_eq!(forest.cost_counters().unwrap().updates, 0);
+        assert_eq!(forest.aggregated_path_of(a).sum, 3.0);
+
+        forest.splay(b); // a single rotation, which recomputes both nodes
+        assert_eq!(forest.cost_counters().unwrap().updates, 2);
+        assert_eq!(forest.aggregated_path_of(b).sum, 3.0);
+        assert_eq!(forest.aggregated_path_of(a).sum, 1.0);
+    }
+
     #[test]
     pub fn splay_preserve_path_pointer() {
         // Node 'a' has a path pointer to Node 'p',
@@ -533,7 +1798,7 @@ mod tests {
         forest.set_right(a, b);
         forest.set_left(b, c);
         forest.set_right(p, a);
-        forest.remove_preferred_child(p);
+        forest.detach_preferred_child(p);
         assert_eq!(forest.path_parent_of(a), Some(p));
 
         forest.splay(c);
@@ -541,6 +1806,124 @@ mod tests {
         assert_eq!(forest.path_parent_of(c), Some(p));
     }
 
+    #[test]
+    pub fn splay_stats() {
+        // form the tree and splay on 'c':
+        //   a                  c
+        //    \       =>       / \
+        //     b              a   b
+        //    /
+        //   c
+        let mut forest: Forest<FindMax> = super::Forest::new();
+        let a = forest.create_node(0.0);
+        let b = forest.create_node(0.0);
+        let c = forest.create_node(0.0);
+        let d = forest.create_node(0.0);
+        forest.set_right(a, b);
+        forest.set_left(b, c);
+        let stats = forest.splay_stats();
+        assert_eq!(stats.depth_histogram, vec![2, 1, 1]);
+        assert_eq!(stats.path_sizes, vec![3, 1]);
+        assert_eq!(stats.max_splay_depth, 0);
+
+        forest.splay(c);
+        let stats = forest.splay_stats();
+        assert_eq!(stats.depth_histogram, vec![2, 2]);
+        assert_eq!(stats.path_sizes, vec![3, 1]);
+        assert_eq!(stats.max_splay_depth, 2);
+
+        // deleted nodes are not counted:
+        forest.delete_node(d);
+        let stats = forest.splay_stats();
+        assert_eq!(stats.depth_histogram, vec![1, 2]);
+        assert_eq!(stats.path_sizes, vec![3]);
+    }
+
+    #[test]
+    pub fn potential() {
+        // a chain of splay trees:
+        //   a
+        //    \
+        //     b - - c (path-parent pointer from c to b)
+        let mut forest: Forest<FindMax> = super::Forest::new();
+        let a = forest.create_node(0.0);
+        let b = forest.create_node(0.0);
+        let c = forest.create_node(0.0);
+        assert_eq!(forest.potential(), 0.0);
+
+        forest.set_right(a, b);
+        forest.set_right(b, c);
+        forest.detach_preferred_child(b);
+        assert_eq!(forest.path_parent_of(c), Some(b));
+        // sizes: a = 3, b = 2, c = 1
+        assert!((forest.potential() - 3_f64.log2() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    pub fn in_order_with_flips() {
+        //      a
+        //     / \
+        //    b   c
+        //       /
+        //      d
+        let mut forest: Forest<FindMax> = super::Forest::new();
+        let a = forest.create_node(0.0);
+        let b = forest.create_node(0.0);
+        let c = forest.create_node(0.0);
+        let d = forest.create_node(0.0);
+        forest.set_left(a, b);
+        forest.set_right(a, c);
+        forest.set_left(c, d);
+        assert_eq!(forest.in_order(a), vec![b, a, d, c]);
+
+        // flipping 'c' (lazily) reverses its subtree:
+        forest.nodes[c].0.set_flipped(true);
+        assert_eq!(forest.in_order(a), vec![b, a, c, d]);
+
+        // flipping 'a' reverses the whole path:
+        forest.nodes[a].0.set_flipped(true);
+        assert_eq!(forest.in_order(a), vec![d, c, a, b]);
+    }
+
+    #[test]
+    pub fn edges() {
+        // the preferred path b - a - c with a path-parent pointer from d to c:
+        let mut forest: Forest<FindMax> = super::Forest::new();
+        let a = forest.create_node(0.0);
+        let b = forest.create_node(0.0);
+        let c = forest.create_node(0.0);
+        let d = forest.create_node(0.0);
+        forest.set_left(a, b);
+        forest.set_right(a, c);
+        forest.set_right(c, d);
+        forest.detach_preferred_child(c);
+        let mut edges = forest.edges();
+        edges.sort_unstable();
+        assert_eq!(edges, vec![(a, b), (c, a), (d, c)]);
+    }
+
+    #[test]
+    pub fn map_weights() {
+        //      a
+        //     / \
+        //    b   c
+        //       /
+        //      d
+        let mut forest: Forest<FindSum> = super::Forest::new();
+        let a = forest.create_node(1.0);
+        let b = forest.create_node(2.0);
+        let c = forest.create_node(3.0);
+        let d = forest.create_node(4.0);
+        forest.set_left(a, b);
+        forest.set_right(a, c);
+        forest.set_left(c, d);
+        forest.map_weights(|idx, weight| if idx == b { 0.0 } else { weight * 10.0 });
+        assert_eq!(forest.aggregated_path_of(a).sum, 80.0);
+        assert_eq!(forest.aggregated_path_of(b).sum, 0.0);
+        assert_eq!(forest.aggregated_path_of(c).sum, 70.0);
+        assert_eq!(forest.aggregated_path_of(d).sum, 40.0);
+    }
+
     #[test]
     pub fn toggle_flip() {
         let mut forest: Forest<FindMax> = super::Forest::new();
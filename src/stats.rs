@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// Shape statistics of the auxiliary (splay) trees that represent the forest.
+///
+/// Each preferred path of the represented forest is stored as a splay tree,
+/// so these numbers describe how the forest is currently decomposed into paths
+/// and how balanced the splay trees are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplayStats {
+    /// `depth_histogram[d]` is the number of nodes at depth `d` of their splay tree.
+    pub depth_histogram: Vec<usize>,
+    /// Sizes of the preferred paths (one entry per splay tree), ordered by the id of the splay root.
+    pub path_sizes: Vec<usize>,
+    /// The maximum depth of a node that has been splayed so far.
+    pub max_splay_depth: usize,
+}
+
+/// The auxiliary splay tree that stores the preferred path of a node,
+/// built by [`LinkCutTree::splay_tree`](crate::LinkCutTree::splay_tree) for debugging.
+///
+/// Its `Display` implementation prints the nodes in order, one per line, indented by their depths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplayTree {
+    /// The nodes of the splay tree in order (i.e. from the top-most to the bottom-most node
+    /// of the preferred path), along with their depths in the splay tree.
+    pub nodes: Vec<(usize, usize)>,
+    /// The root of the splay tree.
+    pub root: usize,
+    /// The node that the top-most node of the path hangs off in the represented tree
+    /// (`None` if the path starts at the root of the represented tree).
+    pub path_parent: Option<usize>,
+}
+
+impl fmt::Display for SplayTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path_parent {
+            Some(parent) => writeln!(f, "splay tree of {} (path-parent: {parent})", self.root)?,
+            None => writeln!(f, "splay tree of {} (root path)", self.root)?,
+        }
+        for &(node, depth) in &self.nodes {
+            writeln!(f, "{:indent$}{node}", "", indent = 2 * depth)?;
+        }
+        Ok(())
+    }
+}
+
+/// Counters of the actual work performed by the link-cut tree operations.
+///
+/// Together with [`LinkCutTree::potential`](crate::LinkCutTree::potential), these can be used
+/// to empirically check the `O(logn)` amortized bound of the operations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CostCounters {
+    /// Number of `access` operations (every link-cut tree operation performs at least one).
+    pub accesses: usize,
+    /// Number of splay operations.
+    pub splays: usize,
+    /// Number of single rotations performed by the splay operations.
+    pub rotations: usize,
+    /// Number of preferred child changes performed by the `access` operations.
+    pub preferred_child_changes: usize,
+    /// Number of path aggregate recomputations (of a single node each).
+    pub updates: usize,
+}
@@ -0,0 +1,168 @@
+use std::ops::{Index, IndexMut};
+
+/// The storage of the nodes of a link-cut tree, indexed by their ids.
+///
+/// The nodes are pushed with consecutive ids and never taken out (the slots of removed nodes
+/// are overwritten when their ids are reused), so a storage only has to grow.
+/// By default, the nodes are stored in a `Vec`, but they can also live in a slab,
+/// a fixed arena or a paged store, e.g. to keep them next to other per-entity data.
+///
+/// # Examples
+/// ```
+/// use std::ops::{Index, IndexMut};
+/// use lctree::{FindSum, LinkCutTree, NodeSlot, NodeStorage};
+///
+/// // Stores the nodes in pages of 64, which never move once they are allocated:
+/// struct Paged<T>(Vec<Vec<T>>);
+///
+/// impl<T> Default for Paged<T> {
+///     fn default() -> Self {
+///         Paged(Vec::new())
+///     }
+/// }
+///
+/// impl<T> Index<usize> for Paged<T> {
+///     type Output = T;
+///     fn index(&self, idx: usize) -> &T {
+///         &self.0[idx / 64][idx % 64]
+///     }
+/// }
+///
+/// impl<T> IndexMut<usize> for Paged<T> {
+///     fn index_mut(&mut self, idx: usize) -> &mut T {
+///         &mut self.0[idx / 64][idx % 64]
+///     }
+/// }
+///
+/// impl<T> NodeStorage<T> for Paged<T> {
+///     fn len(&self) -> usize {
+///         self.0.iter().map(Vec::len).sum()
+///     }
+///
+///     fn push(&mut self, node: T) {
+///         match self.0.last_mut() {
+///             Some(page) if page.len() < 64 => page.push(node),
+///             _ => self.0.push({
+///                 let mut page = Vec::with_capacity(64);
+///                 page.push(node);
+///                 page
+///             }),
+///         }
+///     }
+/// }
+///
+/// let mut lctree: LinkCutTree<FindSum, usize, Paged<NodeSlot<FindSum, usize>>> = LinkCutTree::new();
+/// let nodes: Vec<usize> = (0..100).map(|i| lctree.make_tree(i as f64)).collect();
+/// for i in 1..nodes.len() {
+///     lctree.link(nodes[i], nodes[i - 1]);
+/// }
+/// assert_eq!(lctree.path(nodes[0], nodes[99]).sum, 4950.0);
+/// ```
+pub trait NodeStorage<T>: Default + Index<usize, Output = T> + IndexMut<usize> {
+    /// Creates an empty storage with room for at least `capacity` nodes
+    /// (by default, an empty storage).
+    #[must_use]
+    fn with_capacity(_capacity: usize) -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of nodes in the storage.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the storage holds no nodes.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of nodes the storage can hold without reallocating
+    /// (by default, the number of nodes it holds).
+    fn capacity(&self) -> usize {
+        self.len()
+    }
+
+    /// Appends a node, whose id is the previous length of the storage.
+    fn push(&mut self, node: T);
+}
+
+impl<T> NodeStorage<T> for Vec<T> {
+    fn with_capacity(capacity: usize) -> Self {
+        Vec::with_capacity(capacity)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    fn push(&mut self, node: T) {
+        self.push(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::{Index, IndexMut};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::NodeStorage;
+    use crate::{FindMax, LinkCutTree, NodeSlot};
+
+    // A storage that only grows one node at a time, to check that no capacity is assumed:
+    struct Unreserved<T>(Vec<T>);
+
+    impl<T> Default for Unreserved<T> {
+        fn default() -> Self {
+            Unreserved(Vec::new())
+        }
+    }
+
+    impl<T> Index<usize> for Unreserved<T> {
+        type Output = T;
+        fn index(&self, idx: usize) -> &T {
+            &self.0[idx]
+        }
+    }
+
+    impl<T> IndexMut<usize> for Unreserved<T> {
+        fn index_mut(&mut self, idx: usize) -> &mut T {
+            &mut self.0[idx]
+        }
+    }
+
+    impl<T> NodeStorage<T> for Unreserved<T> {
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn push(&mut self, node: T) {
+            self.0.push(node);
+        }
+    }
+
+    #[test]
+    pub fn custom_storage() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+        let mut custom: LinkCutTree<FindMax, usize, Unreserved<NodeSlot<FindMax, usize>>> =
+            LinkCutTree::new();
+        for _ in 0..100 {
+            let weight = rng.gen_range(0.0..100.0);
+            assert_eq!(lctree.make_tree(weight), custom.make_tree(weight));
+        }
+        for _ in 0..2000 {
+            let v = rng.gen_range(0..100);
+            let w = rng.gen_range(0..100);
+            match rng.gen_range(0..3) {
+                0 => assert_eq!(lctree.link(v, w), custom.link(v, w)),
+                1 => assert_eq!(lctree.cut(v, w), custom.cut(v, w)),
+                _ => assert_eq!(lctree.path(v, w), custom.path(v, w)),
+            }
+        }
+        assert_eq!(custom.len(), 100);
+        assert!(custom.diff(&lctree).is_empty());
+    }
+}
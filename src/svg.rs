@@ -0,0 +1,152 @@
+use std::fmt::Write;
+
+use crate::{index::IndexType, node::NodeSlot, path::Path, storage::NodeStorage, LinkCutTree};
+
+const NODE_RADIUS: f64 = 16.0;
+const H_SPACING: f64 = 48.0; // horizontal distance between neighboring leaves
+const V_SPACING: f64 = 64.0; // vertical distance between levels
+const MARGIN: f64 = 32.0;
+
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+    /// Renders the represented forest as an SVG image
+    /// (available with the `svg` feature).
+    ///
+    /// Every tree is drawn in layers below its current root (see [`LinkCutTree::findroot`]),
+    /// and the trees are placed next to each other in increasing order of their roots.
+    /// Each node is labeled with its id, and its weight is written next to it.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::LinkCutTree;
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let alice = lctree.make_tree(1.0);
+    /// let bob = lctree.make_tree(2.5);
+    /// lctree.link(alice, bob);
+    ///
+    /// let svg = lctree.to_svg();
+    /// assert!(svg.starts_with("<svg"));
+    /// assert!(svg.contains(">2.5</text>"));
+    /// ```
+    #[must_use]
+    pub fn to_svg(&mut self) -> String {
+        let weights = self.weights();
+        let len = weights.last().map_or(0, |&(idx, _)| idx + 1);
+        let mut children = vec![Vec::new(); len];
+        let mut has_parent = vec![false; len];
+        for (child, parent) in self.edges() {
+            children[parent].push(child);
+            has_parent[child] = true;
+        }
+        for list in &mut children {
+            list.sort_unstable();
+        }
+
+        // Layered layout: the leaves are placed left to right in depth-first order,
+        // and every parent is centered above its children.
+        let mut positions = vec![(0.0, 0_u32); len];
+        let mut next_leaf = 0.0;
+        let mut max_depth = 0_u32;
+        for &(root, _) in weights.iter().filter(|&&(idx, _)| !has_parent[idx]) {
+            let mut stack = vec![(root, 0_u32, false)];
+            while let Some((node, depth, visited)) = stack.pop() {
+                max_depth = max_depth.max(depth);
+                if children[node].is_empty() {
+                    positions[node] = (next_leaf, depth);
+                    next_leaf += 1.0;
+                } else if visited {
+                    let first = positions[children[node][0]].0;
+                    let last = positions[children[node][children[node].len() - 1]].0;
+                    positions[node] = ((first + last) / 2.0, depth);
+                } else {
+                    stack.push((node, depth, true));
+                    for &child in children[node].iter().rev() {
+                        stack.push((child, depth + 1, false));
+                    }
+                }
+            }
+        }
+
+        let coordinates = |idx: usize| {
+            let (x, depth) = positions[idx];
+            (
+                MARGIN + x * H_SPACING,
+                MARGIN + f64::from(depth) * V_SPACING,
+            )
+        };
+        let width = 2.0 * MARGIN + (next_leaf - 1.0).max(0.0) * H_SPACING + H_SPACING;
+        let height = 2.0 * MARGIN + f64::from(max_depth) * V_SPACING;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="12">"#
+        );
+        for (parent, list) in children.iter().enumerate() {
+            let (x1, y1) = coordinates(parent);
+            for &child in list {
+                let (x2, y2) = coordinates(child);
+                let _ = writeln!(
+                    svg,
+                    r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="black"/>"#
+                );
+            }
+        }
+        for (idx, weight) in weights {
+            let (x, y) = coordinates(idx);
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{x}" cy="{y}" r="{NODE_RADIUS}" fill="white" stroke="black"/>"#
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{x}" y="{y}" text-anchor="middle" dominant-baseline="central">{idx}</text>"#
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" fill="gray">{weight}</text>"#,
+                x + NODE_RADIUS + 2.0,
+                y - NODE_RADIUS
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LinkCutTree;
+
+    #[test]
+    pub fn svg_layout() {
+        // We form a link-cut tree for the following forest:
+        //     a      d
+        //    / \
+        //   b   c
+        let mut lctree = LinkCutTree::default();
+        let a = lctree.make_tree(0.5);
+        let b = lctree.make_tree(1.5);
+        let c = lctree.make_tree(2.5);
+        let d = lctree.make_tree(3.5);
+        lctree.link(b, a);
+        lctree.link(c, a);
+        assert_eq!(lctree.findroot(b), a);
+
+        let svg = lctree.to_svg();
+        assert_eq!(svg.matches("<circle").count(), 4);
+        assert_eq!(svg.matches("<line").count(), 2);
+        // the leaves b, c and d are placed left to right, and a is centered above b and c:
+        let circle = |idx: usize| {
+            let label = format!(">{idx}</text>");
+            let end = svg.find(&label).unwrap();
+            let start = svg[..end].rfind("<circle").unwrap();
+            svg[start..end].split('"').nth(1).unwrap().to_string() // cx
+        };
+        assert_eq!(circle(b), "32");
+        assert_eq!(circle(c), "80");
+        assert_eq!(circle(d), "128");
+        assert_eq!(circle(a), "56");
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    error::InvalidNode,
+    index::IndexType,
+    node::NodeSlot,
+    outcome::{CutOutcome, LinkOutcome},
+    path::Path,
+    storage::NodeStorage,
+    LinkCutTree,
+};
+
+/// The changes made to the forest inside a transaction, along with the roots of the trees
+/// at the time (so that undoing a change also restores the roots).
+enum Change {
+    Linked {
+        v: usize,
+        w: usize,
+        roots: (usize, usize),
+    },
+    Cut {
+        v: usize,
+        w: usize,
+        root: usize,
+    },
+    Weight {
+        v: usize,
+        weight: f64,
+    },
+    Created(usize),
+}
+
+/// The operations of a transaction (see [`LinkCutTree::transaction`]), which are undone
+/// if the transaction fails.
+///
+/// The operations check the ids of the nodes (as `try_link` and the like do), so that
+/// the `?` operator aborts the transaction on an invalid id.
+pub struct Transaction<'a, P: Path, Ix: IndexType = usize, S = Vec<NodeSlot<P, Ix>>> {
+    lctree: &'a mut LinkCutTree<P, Ix, S>,
+    changes: Vec<Change>,
+}
+
+/// # Transactions
+/// Multiple changes can be applied atomically (see [`Transaction`]).
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+    /// Runs `f` as a transaction: if it returns an error, all the changes it made
+    /// (links, cuts, weight replacements and new nodes) are undone in reverse order,
+    /// including the roots of the trees, and the error is returned.
+    /// Undoing takes `O(logn)` amortized time per change.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `f`, after undoing its changes.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{LinkCutTree, LinkOutcome};
+    ///
+    /// let mut lctree = LinkCutTree::default();
+    /// let nodes = lctree.extend_forest([0.0; 4]);
+    /// lctree.link(nodes[0], nodes[1]);
+    ///
+    /// // rewire node 1 from node 0 to node 2, and attach node 3 to node 0:
+    /// let result = lctree.transaction(|txn| -> Result<(), Box<dyn std::error::Error>> {
+    ///     txn.cut(nodes[0], nodes[1])?;
+    ///     txn.link(nodes[1], nodes[2])?;
+    ///     txn.replace_weight(nodes[2], 5.0)?;
+    ///     match txn.link(nodes[3], 9)? {
+    ///         LinkOutcome::Linked => Ok(()),
+    ///         LinkOutcome::AlreadyConnected { .. } => Err("already connected".into()),
+    ///     }
+    /// });
+    /// // there is no node 9, so nothing changes:
+    /// assert_eq!(result.unwrap_err().to_string(), "there is no node with id 9");
+    /// assert!(lctree.connected(nodes[0], nodes[1]));
+    /// assert!(!lctree.connected(nodes[1], nodes[2]));
+    /// assert_eq!(lctree.path(nodes[2], nodes[2]).weight, 0.0);
+    /// ```
+    pub fn transaction<T, E, F>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Transaction<'_, P, Ix, S>) -> Result<T, E>,
+    {
+        let mut txn = Transaction {
+            lctree: self,
+            changes: Vec::new(),
+        };
+        let result = f(&mut txn);
+        if result.is_err() {
+            txn.rollback();
+        }
+        result
+    }
+}
+
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> Transaction<'_, P, Ix, S> {
+    /// Links two nodes (see [`LinkCutTree::link`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn link(&mut self, v: usize, w: usize) -> Result<LinkOutcome, InvalidNode> {
+        self.check_node(v)?;
+        self.check_node(w)?;
+        let roots = (self.lctree.find_root(v), self.lctree.find_root(w));
+        let outcome = self.lctree.link(v, w);
+        if outcome.is_linked() {
+            self.changes.push(Change::Linked { v, w, roots });
+        }
+        Ok(outcome)
+    }
+
+    /// Cuts the edge between two nodes (see [`LinkCutTree::cut`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn cut(&mut self, v: usize, w: usize) -> Result<CutOutcome, InvalidNode> {
+        self.check_node(v)?;
+        self.check_node(w)?;
+        let root = self.lctree.find_root(v);
+        let outcome = self.lctree.cut(v, w);
+        if outcome != CutOutcome::NoSuchEdge {
+            self.changes.push(Change::Cut { v, w, root });
+        }
+        Ok(outcome)
+    }
+
+    /// Replaces the weight of a node and returns its previous weight
+    /// (see [`LinkCutTree::replace_weight`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] if `v` is not a node of the forest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight validation is enabled and the weight is NaN or infinite.
+    pub fn replace_weight(&mut self, v: usize, weight: f64) -> Result<f64, InvalidNode> {
+        self.check_node(v)?;
+        let old = self.lctree.replace_weight(v, weight);
+        self.changes.push(Change::Weight { v, weight: old });
+        Ok(old)
+    }
+
+    /// Creates a new tree with a single node and returns its id (see [`LinkCutTree::make_tree`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight validation is enabled and the weight is NaN or infinite.
+    pub fn make_tree(&mut self, weight: f64) -> usize {
+        let v = self.lctree.make_tree(weight);
+        self.changes.push(Change::Created(v));
+        v
+    }
+
+    /// Checks if two nodes are connected (see [`LinkCutTree::connected`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn connected(&mut self, v: usize, w: usize) -> Result<bool, InvalidNode> {
+        self.lctree.try_connected(v, w)
+    }
+
+    /// Aggregates the weights on the path between two nodes (see [`LinkCutTree::path`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNode`] with the first id that is not a node of the forest.
+    pub fn path(&mut self, v: usize, w: usize) -> Result<P, InvalidNode> {
+        self.lctree.try_path(v, w)
+    }
+
+    fn check_node(&self, v: usize) -> Result<(), InvalidNode> {
+        if self.lctree.contains(v) {
+            Ok(())
+        } else {
+            Err(InvalidNode(v))
+        }
+    }
+
+    // Undoes the changes in reverse order, so every change is undone in the forest it was made in.
+    fn rollback(&mut self) {
+        while let Some(change) = self.changes.pop() {
+            match change {
+                Change::Linked { v, w, roots } => {
+                    self.lctree.cut(v, w);
+                    self.lctree.reroot(roots.0);
+                    self.lctree.reroot(roots.1);
+                }
+                Change::Cut { v, w, root } => {
+                    self.lctree.link(v, w);
+                    self.lctree.reroot(root);
+                }
+                Change::Weight { v, weight } => {
+                    self.lctree.replace_weight(v, weight);
+                }
+                Change::Created(v) => self.lctree.remove_tree_unchecked(v),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FindSum, InvalidNode, LinkCutTree};
+
+    #[test]
+    pub fn rollback() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0, 4.0, 5.0]);
+        lctree.link_all(&[(1, 0), (2, 0), (3, 2)]);
+        let snapshot = lctree.snapshot();
+        let roots: Vec<usize> = nodes.iter().map(|&v| lctree.findroot(v)).collect();
+
+        let result: Result<(), InvalidNode> = lctree.transaction(|txn| {
+            txn.cut(nodes[0], nodes[2])?;
+            txn.link(nodes[2], nodes[4])?;
+            txn.link(nodes[0], nodes[3])?;
+            let v = txn.make_tree(6.0);
+            txn.link(v, nodes[1])?;
+            txn.replace_weight(nodes[0], 10.0)?;
+            txn.replace_weight(nodes[0], 20.0)?;
+            assert_eq!(
+                txn.path(v, nodes[4])?.sum,
+                6.0 + 2.0 + 20.0 + 4.0 + 3.0 + 5.0
+            );
+            txn.cut(nodes[0], 7)?;
+            Ok(())
+        });
+        assert_eq!(result, Err(InvalidNode(7)));
+        assert!(snapshot.diff(&lctree.snapshot()).is_empty());
+        let actual: Vec<usize> = nodes.iter().map(|&v| lctree.findroot(v)).collect();
+        assert_eq!(actual, roots);
+        assert_eq!(lctree.make_tree(0.0), 5); // the id of the undone node is free again
+    }
+
+    #[test]
+    pub fn commit() {
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0, 2.0, 3.0]);
+        let result = lctree.transaction(|txn| -> Result<f64, InvalidNode> {
+            txn.link(nodes[0], nodes[1])?;
+            txn.link(nodes[1], nodes[2])?;
+            assert!(!txn.link(nodes[0], nodes[2])?.is_linked());
+            assert!(txn.connected(nodes[0], nodes[2])?);
+            Ok(txn.path(nodes[0], nodes[2])?.sum)
+        });
+        assert_eq!(result, Ok(6.0));
+        assert_eq!(lctree.edges().count(), 2);
+    }
+}
@@ -0,0 +1,217 @@
+use std::cmp::Ordering;
+
+use crate::{index::IndexType, node::NodeSlot, path::Path, storage::NodeStorage, LinkCutTree};
+
+/// The virtual (auxiliary) tree of a set of nodes, built by [`LinkCutTree::virtual_tree`].
+///
+/// It contains the given nodes and the lowest common ancestors of their pairs, where every path
+/// between them is contracted into a single edge that carries the aggregate of its weights.
+#[derive(Clone)]
+pub struct VirtualTree<P: Path> {
+    /// The nodes of the virtual tree in depth-first order, starting with its root.
+    pub nodes: Vec<usize>,
+    /// The edges of the virtual tree, in the depth-first order of their children.
+    pub edges: Vec<VirtualEdge<P>>,
+}
+
+/// An edge of a [`VirtualTree`], which stands for a contracted path of the represented tree.
+#[derive(Clone, Copy)]
+pub struct VirtualEdge<P: Path> {
+    pub parent: usize,
+    pub child: usize,
+    /// The aggregate of the weights on the path from `child` up to `parent`, excluding `parent`
+    /// (so every weight of the virtual tree's span, except the root's, is counted exactly once).
+    pub path: P,
+}
+
+impl<P: Path, Ix: IndexType, S: NodeStorage<NodeSlot<P, Ix>>> LinkCutTree<P, Ix, S> {
+    /// Builds the virtual tree over a set of `k` nodes under the current root of their tree
+    /// (see [`VirtualTree`]), or returns `None` if the set is empty or not connected.
+    /// This takes `O(k logk)` accesses, so dynamic programming over a small subset of a huge tree
+    /// can run on the virtual tree instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// // a is the root of the tree:
+    /// //       a(1)
+    /// //      /   \
+    /// //    b(2)   c(3)
+    /// //    |       |
+    /// //    d(4)   f(6)
+    /// //   /   \
+    /// // e(5)  g(7)
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let (a, b, c, d, e, f, g) = (0, 1, 2, 3, 4, 5, 6);
+    /// lctree.extend_forest(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    /// lctree.link(d, b);
+    /// lctree.link(e, d);
+    /// lctree.link(g, d);
+    /// lctree.link(f, c);
+    ///
+    /// // the virtual tree of e, g and f also contains their lcas d and a:
+    /// let tree = lctree.virtual_tree(&[e, g, f]).unwrap();
+    /// assert_eq!(tree.nodes[0], a);
+    /// assert_eq!(tree.nodes.len(), 5);
+    ///
+    /// // the path from d up to a (excluding a) is contracted:
+    /// let edge = tree.edges.iter().find(|edge| edge.child == d).unwrap();
+    /// assert_eq!(edge.parent, a);
+    /// assert_eq!(edge.path.sum, 4.0 + 2.0);
+    /// ```
+    pub fn virtual_tree(&mut self, nodes: &[usize]) -> Option<VirtualTree<P>> {
+        self.lca_many(nodes)?; // checks that the nodes are connected
+        let mut nodes = nodes.to_vec();
+        self.sort_preorder(&mut nodes);
+        // the lcas of the adjacent nodes in depth-first order are the lcas of all pairs:
+        let lcas: Vec<usize> = nodes
+            .windows(2)
+            .map(|pair| self.connected_lca(pair[0], pair[1]))
+            .collect();
+        nodes.extend(lcas);
+        self.sort_preorder(&mut nodes);
+
+        // link every node to the closest ancestor in depth-first order:
+        let mut edges = Vec::with_capacity(nodes.len() - 1);
+        let mut stack: Vec<usize> = Vec::with_capacity(nodes.len());
+        for &v in &nodes {
+            while let Some(&top) = stack.last() {
+                if self.connected_lca(top, v) == top {
+                    break;
+                }
+                stack.pop();
+            }
+            if let Some(&parent) = stack.last() {
+                let path = self.path_below(parent, v);
+                edges.push(VirtualEdge {
+                    parent,
+                    child: v,
+                    path,
+                });
+            }
+            stack.push(v);
+        }
+        Some(VirtualTree { nodes, edges })
+    }
+
+    /// Aggregates the weights of the nodes of the smallest subtree that connects a set of `k` nodes
+    /// (their Steiner tree), or returns `None` if the set is empty or not connected.
+    /// Every node of the subtree is aggregated exactly once, using the paths of the virtual tree
+    /// (see [`LinkCutTree::virtual_tree`]), so this takes `O(k logk)` accesses as well.
+    ///
+    /// # Examples
+    /// ```
+    /// use lctree::{FindSum, LinkCutTree};
+    ///
+    /// // the cable lengths of the sites of a network:
+    /// //       a(1)
+    /// //      /   \
+    /// //    b(2)   c(3)
+    /// //    |       |
+    /// //    d(4)   f(6)
+    /// //   /   \
+    /// // e(5)  g(7)
+    /// let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    /// let (a, b, c, d, e, f, g) = (0, 1, 2, 3, 4, 5, 6);
+    /// lctree.extend_forest([1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+    /// lctree.link(b, a);
+    /// lctree.link(c, a);
+    /// lctree.link(d, b);
+    /// lctree.link(e, d);
+    /// lctree.link(g, d);
+    /// lctree.link(f, c);
+    ///
+    /// // connecting e, g and b takes the cables of e, d, g and b:
+    /// assert_eq!(lctree.steiner_aggregate(&[e, g, b]).unwrap().sum, 18.0);
+    /// // connecting e and f takes every cable except g's:
+    /// assert_eq!(lctree.steiner_aggregate(&[e, f]).unwrap().sum, 21.0);
+    /// ```
+    pub fn steiner_aggregate(&mut self, nodes: &[usize]) -> Option<P> {
+        let tree = self.virtual_tree(nodes)?;
+        // the paths of the edges exclude their parents, so only the root is missing:
+        let root = tree.nodes[0];
+        let mut aggregate = P::default(self.weight_of(root), root);
+        for edge in tree.edges {
+            aggregate.aggregate(edge.path);
+        }
+        Some(aggregate)
+    }
+
+    // Sorts the nodes in a depth-first order of their tree (visiting the children by their ids)
+    // and removes the duplicates.
+    fn sort_preorder(&mut self, nodes: &mut Vec<usize>) {
+        nodes.sort_unstable();
+        nodes.dedup();
+        nodes.sort_by(|&v, &w| self.cmp_preorder(v, w));
+    }
+
+    fn cmp_preorder(&mut self, v: usize, w: usize) -> Ordering {
+        if v == w {
+            return Ordering::Equal;
+        }
+        let lca = self.connected_lca(v, w);
+        if lca == v {
+            return Ordering::Less;
+        }
+        if lca == w {
+            return Ordering::Greater;
+        }
+        // compare the children of the lca that lead to v and w:
+        let depth = self.depth_of(lca) + 1;
+        let v_child = self.ancestor_at_depth(v, depth);
+        let w_child = self.ancestor_at_depth(w, depth);
+        v_child.cmp(&w_child)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FindSum, LinkCutTree};
+
+    #[test]
+    pub fn virtual_tree() {
+        // a path 0 - 1 - ... - 9 rooted at 0, with a branch 4 - 10 - 11:
+        let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+        let nodes = lctree.extend_forest([1.0; 12]);
+        for i in 1..10 {
+            lctree.link(nodes[i], nodes[i - 1]);
+        }
+        lctree.link(nodes[10], nodes[4]);
+        lctree.link(nodes[11], nodes[10]);
+        assert_eq!(lctree.findroot(nodes[11]), nodes[0]);
+
+        let tree = lctree
+            .virtual_tree(&[nodes[9], nodes[11], nodes[7]])
+            .unwrap();
+        assert_eq!(tree.nodes, vec![nodes[4], nodes[7], nodes[9], nodes[11]]);
+        let edges: Vec<(usize, usize, f64)> = tree
+            .edges
+            .iter()
+            .map(|edge| (edge.parent, edge.child, edge.path.sum))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (nodes[4], nodes[7], 3.0),
+                (nodes[7], nodes[9], 2.0),
+                (nodes[4], nodes[11], 2.0)
+            ]
+        );
+        assert_eq!(lctree.findroot(nodes[11]), nodes[0]); // the root does not change
+
+        // a single node:
+        let tree = lctree.virtual_tree(&[nodes[3]]).unwrap();
+        assert_eq!(tree.nodes, vec![nodes[3]]);
+        assert!(tree.edges.is_empty());
+
+        // disconnected nodes:
+        let other = lctree.make_tree(0.0);
+        assert!(lctree.virtual_tree(&[nodes[3], other]).is_none());
+        assert!(lctree.virtual_tree(&[]).is_none());
+        assert!(lctree.steiner_aggregate(&[nodes[3], other]).is_none());
+        assert_eq!(lctree.steiner_aggregate(&[nodes[3]]).unwrap().sum, 1.0);
+    }
+}
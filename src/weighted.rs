@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use crate::{outcome::LinkOutcome, path::Path, LinkCutTree};
+
+/// The aggregate of a path in a [`WeightedLinkCutTree`]: the node aggregate `N` over
+/// the weights of the nodes on the path, and the edge aggregate `E` over the weights of the edges.
+///
+/// The `idx` reported by the node aggregate (e.g. by `FindMax`) is a node id,
+/// and the one reported by the edge aggregate is an edge id (see [`WeightedLinkCutTree::endpoints`]).
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeEdge<N: Path, E: Path> {
+    pub node: Option<N>,
+    pub edge: Option<E>, // `None` if the path has no edges
+}
+
+impl<N: Path, E: Path> Path for NodeEdge<N, E> {
+    // The nodes of the forest are stored at even indices and the edges at odd indices:
+    fn default(weight: f64, index: usize) -> Self {
+        if index % 2 == 0 {
+            NodeEdge {
+                node: Some(N::default(weight, index / 2)),
+                edge: None,
+            }
+        } else {
+            NodeEdge {
+                node: None,
+                edge: Some(E::default(weight, index / 2)),
+            }
+        }
+    }
+
+    fn aggregate(&mut self, other: Self) {
+        self.node = merge(self.node.take(), other.node);
+        self.edge = merge(self.edge.take(), other.edge);
+    }
+}
+
+fn merge<P: Path>(lhs: Option<P>, rhs: Option<P>) -> Option<P> {
+    match (lhs, rhs) {
+        (Some(mut lhs), Some(rhs)) => {
+            lhs.aggregate(rhs);
+            Some(lhs)
+        }
+        (lhs, rhs) => lhs.or(rhs),
+    }
+}
+
+/// A link-cut tree where both the nodes and the edges carry weights.
+///
+/// Path queries aggregate the node weights with `N` and the edge weights with `E`
+/// (e.g. the maximum node weight and the sum of the edge weights on a path), see [`NodeEdge`].
+/// Every edge is represented by a node of its own in the underlying [`LinkCutTree`], so
+/// the operations take `O(logn)` amortized time as well.
+///
+/// # Examples
+/// ```
+/// use lctree::{FindMax, FindSum, WeightedLinkCutTree};
+///
+/// // a road network, where the nodes are weighted by their traffic and the edges by their lengths:
+/// let mut roads: WeightedLinkCutTree<FindMax, FindSum> = WeightedLinkCutTree::new();
+/// let a = roads.make_tree(3.0);
+/// let b = roads.make_tree(7.0);
+/// let c = roads.make_tree(5.0);
+/// roads.link(a, b, 10.0);
+/// roads.link(b, c, 2.5);
+///
+/// let path = roads.path(a, c);
+/// assert_eq!(path.node.unwrap().idx, b); // the busiest node
+/// assert_eq!(path.edge.unwrap().sum, 12.5); // the length of the path
+///
+/// assert_eq!(roads.cut(a, b), Some(10.0)); // the weight of the removed edge
+/// assert!(!roads.connected(a, c));
+/// ```
+///
+/// The edges can also carry a payload of type `D` (e.g. a capacity, a label or a timestamp),
+/// which is given to `link_with_data` and returned by `cut_with_data`:
+/// ```
+/// use lctree::{FindMax, FindSum, WeightedLinkCutTree};
+///
+/// let mut roads: WeightedLinkCutTree<FindMax, FindSum, &str> = WeightedLinkCutTree::new();
+/// let a = roads.make_tree(3.0);
+/// let b = roads.make_tree(7.0);
+/// roads.link_with_data(a, b, 10.0, "highway");
+///
+/// let edge = roads.edge(a, b).unwrap();
+/// assert_eq!(roads.edge_data(edge), Some(&"highway"));
+/// assert_eq!(roads.cut_with_data(a, b), Some((10.0, "highway")));
+/// ```
+pub struct WeightedLinkCutTree<N: Path, E: Path, D = ()> {
+    // Node `v` is stored at index `2v` of the link-cut tree and edge `e` at index `2e + 1`.
+    // Each node comes with an edge slot, which is enough as a forest of `n` nodes has
+    // less than `n` edges.
+    lctree: LinkCutTree<NodeEdge<N, E>>,
+    edge_ids: HashMap<(usize, usize), usize>,
+    edges: Vec<Option<(usize, usize, f64, D)>>, // the endpoints, weight and payload of each edge slot
+    free_edges: Vec<usize>,
+}
+
+impl<N: Path, E: Path, D> WeightedLinkCutTree<N, E, D> {
+    /// Creates a new empty forest.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            lctree: LinkCutTree::new(),
+            edge_ids: HashMap::new(),
+            edges: Vec::new(),
+            free_edges: Vec::new(),
+        }
+    }
+
+    /// Creates a new tree with a single node with the given weight and returns its id
+    /// (the ids are assigned in order, starting from 0).
+    pub fn make_tree(&mut self, weight: f64) -> usize {
+        let v = self.lctree.make_tree(weight) / 2;
+        self.lctree.make_tree(0.0); // the edge slot
+        self.edges.push(None);
+        self.free_edges.push(v);
+        v
+    }
+
+    /// Returns the number of nodes in the forest.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns `true` if the forest has no nodes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Same as [`WeightedLinkCutTree::link`], but also attaches a payload to the new edge
+    /// (which is dropped if the nodes are already connected).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node does not exist.
+    #[allow(clippy::must_use_candidate)]
+    pub fn link_with_data(&mut self, v: usize, w: usize, weight: f64, data: D) -> LinkOutcome {
+        self.check(v, "link_with_data");
+        self.check(w, "link_with_data");
+        if self.lctree.connected(2 * v, 2 * w) {
+            let root = self.lctree.findroot(2 * w) / 2;
+            return LinkOutcome::AlreadyConnected { root };
+        }
+        let edge = self.free_edges.pop().unwrap();
+        self.lctree.update_weight(2 * edge + 1, weight);
+        // the root of w's tree stays the root of the merged tree:
+        self.lctree.link(2 * edge + 1, 2 * w);
+        self.lctree.link(2 * v, 2 * edge + 1);
+        self.edge_ids.insert((v.min(w), v.max(w)), edge);
+        self.edges[edge] = Some((v, w, weight, data));
+        LinkOutcome::Linked
+    }
+
+    /// Removes the edge between nodes `v` and `w` and returns its weight,
+    /// or `None` if there is no such edge.
+    #[allow(clippy::must_use_candidate)]
+    pub fn cut(&mut self, v: usize, w: usize) -> Option<f64> {
+        self.cut_with_data(v, w).map(|(weight, _)| weight)
+    }
+
+    /// Removes the edge between nodes `v` and `w` and returns its weight along with its payload,
+    /// or `None` if there is no such edge.
+    #[allow(clippy::must_use_candidate)]
+    pub fn cut_with_data(&mut self, v: usize, w: usize) -> Option<(f64, D)> {
+        let edge = self.edge_ids.remove(&(v.min(w), v.max(w)))?;
+        let (_, _, weight, data) = self.edges[edge].take()?;
+        // the edge node is left as a single node tree, so it can be reused by `link`:
+        self.lctree.cut(2 * edge + 1, 2 * v);
+        self.lctree.cut(2 * edge + 1, 2 * w);
+        self.free_edges.push(edge);
+        Some((weight, data))
+    }
+
+    /// Checks if two nodes are connected (i.e. in the same tree).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node does not exist.
+    pub fn connected(&mut self, v: usize, w: usize) -> bool {
+        self.check(v, "connected");
+        self.check(w, "connected");
+        self.lctree.connected(2 * v, 2 * w)
+    }
+
+    /// Aggregates the weights of the nodes and the edges on the path between `v` and `w`.
+    /// Both aggregates are `None` if the nodes are not connected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node does not exist.
+    pub fn path(&mut self, v: usize, w: usize) -> NodeEdge<N, E> {
+        self.check(v, "path");
+        self.check(w, "path");
+        if self.lctree.connected(2 * v, 2 * w) {
+            self.lctree.path(2 * v, 2 * w)
+        } else {
+            NodeEdge {
+                node: None,
+                edge: None,
+            }
+        }
+    }
+
+    /// Finds the root of the tree containing `v` (see [`LinkCutTree::findroot`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    pub fn findroot(&mut self, v: usize) -> usize {
+        self.check(v, "findroot");
+        self.lctree.findroot(2 * v) / 2
+    }
+
+    /// Returns the id of the edge between nodes `v` and `w`, if there is one.
+    #[must_use]
+    pub fn edge(&self, v: usize, w: usize) -> Option<usize> {
+        self.edge_ids.get(&(v.min(w), v.max(w))).copied()
+    }
+
+    /// Returns the weight of the edge between nodes `v` and `w`, if there is one.
+    #[must_use]
+    pub fn edge_weight(&self, v: usize, w: usize) -> Option<f64> {
+        let edge = self.edge(v, w)?;
+        self.edges[edge].as_ref().map(|&(_, _, weight, _)| weight)
+    }
+
+    /// Returns the payload of the edge with the given id, or `None` if the edge was cut.
+    #[must_use]
+    pub fn edge_data(&self, edge: usize) -> Option<&D> {
+        self.edges.get(edge)?.as_ref().map(|(_, _, _, data)| data)
+    }
+
+    /// Returns a mutable reference to the payload of the edge with the given id,
+    /// or `None` if the edge was cut.
+    pub fn edge_data_mut(&mut self, edge: usize) -> Option<&mut D> {
+        self.edges
+            .get_mut(edge)?
+            .as_mut()
+            .map(|(_, _, _, data)| data)
+    }
+
+    /// Returns the nodes connected by the edge with the given id (in the order given to `link`),
+    /// or `None` if the edge was cut. The edge ids are reused after the edges are cut.
+    #[must_use]
+    pub fn endpoints(&self, edge: usize) -> Option<(usize, usize)> {
+        self.edges.get(edge)?.as_ref().map(|&(v, w, _, _)| (v, w))
+    }
+
+    fn check(&self, v: usize, operation: &str) {
+        assert!(v < self.len(), "{operation}: no such node");
+    }
+}
+
+impl<N: Path, E: Path, D: Default> WeightedLinkCutTree<N, E, D> {
+    /// Creates an edge with the given weight between nodes `v` and `w`,
+    /// unless they are already connected (see [`LinkCutTree::link`]).
+    /// The edge gets the default payload (see [`WeightedLinkCutTree::link_with_data`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node does not exist.
+    #[allow(clippy::must_use_candidate)]
+    pub fn link(&mut self, v: usize, w: usize, weight: f64) -> LinkOutcome {
+        self.link_with_data(v, w, weight, D::default())
+    }
+}
+
+impl<N: Path, E: Path, D> Default for WeightedLinkCutTree<N, E, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeightedLinkCutTree;
+    use crate::{FindMax, FindMin, FindSum, LinkOutcome};
+
+    #[test]
+    pub fn node_and_edge_aggregates() {
+        // a(1) -5- b(4) -2- c(3) -9- d(2)
+        let mut forest: WeightedLinkCutTree<FindSum, FindMax> = WeightedLinkCutTree::new();
+        let weights = [1.0, 4.0, 3.0, 2.0];
+        let nodes: Vec<usize> = weights.iter().map(|&w| forest.make_tree(w)).collect();
+        let (a, b, c, d) = (nodes[0], nodes[1], nodes[2], nodes[3]);
+        assert!(forest.link(a, b, 5.0).is_linked());
+        assert!(forest.link(c, b, 2.0).is_linked());
+        assert!(forest.link(c, d, 9.0).is_linked());
+
+        let path = forest.path(a, d);
+        assert_eq!(path.node.unwrap().sum, 10.0);
+        let heaviest = path.edge.unwrap();
+        assert_eq!(heaviest.weight, 9.0);
+        assert_eq!(forest.endpoints(heaviest.idx), Some((c, d)));
+        assert_eq!(forest.edge(d, c), Some(heaviest.idx));
+
+        let path = forest.path(b, b);
+        assert_eq!(path.node.unwrap().sum, 4.0);
+        assert!(path.edge.is_none());
+
+        // path queries reroot the tree at their first node:
+        assert_eq!(
+            forest.link(a, d, 1.0),
+            LinkOutcome::AlreadyConnected { root: b }
+        );
+    }
+
+    #[test]
+    pub fn edges_are_reused() {
+        let mut forest: WeightedLinkCutTree<FindMin, FindMin> = WeightedLinkCutTree::default();
+        let a = forest.make_tree(0.0);
+        let b = forest.make_tree(0.0);
+        let c = forest.make_tree(0.0);
+        forest.link(a, b, 1.0);
+        forest.link(b, c, 2.0);
+        assert_eq!(forest.cut(b, a), Some(1.0));
+        assert_eq!(forest.cut(b, a), None);
+        assert_eq!(forest.edge_weight(a, b), None);
+        assert!(forest.path(a, c).edge.is_none());
+
+        // the slot of the removed edge gets the new weight:
+        forest.link(c, a, 3.0);
+        assert_eq!(forest.edge_weight(a, c), Some(3.0));
+        assert_eq!(forest.path(a, b).edge.unwrap().weight, 2.0);
+        assert_eq!(forest.findroot(b), a);
+        assert_eq!(forest.len(), 3);
+    }
+
+    #[test]
+    pub fn edge_data() {
+        // the capacities and the installation years of the pipes of a network:
+        let mut network: WeightedLinkCutTree<FindSum, FindMin, u32> = WeightedLinkCutTree::new();
+        let a = network.make_tree(0.0);
+        let b = network.make_tree(0.0);
+        let c = network.make_tree(0.0);
+        network.link_with_data(a, b, 5.0, 1990);
+        network.link(b, c, 2.0); // the default payload
+        assert_eq!(
+            network.link_with_data(a, c, 1.0, 2020),
+            LinkOutcome::AlreadyConnected { root: c }
+        );
+
+        let ab = network.edge(a, b).unwrap();
+        let bc = network.edge(b, c).unwrap();
+        assert_eq!(network.edge_data(ab), Some(&1990));
+        assert_eq!(network.edge_data(bc), Some(&0));
+        *network.edge_data_mut(bc).unwrap() = 2001;
+        assert_eq!(network.path(a, c).edge.unwrap().weight, 2.0); // the bottleneck
+
+        assert_eq!(network.cut_with_data(c, b), Some((2.0, 2001)));
+        assert_eq!(network.edge_data(bc), None);
+        assert_eq!(network.cut_with_data(c, b), None);
+        assert_eq!(network.edge_data(100), None);
+    }
+}
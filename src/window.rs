@@ -0,0 +1,190 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{path::FindMin, LinkCutTree};
+
+/// Answers connectivity queries over the edges inserted within a sliding time window.
+///
+/// Every edge has an insertion time and expires once the time advances by `window` past it.
+/// The structure maintains a spanning forest of the active edges that prefers the most recent
+/// edges: when an edge closes a cycle, it replaces the oldest edge of the cycle, which is
+/// the one that would expire first. So the expired edges can simply be cut from the forest.
+/// Each insertion and query takes `O(logn)` amortized time.
+///
+/// # Examples
+/// ```
+/// use lctree::SlidingWindowConnectivity;
+///
+/// // 3 vertices, where the edges stay active for 10 units of time:
+/// let mut window = SlidingWindowConnectivity::new(3, 10);
+/// window.insert_edge(0, 1, 0);
+/// window.insert_edge(1, 2, 5);
+/// assert!(window.connected(0, 2));
+///
+/// window.advance(10); // the first edge expires
+/// assert!(!window.connected(0, 2));
+/// assert!(window.connected(1, 2));
+///
+/// window.insert_edge(0, 2, 12);
+/// window.advance(15); // the second edge expires
+/// assert!(window.connected(0, 2));
+/// assert!(!window.connected(0, 1));
+/// ```
+pub struct SlidingWindowConnectivity {
+    // The vertices are the first nodes of the link-cut tree (with infinite weights), and every
+    // edge of the spanning forest is a node of its own weighted by its serial number:
+    lctree: LinkCutTree<FindMin>,
+    num_vertices: usize,
+    window: u64,
+    now: u64,
+    next_serial: u64,
+    // The serial numbers and insertion times of the edges, in insertion order:
+    edges: VecDeque<(u64, u64)>,
+    // The edges of the spanning forest by serial number: (edge node, u, v).
+    forest: HashMap<u64, (usize, usize, usize)>,
+    // The serial number of each edge node of the spanning forest.
+    serials: HashMap<usize, u64>,
+}
+
+impl SlidingWindowConnectivity {
+    /// Creates a structure over `num_vertices` vertices (with ids `0..num_vertices`),
+    /// where the edges stay active for `window` units of time after their insertion.
+    #[must_use]
+    pub fn new(num_vertices: usize, window: u64) -> Self {
+        let mut lctree = LinkCutTree::new();
+        for _ in 0..num_vertices {
+            lctree.make_tree(f64::INFINITY);
+        }
+        Self {
+            lctree,
+            num_vertices,
+            window,
+            now: 0,
+            next_serial: 0,
+            edges: VecDeque::new(),
+            forest: HashMap::new(),
+            serials: HashMap::new(),
+        }
+    }
+
+    /// Returns the current time (i.e. the latest time passed to `insert_edge` or `advance`).
+    #[must_use]
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Returns the number of active edges (including the ones that are not in the spanning forest).
+    #[must_use]
+    pub fn active_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Advances the time to `time`, expiring the edges inserted at or before `time - window`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `time` is earlier than the current time.
+    pub fn advance(&mut self, time: u64) {
+        assert!(time >= self.now, "advance: the time cannot go backwards");
+        self.now = time;
+        while let Some(&(serial, inserted)) = self.edges.front() {
+            if inserted.saturating_add(self.window) > time {
+                break;
+            }
+            self.edges.pop_front();
+            if self.forest.contains_key(&serial) {
+                self.remove_forest_edge(serial);
+            }
+        }
+    }
+
+    /// Inserts an edge between vertices `u` and `v` at time `time`
+    /// (advancing the current time to `time` first).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `time` is earlier than the current time or if a vertex does not exist.
+    pub fn insert_edge(&mut self, u: usize, v: usize, time: u64) {
+        assert!(
+            u < self.num_vertices && v < self.num_vertices,
+            "insert_edge: no such vertex"
+        );
+        self.advance(time);
+        if self.window == 0 {
+            return; // the edge expires right away
+        }
+        let serial = self.next_serial;
+        self.next_serial += 1;
+        self.edges.push_back((serial, time));
+        if u == v {
+            return;
+        }
+        if self.lctree.connected(u, v) {
+            // replace the oldest edge on the cycle (the new edge is the most recent one):
+            let oldest = self.lctree.path(u, v).idx;
+            let oldest_serial = self.serials[&oldest];
+            self.remove_forest_edge(oldest_serial);
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let node = self.lctree.make_tree(serial as f64);
+        self.lctree.link(node, u);
+        self.lctree.link(node, v);
+        self.forest.insert(serial, (node, u, v));
+        self.serials.insert(node, serial);
+    }
+
+    /// Checks if two vertices are connected by the active edges.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex does not exist.
+    pub fn connected(&mut self, u: usize, v: usize) -> bool {
+        assert!(
+            u < self.num_vertices && v < self.num_vertices,
+            "connected: no such vertex"
+        );
+        self.lctree.connected(u, v)
+    }
+
+    fn remove_forest_edge(&mut self, serial: u64) {
+        let (node, u, v) = self.forest.remove(&serial).unwrap();
+        self.serials.remove(&node);
+        self.lctree.cut(node, u);
+        self.lctree.cut(node, v);
+        self.lctree.remove_tree_unchecked(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlidingWindowConnectivity;
+
+    #[test]
+    pub fn cycle_replacement() {
+        // a triangle, where the newest edges replace the oldest one in the spanning forest:
+        let mut window = SlidingWindowConnectivity::new(3, 10);
+        window.insert_edge(0, 1, 0);
+        window.insert_edge(1, 2, 1);
+        window.insert_edge(2, 0, 2); // replaces (0, 1) in the spanning forest
+        assert_eq!(window.active_edges(), 3);
+
+        window.advance(10);
+        assert_eq!(window.active_edges(), 2);
+        assert!(window.connected(0, 1));
+        window.advance(11);
+        assert!(window.connected(0, 2));
+        assert!(!window.connected(0, 1));
+        assert!(!window.connected(1, 2));
+        window.advance(12);
+        assert!(!window.connected(0, 2));
+        assert_eq!(window.active_edges(), 0);
+    }
+
+    #[test]
+    pub fn empty_window() {
+        let mut window = SlidingWindowConnectivity::new(2, 0);
+        window.insert_edge(0, 1, 5);
+        assert!(!window.connected(0, 1));
+        assert!(window.connected(1, 1));
+        assert_eq!(window.now(), 5);
+    }
+}
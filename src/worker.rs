@@ -0,0 +1,400 @@
+use std::{
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{mpsc, Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    index::IndexType,
+    outcome::{CutOutcome, LinkOutcome},
+    path::Path,
+    LinkCutTree,
+};
+
+type Command<P, Ix> = Box<dyn FnOnce(&mut LinkCutTree<P, Ix>) + Send>;
+
+/// A worker thread that owns a link-cut tree and applies the commands sent by its
+/// [`ForestHandle`]s one at a time, in the order they were sent.
+///
+/// This is a single-writer front end for async services: every command returns a [`Reply`]
+/// future, which can be awaited on any executor (e.g. tokio) without blocking it.
+///
+/// A command that panics only fails its own reply (with [`ReplyError::Panicked`]), and the worker
+/// goes on with the next command. The panic should happen before the command changes the forest
+/// (as with the checks of the link-cut tree's methods), otherwise the forest may be left broken.
+///
+/// # Examples
+/// ```
+/// use lctree::{FindSum, ForestWorker, LinkCutTree};
+///
+/// let worker = ForestWorker::spawn(LinkCutTree::<FindSum>::new());
+/// let handle = worker.handle();
+///
+/// // The commands are applied in order, even if their replies are not awaited:
+/// let alice = handle.make_tree(1.0);
+/// let bob = handle.make_tree(2.0);
+/// let (alice, bob) = (alice.wait().unwrap(), bob.wait().unwrap());
+/// handle.link(alice, bob);
+///
+/// // Usually, the replies are awaited in async code: `handle.path(alice, bob).await`.
+/// let sum = handle.execute(move |lctree| lctree.path(alice, bob).sum);
+/// assert_eq!(sum.wait(), Ok(3.0));
+///
+/// drop(handle);
+/// let lctree = worker.shutdown().unwrap();
+/// assert_eq!(lctree.len(), 2);
+/// ```
+pub struct ForestWorker<P: Path, Ix: IndexType = usize> {
+    handle: ForestHandle<P, Ix>,
+    thread: JoinHandle<LinkCutTree<P, Ix>>,
+}
+
+impl<P, Ix> ForestWorker<P, Ix>
+where
+    P: Path + Send + 'static,
+    Ix: IndexType + Send + 'static,
+{
+    /// Moves the link-cut tree to a new worker thread.
+    #[must_use]
+    pub fn spawn(mut lctree: LinkCutTree<P, Ix>) -> Self {
+        let (sender, receiver) = mpsc::channel::<Command<P, Ix>>();
+        let thread = thread::spawn(move || {
+            for command in receiver {
+                // the reply of a panicking command is resolved with an error when it unwinds:
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| command(&mut lctree)));
+            }
+            lctree
+        });
+        Self {
+            handle: ForestHandle { sender },
+            thread,
+        }
+    }
+
+    /// Returns a new handle to send commands to the worker.
+    #[must_use]
+    pub fn handle(&self) -> ForestHandle<P, Ix> {
+        self.handle.clone()
+    }
+
+    /// Waits until all the handles are dropped and all the commands are applied,
+    /// then stops the worker and returns the link-cut tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns the panic payload if the worker thread panicked outside of a command
+    /// (e.g. while dropping one).
+    pub fn shutdown(self) -> thread::Result<LinkCutTree<P, Ix>> {
+        drop(self.handle);
+        self.thread.join()
+    }
+}
+
+/// Sends commands to a [`ForestWorker`] (the handles can be cloned and sent to other threads).
+pub struct ForestHandle<P: Path, Ix: IndexType = usize> {
+    sender: mpsc::Sender<Command<P, Ix>>,
+}
+
+impl<P: Path, Ix: IndexType> Clone for ForestHandle<P, Ix> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<P, Ix> ForestHandle<P, Ix>
+where
+    P: Path + Send + 'static,
+    Ix: IndexType + Send + 'static,
+{
+    /// Sends a command that runs `f` on the link-cut tree and returns a future of its result.
+    /// The command is sent immediately: it is applied even if the reply is never awaited.
+    #[allow(clippy::must_use_candidate)]
+    pub fn execute<T, F>(&self, f: F) -> Reply<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut LinkCutTree<P, Ix>) -> T + Send + 'static,
+    {
+        let slot = Arc::new(Slot::default());
+        let sender = ReplySender {
+            slot: Arc::clone(&slot),
+        };
+        let command: Command<P, Ix> = Box::new(move |lctree| sender.send(f(lctree)));
+        // if the worker stopped, the command is dropped and the reply is resolved with an error:
+        let _ = self.sender.send(command);
+        Reply { slot }
+    }
+
+    /// Sends a `make_tree` command (see [`LinkCutTree::make_tree`]).
+    #[must_use]
+    pub fn make_tree(&self, weight: f64) -> Reply<usize> {
+        self.execute(move |lctree| lctree.make_tree(weight))
+    }
+
+    /// Sends a `link` command (see [`LinkCutTree::link`]).
+    #[allow(clippy::must_use_candidate)]
+    pub fn link(&self, v: usize, w: usize) -> Reply<LinkOutcome> {
+        self.execute(move |lctree| lctree.link(v, w))
+    }
+
+    /// Sends a `cut` command (see [`LinkCutTree::cut`]).
+    #[allow(clippy::must_use_candidate)]
+    pub fn cut(&self, v: usize, w: usize) -> Reply<CutOutcome> {
+        self.execute(move |lctree| lctree.cut(v, w))
+    }
+
+    /// Sends a `connected` query (see [`LinkCutTree::connected`]).
+    #[must_use]
+    pub fn connected(&self, v: usize, w: usize) -> Reply<bool> {
+        self.execute(move |lctree| lctree.connected(v, w))
+    }
+
+    /// Sends a `path` query (see [`LinkCutTree::path`]).
+    #[must_use]
+    pub fn path(&self, v: usize, w: usize) -> Reply<P> {
+        self.execute(move |lctree| lctree.path(v, w))
+    }
+
+    /// Sends a `findroot` query (see [`LinkCutTree::findroot`]).
+    #[must_use]
+    pub fn findroot(&self, v: usize) -> Reply<usize> {
+        self.execute(move |lctree| lctree.findroot(v))
+    }
+}
+
+/// The error returned by a [`Reply`] when its command was not applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyError {
+    /// The worker stopped before applying the command.
+    WorkerStopped,
+    /// The command panicked (the worker goes on with the next command).
+    Panicked,
+}
+
+impl fmt::Display for ReplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplyError::WorkerStopped => {
+                write!(f, "the worker stopped before applying the command")
+            }
+            ReplyError::Panicked => write!(f, "the command panicked"),
+        }
+    }
+}
+
+impl std::error::Error for ReplyError {}
+
+// The result of the command: pending until it is applied, then ready until the reply takes it.
+enum Outcome<T> {
+    Pending,
+    Ready(Result<T, ReplyError>),
+    Taken,
+}
+
+impl<T> Outcome<T> {
+    fn take(&mut self) -> Option<Result<T, ReplyError>> {
+        match std::mem::replace(self, Outcome::Taken) {
+            Outcome::Pending => {
+                *self = Outcome::Pending;
+                None
+            }
+            Outcome::Ready(result) => Some(result),
+            Outcome::Taken => panic!("the reply was already taken"),
+        }
+    }
+}
+
+// The result of the command and the waker of the task awaiting it.
+type SlotState<T> = (Outcome<T>, Option<Waker>);
+
+struct Slot<T> {
+    state: Mutex<SlotState<T>>,
+    ready: Condvar,
+}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new((Outcome::Pending, None)),
+            ready: Condvar::new(),
+        }
+    }
+}
+
+impl<T> Slot<T> {
+    fn resolve(&self, result: Result<T, ReplyError>) {
+        let mut state = self.state.lock().unwrap();
+        if let Outcome::Pending = state.0 {
+            state.0 = Outcome::Ready(result);
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+            self.ready.notify_all();
+        }
+    }
+}
+
+// Resolves the reply with an error if it is dropped without sending a result
+// (i.e. the command panicked, or it was never applied).
+struct ReplySender<T> {
+    slot: Arc<Slot<T>>,
+}
+
+impl<T> ReplySender<T> {
+    fn send(self, value: T) {
+        self.slot.resolve(Ok(value));
+    }
+}
+
+impl<T> Drop for ReplySender<T> {
+    fn drop(&mut self) {
+        self.slot.resolve(Err(if thread::panicking() {
+            ReplyError::Panicked
+        } else {
+            ReplyError::WorkerStopped
+        }));
+    }
+}
+
+/// The future result of a command sent to a [`ForestWorker`].
+pub struct Reply<T> {
+    slot: Arc<Slot<T>>,
+}
+
+impl<T> Reply<T> {
+    /// Blocks the current thread until the command is applied (for use outside of async code).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command was not applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reply was already taken by polling the future to completion.
+    /// Polling the future again after it completed panics as well.
+    pub fn wait(self) -> Result<T, ReplyError> {
+        let mut state = self.slot.state.lock().unwrap();
+        loop {
+            if let Some(result) = state.0.take() {
+                return result;
+            }
+            state = self.slot.ready.wait(state).unwrap();
+        }
+    }
+}
+
+impl<T> Future for Reply<T> {
+    type Output = Result<T, ReplyError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.slot.state.lock().unwrap();
+        if let Some(result) = state.0.take() {
+            Poll::Ready(result)
+        } else {
+            state.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ForestWorker, ReplyError};
+    use crate::{FindMax, LinkCutTree};
+    use std::{
+        future::Future,
+        sync::Arc,
+        task::{Context, Poll, Wake},
+        thread::{self, Thread},
+    };
+
+    // A minimal executor that parks the thread until the future is woken.
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    pub fn async_commands() {
+        let worker = ForestWorker::spawn(LinkCutTree::<FindMax>::new());
+        let handle = worker.handle();
+        let (a, b) = block_on(async {
+            let a = handle.make_tree(1.0).await.unwrap();
+            let b = handle.make_tree(5.0).await.unwrap();
+            let c = handle.make_tree(2.0).await.unwrap();
+            assert!(handle.link(a, b).await.unwrap().is_linked());
+            assert!(handle.link(b, c).await.unwrap().is_linked());
+            assert!(handle.connected(a, c).await.unwrap());
+            assert_eq!(handle.path(a, c).await.unwrap().idx, b);
+            assert_eq!(handle.findroot(a).await, Ok(a)); // path(a, c) rerooted the tree at a
+            assert!(handle.cut(b, c).await.unwrap().is_cut());
+            assert!(!handle.connected(a, c).await.unwrap());
+            (a, b)
+        });
+
+        // many handles on other threads:
+        let threads = (0..4)
+            .map(|_| {
+                let handle = worker.handle();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        drop(handle.make_tree(0.0));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // a panicking command only fails its own reply:
+        let failed = handle.execute(move |lctree| lctree.remove_tree_unchecked(a));
+        assert_eq!(failed.wait(), Err(ReplyError::Panicked));
+        assert!(handle.connected(a, b).wait().unwrap());
+
+        drop(handle);
+        let lctree = worker.shutdown().unwrap();
+        assert_eq!(lctree.len(), 403);
+    }
+
+    #[test]
+    #[should_panic(expected = "the reply was already taken")]
+    pub fn wait_after_poll() {
+        let worker = ForestWorker::spawn(LinkCutTree::<FindMax>::new());
+        let mut reply = worker.handle().make_tree(0.0);
+        assert_eq!(block_on(&mut reply), Ok(0));
+        let _ = reply.wait();
+    }
+
+    #[test]
+    pub fn shutdown() {
+        let worker = ForestWorker::spawn(LinkCutTree::<FindMax>::new());
+        let handle = worker.handle();
+        for _ in 0..10 {
+            drop(handle.make_tree(0.0));
+        }
+        drop(handle);
+        let lctree = worker.shutdown().unwrap();
+        assert_eq!(lctree.len(), 10);
+    }
+}
@@ -0,0 +1,182 @@
+use lctree::{FindSum, LinkCutTree, Operation};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+// Replays every script of the regression corpus (see `tests/corpus/README.md`)
+// and checks the results against a brute-force model.
+#[test]
+pub fn regression_corpus() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut scripts: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "txt"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty());
+    for script in scripts {
+        let name = script.file_name().unwrap().to_string_lossy().into_owned();
+        let content = fs::read_to_string(&script).unwrap();
+        replay(&name, &content);
+    }
+}
+
+fn replay(name: &str, script: &str) {
+    let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    let mut brute = BruteForce::default();
+    for (i, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let at = format!("{name}:{}: {line}", i + 1);
+        let operation: Operation = line.parse().unwrap_or_else(|err| panic!("{at}: {err}"));
+        match operation {
+            Operation::MakeTree(weight) => {
+                let v = lctree.make_tree(weight);
+                brute.make_tree(v, weight);
+            }
+            Operation::RemoveTree(v) => {
+                let removed = lctree.remove_tree(v).is_ok();
+                assert_eq!(removed, brute.adj[v].is_empty(), "{at}");
+                if removed {
+                    brute.weights[v] = None;
+                }
+            }
+            Operation::Link(v, w) => {
+                let expected = !brute.connected(v, w);
+                assert_eq!(lctree.link(v, w).is_linked(), expected, "{at}");
+                if expected {
+                    brute.adj[v].insert(w);
+                    brute.adj[w].insert(v);
+                }
+            }
+            Operation::Cut(v, w) => {
+                let expected = brute.adj[v].remove(&w);
+                brute.adj[w].remove(&v);
+                assert_eq!(lctree.cut(v, w).is_cut(), expected, "{at}");
+            }
+            Operation::Linked(v, w) => {
+                assert_eq!(lctree.linked(v, w), brute.adj[v].contains(&w), "{at}");
+            }
+            Operation::Connected(v, w) => {
+                assert_eq!(lctree.connected(v, w), brute.connected(v, w), "{at}");
+            }
+            Operation::Path(v, w) => {
+                let expected = brute.path(v, w).map(|path| brute.sum(&path));
+                let actual = Some(lctree.path(v, w).sum).filter(|sum| sum.is_finite());
+                assert_eq!(actual, expected, "{at}");
+            }
+            Operation::FindRoot(v) => {
+                let root = lctree.findroot(v);
+                assert!(brute.connected(v, root), "{at}");
+            }
+            #[cfg(feature = "lazy-updates")]
+            Operation::SubtreeAdd(v, delta) => {
+                // the subtree is taken under the current root, which findroot does not change:
+                let root = lctree.findroot(v);
+                for x in brute.subtree(root, v) {
+                    *brute.weights[x].as_mut().unwrap() += delta;
+                }
+                lctree.subtree_add(v, delta);
+            }
+            Operation::ReplaceWeight(v, weight) => {
+                let old = brute.weights[v].replace(weight);
+                assert_eq!(Some(lctree.replace_weight(v, weight)), old, "{at}");
+            }
+            #[cfg(feature = "lazy-updates")]
+            Operation::PathAdd(v, w, delta) => {
+                let path = brute.path(v, w);
+                for &x in path.iter().flatten() {
+                    *brute.weights[x].as_mut().unwrap() += delta;
+                }
+                assert_eq!(lctree.path_add(v, w, delta), path.is_some(), "{at}");
+            }
+            #[cfg(feature = "lazy-updates")]
+            Operation::PathAssign(v, w, weight) => {
+                let path = brute.path(v, w);
+                for &x in path.iter().flatten() {
+                    brute.weights[x] = Some(weight);
+                }
+                assert_eq!(lctree.path_assign(v, w, weight), path.is_some(), "{at}");
+            }
+            // the rest of the script is skipped without the lazy updates:
+            #[cfg(not(feature = "lazy-updates"))]
+            Operation::SubtreeAdd(..) | Operation::PathAdd(..) | Operation::PathAssign(..) => {
+                return
+            }
+            Operation::Reroot(v) => {
+                // only changes the root, which the model does not track:
+                lctree.reroot(v);
+            }
+            Operation::IdReuse(enabled) => lctree.set_id_reuse(enabled),
+            Operation::IdReusePolicy(policy) => lctree.set_id_reuse_policy(policy),
+        }
+    }
+}
+
+#[derive(Default)]
+struct BruteForce {
+    weights: Vec<Option<f64>>, // `None` for the removed nodes
+    adj: Vec<HashSet<usize>>,
+}
+
+impl BruteForce {
+    fn make_tree(&mut self, v: usize, weight: f64) {
+        if v == self.weights.len() {
+            self.weights.push(None);
+            self.adj.push(HashSet::new());
+        }
+        self.weights[v] = Some(weight);
+    }
+
+    fn connected(&self, v: usize, w: usize) -> bool {
+        self.path(v, w).is_some()
+    }
+
+    fn sum(&self, nodes: &[usize]) -> f64 {
+        nodes.iter().map(|&v| self.weights[v].unwrap()).sum()
+    }
+
+    // Finds the nodes on the path from `src` to `dest` (if they are connected).
+    fn path(&self, src: usize, dest: usize) -> Option<Vec<usize>> {
+        let mut parent = vec![usize::MAX; self.adj.len()];
+        let mut stack = vec![src];
+        parent[src] = src;
+        while let Some(cur) = stack.pop() {
+            for &next in &self.adj[cur] {
+                if parent[next] == usize::MAX {
+                    parent[next] = cur;
+                    stack.push(next);
+                }
+            }
+        }
+        if parent[dest] == usize::MAX {
+            return None;
+        }
+        let mut path = vec![dest];
+        while *path.last().unwrap() != src {
+            path.push(parent[*path.last().unwrap()]);
+        }
+        Some(path)
+    }
+
+    // Lists the nodes of the subtree of `v` when its tree is rooted at `root`.
+    #[cfg(feature = "lazy-updates")]
+    fn subtree(&self, root: usize, v: usize) -> Vec<usize> {
+        // the path is listed from `v` to `root`:
+        let parent = self.path(root, v).unwrap().get(1).copied();
+        let mut nodes = Vec::new();
+        let mut stack = vec![(v, parent)];
+        while let Some((cur, prev)) = stack.pop() {
+            nodes.push(cur);
+            for &next in &self.adj[cur] {
+                if Some(next) != prev {
+                    stack.push((next, Some(cur)));
+                }
+            }
+        }
+        nodes
+    }
+}
@@ -0,0 +1,100 @@
+use lctree::{FindMax, FindSum, LinkCutTreeFixed};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Counts the heap allocations made by each thread of this test binary
+// (the tests run on their own threads):
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[test]
+pub fn no_allocation_after_construction() {
+    let mut lctree: LinkCutTreeFixed<FindSum, 100> = LinkCutTreeFixed::new();
+
+    let before = allocations();
+    let mut nodes = [0; 100];
+    for (i, node) in nodes.iter_mut().enumerate() {
+        *node = lctree.make_tree(i as f64).unwrap();
+    }
+    assert_eq!(lctree.make_tree(0.0), None);
+    for i in 1..nodes.len() {
+        assert!(lctree.link(nodes[i - 1], nodes[i]).is_linked());
+    }
+    assert_eq!(lctree.path(nodes[0], nodes[99]).sum, 4950.0);
+    assert!(lctree.cut(nodes[49], nodes[50]).is_cut());
+    assert!(!lctree.connected(nodes[0], nodes[99]));
+    assert_eq!(lctree.findroot(nodes[10]), nodes[49]); // the cut rerooted the tree at 49
+
+    // free a slot and reuse it:
+    assert!(lctree.cut(nodes[98], nodes[99]).is_cut());
+    lctree.remove_tree(nodes[99]).unwrap();
+    assert_eq!(lctree.make_tree(1.0), Some(nodes[99]));
+    assert_eq!(allocations(), before);
+}
+
+#[test]
+pub fn no_allocation_in_random_operations() {
+    const NUM_NODES: usize = 100;
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut lctree: LinkCutTreeFixed<FindMax, NUM_NODES> = LinkCutTreeFixed::new();
+
+    let before = allocations();
+    for _ in 0..NUM_NODES {
+        lctree.make_tree(rng.gen_range(0.0..100.0)).unwrap();
+    }
+    for _ in 0..5000 {
+        let v = rng.gen_range(0..NUM_NODES);
+        let w = rng.gen_range(0..NUM_NODES);
+        match rng.gen_range(0..6) {
+            0 => {
+                let _ = lctree.link(v, w);
+            }
+            1 => {
+                let _ = lctree.cut(v, w);
+            }
+            2 => {
+                let _ = lctree.connected(v, w);
+            }
+            3 => {
+                let _ = lctree.path(v, w);
+            }
+            4 => {
+                let _ = lctree.findroot(v);
+            }
+            _ => {
+                // remove a single node and take its slot again:
+                if lctree.remove_tree(v).is_ok() {
+                    lctree.make_tree(rng.gen_range(0.0..100.0)).unwrap();
+                }
+            }
+        }
+    }
+    assert_eq!(allocations(), before);
+}
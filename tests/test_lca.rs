@@ -0,0 +1,149 @@
+use lctree::{FindSum, LinkCutTree, TreeGenerator};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+#[cfg(feature = "depth-queries")]
+use std::collections::BTreeSet;
+
+#[test]
+pub fn lca_many() {
+    let num_nodes = 100;
+    let seed = rand::thread_rng().gen();
+    println!("Seed: {}", seed); // print seed so we can reproduce the test (if it fails).
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    TreeGenerator::new(seed).uniform_tree(&mut lctree, &vec![1.0; num_nodes]);
+    let mut adj = vec![Vec::new(); num_nodes];
+    for (v, w) in lctree.edges() {
+        adj[v].push(w);
+        adj[w].push(v);
+    }
+
+    for _ in 0..200 {
+        // change the root every now and then (path(v, w) reroots the tree at v):
+        if rng.gen_bool(0.1) {
+            let v = rng.gen_range(0..num_nodes);
+            lctree.path(v, v);
+        }
+        // the root can be found without restructuring the splay trees:
+        let root = lctree.root_of(rng.gen_range(0..num_nodes));
+        assert_eq!(lctree.findroot(0), root);
+        let depth_and_parent = rooted(&adj, root);
+
+        let k = rng.gen_range(1..6);
+        let nodes: Vec<usize> = (0..num_nodes)
+            .collect::<Vec<_>>()
+            .choose_multiple(&mut rng, k)
+            .copied()
+            .collect();
+        let expected = nodes
+            .iter()
+            .copied()
+            .reduce(|u, v| brute_lca(&depth_and_parent, u, v))
+            .unwrap();
+        assert_eq!(lctree.lca_many(&nodes), Some(expected));
+        assert_eq!(lctree.findroot(0), root); // the root does not change
+
+        // the parents, and the k-th ancestors:
+        let v = rng.gen_range(0..num_nodes);
+        let expected = Some(depth_and_parent[v].1).filter(|_| v != root);
+        assert_eq!(lctree.parent(v), expected);
+        #[cfg(feature = "depth-queries")]
+        {
+            let (depth, _) = depth_and_parent[v];
+            let k = rng.gen_range(0..depth + 2);
+            let mut expected = Some(v);
+            for _ in 0..k {
+                expected = expected
+                    .filter(|&u| u != root)
+                    .map(|u| depth_and_parent[u].1);
+            }
+            assert_eq!(lctree.ancestor(v, k), expected);
+            assert_eq!(lctree.depth(v), depth);
+
+            // the distances go through the lca:
+            let w = rng.gen_range(0..num_nodes);
+            let lca = brute_lca(&depth_and_parent, v, w);
+            let expected = depth + depth_and_parent[w].0 - 2 * depth_and_parent[lca].0;
+            assert_eq!(lctree.distance(v, w), Some(expected));
+            lctree.path(root, root); // distance(v, w) reroots the tree at v
+
+            // the virtual tree contains the nodes and the lcas of all pairs:
+            let tree = lctree.virtual_tree(&nodes).unwrap();
+            let mut expected: BTreeSet<usize> = nodes.iter().copied().collect();
+            for &u in &nodes {
+                for &v in &nodes {
+                    expected.insert(brute_lca(&depth_and_parent, u, v));
+                }
+            }
+            assert_eq!(
+                tree.nodes.iter().copied().collect::<BTreeSet<_>>(),
+                expected
+            );
+            assert_eq!(tree.edges.len(), expected.len() - 1);
+            for edge in &tree.edges {
+                // the parent is the closest ancestor in the virtual tree:
+                let mut ancestor = depth_and_parent[edge.child].1;
+                while !expected.contains(&ancestor) {
+                    ancestor = depth_and_parent[ancestor].1;
+                }
+                assert_eq!(edge.parent, ancestor);
+                let length = depth_and_parent[edge.child].0 - depth_and_parent[edge.parent].0;
+                assert_eq!(edge.path.sum, length as f64);
+            }
+
+            // the steiner tree consists of the paths from the nodes up to their lca:
+            let lca = tree.nodes[0];
+            let mut steiner = BTreeSet::new();
+            for &u in &nodes {
+                let mut u = u;
+                while u != lca {
+                    steiner.insert(u);
+                    u = depth_and_parent[u].1;
+                }
+                steiner.insert(lca);
+            }
+            let actual = lctree.steiner_aggregate(&nodes).unwrap().sum;
+            assert_eq!(actual, steiner.len() as f64);
+        }
+
+        // the subtree sizes under another root (which becomes the root of the tree):
+        #[cfg(feature = "component-sizes")]
+        {
+            let root = rng.gen_range(0..num_nodes);
+            let depth_and_parent = rooted(&adj, root);
+            let v = rng.gen_range(0..num_nodes);
+            let expected = (0..num_nodes)
+                .filter(|&u| brute_lca(&depth_and_parent, u, v) == v)
+                .count();
+            assert_eq!(lctree.subtree_size(v, root), Some(expected));
+            assert_eq!(lctree.findroot(v), root);
+        }
+    }
+}
+
+// Computes the depth and the parent of each node in the tree rooted at `root`.
+fn rooted(adj: &[Vec<usize>], root: usize) -> Vec<(usize, usize)> {
+    let mut result = vec![(usize::MAX, usize::MAX); adj.len()];
+    result[root] = (0, root);
+    let mut stack = vec![root];
+    while let Some(v) = stack.pop() {
+        for &w in &adj[v] {
+            if result[w].0 == usize::MAX {
+                result[w] = (result[v].0 + 1, v);
+                stack.push(w);
+            }
+        }
+    }
+    result
+}
+
+fn brute_lca(depth_and_parent: &[(usize, usize)], mut u: usize, mut v: usize) -> usize {
+    while u != v {
+        if depth_and_parent[u].0 >= depth_and_parent[v].0 {
+            u = depth_and_parent[u].1;
+        } else {
+            v = depth_and_parent[v].1;
+        }
+    }
+    u
+}
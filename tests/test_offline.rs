@@ -0,0 +1,64 @@
+use lctree::OfflineSpanningForest;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[test]
+pub fn offline_spanning_forest() {
+    let num_vertices: usize = 20;
+    let num_operations: usize = 2000;
+
+    let seed = rand::thread_rng().gen();
+    println!("Seed: {}", seed); // print seed so we can reproduce the test (if it fails).
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Integer weights, so that the sums are exact:
+    let mut timeline = OfflineSpanningForest::new(num_vertices);
+    let mut edges: Vec<(usize, usize, f64)> = Vec::new();
+    let mut expected = Vec::new();
+    for _ in 0..num_operations {
+        let u = rng.gen_range(0..num_vertices);
+        let v = rng.gen_range(0..num_vertices);
+        match rng.gen_range(0..3) {
+            0 => {
+                let weight = f64::from(rng.gen_range(0..100));
+                timeline.insert_edge(u, v, weight);
+                edges.push((u, v, weight));
+            }
+            1 => {
+                // the most recently inserted edge between u and v is deleted:
+                let last = edges
+                    .iter()
+                    .rposition(|&(x, y, _)| (x, y) == (u, v) || (x, y) == (v, u));
+                assert_eq!(timeline.delete_edge(u, v), last.is_some());
+                if let Some(last) = last {
+                    edges.remove(last);
+                }
+            }
+            _ => {
+                timeline.query();
+                expected.push(kruskal(num_vertices, &edges));
+            }
+        }
+    }
+    assert_eq!(timeline.solve(), expected);
+}
+
+fn kruskal(num_vertices: usize, edges: &[(usize, usize, f64)]) -> f64 {
+    let mut sorted = edges.to_vec();
+    sorted.sort_by(|a, b| a.2.total_cmp(&b.2));
+    let mut parent = (0..num_vertices).collect::<Vec<_>>();
+    fn find(parent: &mut [usize], x: usize) -> usize {
+        if parent[x] != x {
+            parent[x] = find(parent, parent[x]);
+        }
+        parent[x]
+    }
+    let mut total = 0.0;
+    for (u, v, weight) in sorted {
+        let (ru, rv) = (find(&mut parent, u), find(&mut parent, v));
+        if ru != rv {
+            parent[ru] = rv;
+            total += weight;
+        }
+    }
+    total
+}
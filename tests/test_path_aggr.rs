@@ -1,4 +1,4 @@
-use lctree::{FindSum, LinkCutTree, Path};
+use lctree::{FindMax, FindMin, FindSum, LinkCutTree, Path};
 
 #[test]
 pub fn path_aggregation() {
@@ -30,7 +30,28 @@ pub fn path_aggregation() {
     assert_eq!(result.sum, 8. + 1. + 9. + 2. + 4.);
 }
 
-#[derive(Copy, Clone)]
+#[test]
+pub fn compare_results() {
+    // The results of the built-in aggregates can be compared and printed as a whole:
+    let mut lctree: LinkCutTree<FindMax> = LinkCutTree::new();
+    let a = lctree.make_tree(9.);
+    let b = lctree.make_tree(1.);
+    let c = lctree.make_tree(8.);
+    lctree.link(b, a);
+    lctree.link(c, b);
+    assert_eq!(lctree.path(c, b), FindMax { idx: c, weight: 8. });
+    assert_eq!(
+        format!("{:?}", lctree.path(c, a)),
+        "FindMax { idx: 0, weight: 9.0 }"
+    );
+
+    let mut lctree: LinkCutTree<FindMin> = lctree.into_aggregate();
+    assert_eq!(lctree.path(c, a), FindMin { idx: b, weight: 1. });
+    let mut lctree: LinkCutTree<FindSum> = lctree.into_aggregate();
+    assert_ne!(lctree.path(c, a), lctree.path(c, b));
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct FindXor {
     pub xor: u64,
 }
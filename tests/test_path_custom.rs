@@ -1,6 +1,6 @@
 use lctree::{LinkCutTree, Path};
 
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct FindXor {
     pub xor: u64,
 }
@@ -15,6 +15,46 @@ impl Path for FindXor {
     }
 }
 
+// The `k` largest weights on a path (aggregates only have to be `Clone`, not `Copy`):
+#[derive(Clone)]
+pub struct TopK {
+    pub weights: Vec<f64>,
+}
+
+const K: usize = 3;
+
+// The number of nodes on a path, where the nodes created without a weight count once:
+#[derive(Copy, Clone)]
+pub struct CountNodes {
+    pub count: f64,
+}
+
+impl Path for CountNodes {
+    const DEFAULT_WEIGHT: f64 = 1.0;
+
+    fn default(weight: f64, _: usize) -> Self {
+        CountNodes { count: weight }
+    }
+
+    fn aggregate(&mut self, other: Self) {
+        self.count += other.count;
+    }
+}
+
+impl Path for TopK {
+    fn default(weight: f64, _: usize) -> Self {
+        TopK {
+            weights: vec![weight],
+        }
+    }
+
+    fn aggregate(&mut self, other: Self) {
+        self.weights.extend(other.weights);
+        self.weights.sort_by(|a, b| b.total_cmp(a));
+        self.weights.truncate(K);
+    }
+}
+
 #[test]
 pub fn custom_path_aggregation() {
     // We form a link-cut tree from the following rooted tree
@@ -42,3 +82,52 @@ pub fn custom_path_aggregation() {
     let result = lctree.path(c, f);
     assert_eq!(result.xor, 8 ^ 1 ^ 9 ^ 2 ^ 4);
 }
+
+#[test]
+pub fn compare_and_print_path_results() {
+    let mut lctree: LinkCutTree<FindXor> = LinkCutTree::new();
+    let a = lctree.make_tree(3.);
+    let b = lctree.make_tree(5.);
+    lctree.link(a, b);
+
+    // Path results can be compared and printed as a whole:
+    assert_eq!(lctree.path(a, b), FindXor { xor: 3 ^ 5 });
+    assert_eq!(lctree.path(a, b), lctree.path(b, a));
+    assert_eq!(format!("{:?}", lctree.path(a, a)), "FindXor { xor: 3 }");
+}
+
+#[test]
+pub fn heap_allocated_path_aggregation() {
+    // The same tree as above:
+    let mut lctree: LinkCutTree<TopK> = LinkCutTree::new();
+    let nodes = lctree.extend_forest([9., 1., 8., 10., 2., 4.]);
+    let (a, b, c, d, e, f) = (nodes[0], nodes[1], nodes[2], nodes[3], nodes[4], nodes[5]);
+
+    lctree.link(b, a);
+    lctree.link(c, b);
+    lctree.link(d, b);
+    lctree.link(e, a);
+    lctree.link(f, e);
+
+    assert_eq!(lctree.path(c, f).weights, vec![9., 8., 4.]);
+    assert_eq!(lctree.path(d, c).weights, vec![10., 8., 1.]);
+    assert_eq!(lctree.path(e, e).weights, vec![2.]);
+
+    lctree.cut(b, a);
+    lctree.link(b, f);
+    assert_eq!(lctree.path(a, d).weights, vec![10., 9., 4.]);
+}
+
+#[test]
+pub fn default_weight() {
+    let mut lctree: LinkCutTree<CountNodes> = LinkCutTree::new();
+    let a = lctree.make_tree_default();
+    let b = lctree.make_tree_default();
+    let c = lctree.make_tree_default();
+    let heavy = lctree.make_tree(10.);
+    lctree.link(a, b);
+    lctree.link(b, c);
+    lctree.link(heavy, c);
+    assert_eq!(lctree.path(a, c).count, 3.);
+    assert_eq!(lctree.path(a, heavy).count, 13.);
+}
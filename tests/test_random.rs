@@ -62,6 +62,128 @@ pub fn validation() {
     }
 }
 
+#[test]
+pub fn forest_queries() {
+    let num_nodes: usize = 100;
+    let num_operations: usize = 2000;
+
+    let seed = rand::thread_rng().gen();
+    println!("Seed: {}", seed); // print seed so we can reproduce the test (if it fails).
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut lctree = LinkCutTree::default();
+    let _ = lctree.extend_forest(vec![0.0; num_nodes]);
+    let mut brute = BruteForce::new(vec![0.0; num_nodes]);
+    for _ in 0..num_operations {
+        let v = rng.gen_range(0..num_nodes);
+        let w = rng.gen_range(0..num_nodes);
+        if rng.gen_bool(0.5) {
+            lctree.link(v, w);
+            brute.link(v, w);
+        } else {
+            lctree.cut(v, w);
+            brute.cut(v, w);
+        }
+    }
+
+    // The edges of the forest should match the brute force:
+    let mut actual = lctree
+        .edges()
+        .map(|(v, w)| (v.min(w), v.max(w)))
+        .collect::<Vec<_>>();
+    actual.sort_unstable();
+    assert_eq!(actual, brute.edges());
+
+    // The pairwise connectivity of a sample of nodes should match the brute force:
+    let sample: Vec<usize> = (0..num_nodes).filter(|_| rng.gen_bool(0.3)).collect();
+    let matrix = lctree.connectivity_matrix(&sample);
+    for (i, &v) in sample.iter().enumerate() {
+        for (j, &w) in sample.iter().enumerate() {
+            assert_eq!(matrix.get(i, j), brute.connected(v, w));
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "component-sizes")]
+pub fn component_sizes() {
+    let num_nodes: usize = 100;
+    let num_operations: usize = 2000;
+
+    let seed = rand::thread_rng().gen();
+    println!("Seed: {}", seed); // print seed so we can reproduce the test (if it fails).
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut lctree = LinkCutTree::default();
+    let _ = lctree.extend_forest(vec![0.0; num_nodes]);
+    lctree.set_component_tracking(true);
+    let mut brute = BruteForce::new(vec![0.0; num_nodes]);
+
+    // The sizes of the trees should match the brute force after random links and cuts:
+    for _ in 0..num_operations {
+        let v = rng.gen_range(0..num_nodes);
+        let w = rng.gen_range(0..num_nodes);
+        let operation: Operation = rng.gen();
+        match operation {
+            Operation::Link => {
+                lctree.link(v, w);
+                brute.link(v, w);
+            }
+            Operation::Cut => {
+                lctree.cut(v, w);
+                brute.cut(v, w);
+            }
+            Operation::Connected | Operation::Path => {
+                assert_eq!(lctree.component_size(v), brute.component_size(v));
+                let expected = (0..num_nodes).map(|x| brute.component_size(x)).max();
+                assert_eq!(Some(lctree.max_component_size()), expected);
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "component-sizes")]
+pub fn component_ids() {
+    let num_nodes: usize = 100;
+    let num_operations: usize = 2000;
+
+    let seed = rand::thread_rng().gen();
+    println!("Seed: {}", seed); // print seed so we can reproduce the test (if it fails).
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut lctree = LinkCutTree::default();
+    let _ = lctree.extend_forest(vec![0.0; num_nodes]);
+    let mut brute = BruteForce::new(vec![0.0; num_nodes]);
+
+    // Two nodes should share an id exactly when they are connected:
+    for _ in 0..num_operations {
+        let v = rng.gen_range(0..num_nodes);
+        let w = rng.gen_range(0..num_nodes);
+        let operation: Operation = rng.gen();
+        match operation {
+            Operation::Link => {
+                lctree.link(v, w);
+                brute.link(v, w);
+            }
+            Operation::Cut => {
+                lctree.cut(v, w);
+                brute.cut(v, w);
+            }
+            Operation::Connected => {
+                let same_id = lctree.component_id(v) == lctree.component_id(w);
+                assert_eq!(same_id, brute.connected(v, w));
+            }
+            Operation::Path => {
+                // path queries reroot the tree, which keeps its id:
+                let id = lctree.component_id(v);
+                lctree.path(v, w);
+                assert_eq!(lctree.component_id(v), id);
+            }
+        }
+    }
+}
+
 #[derive(RandGen)]
 enum Operation {
     Link,
@@ -137,6 +259,26 @@ impl BruteForce {
         self.component_ids[v] == self.component_ids[w]
     }
 
+    #[cfg(feature = "component-sizes")]
+    pub fn component_size(&self, v: usize) -> usize {
+        let component_id = self.component_ids[v];
+        self.component_ids
+            .iter()
+            .filter(|&&id| id == component_id)
+            .count()
+    }
+
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = Vec::new();
+        for (v, neighbors) in self.adj.iter().enumerate() {
+            for &w in neighbors.iter().filter(|&&w| v < w) {
+                edges.push((v, w));
+            }
+        }
+        edges.sort_unstable();
+        edges
+    }
+
     pub fn findmax(&self, src: usize, dest: usize) -> usize {
         if self.component_ids[src] != self.component_ids[dest] {
             return usize::MAX;
@@ -0,0 +1,150 @@
+use lctree::{FindSum, LinkCutTree};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashSet;
+
+#[test]
+pub fn subtree_add() {
+    let num_nodes: usize = 100;
+    let num_operations: usize = 5000;
+
+    let seed = rand::thread_rng().gen();
+    println!("Seed: {}", seed); // print seed so we can reproduce the test (if it fails).
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Integer weights and deltas, so that the sums are exact:
+    let mut weights = (0..num_nodes)
+        .map(|_| f64::from(rng.gen_range(0..100)))
+        .collect::<Vec<_>>();
+    let mut lctree: LinkCutTree<FindSum> = LinkCutTree::new();
+    let _ = lctree.extend_forest(&weights);
+    let mut adj = vec![HashSet::new(); num_nodes];
+
+    for _ in 0..num_operations {
+        let v = rng.gen_range(0..num_nodes);
+        let w = rng.gen_range(0..num_nodes);
+        match rng.gen_range(0..8) {
+            0 => {
+                if lctree.link(v, w).is_linked() {
+                    adj[v].insert(w);
+                    adj[w].insert(v);
+                }
+            }
+            1 => {
+                if lctree.cut(v, w).is_cut() {
+                    adj[v].remove(&w);
+                    adj[w].remove(&v);
+                }
+            }
+            2 => {
+                let delta = f64::from(rng.gen_range(-10..10));
+                let root = lctree.findroot(v);
+                for node in subtree(&adj, root, v) {
+                    weights[node] += delta;
+                }
+                lctree.subtree_add(v, delta);
+            }
+            3 => {
+                // a batch of queries (mostly sharing their first nodes):
+                let mut pairs = (0..10)
+                    .map(|_| (v, rng.gen_range(0..num_nodes)))
+                    .collect::<Vec<_>>();
+                pairs.extend((0..5).map(|_| (rng.gen_range(0..num_nodes), w)));
+                let actual = lctree.path_many(&pairs);
+                for (&(x, y), actual) in pairs.iter().zip(actual) {
+                    let expected =
+                        path(&adj, x, y).map(|path| path.iter().map(|&z| weights[z]).sum());
+                    assert_eq!(actual.map(|path| path.sum), expected);
+                }
+            }
+            4 => {
+                // the previous weight includes the pending additions:
+                let weight = f64::from(rng.gen_range(0..100));
+                assert_eq!(lctree.replace_weight(v, weight), weights[v]);
+                weights[v] = weight;
+            }
+            5 => {
+                let delta = f64::from(rng.gen_range(-10..10));
+                let nodes = path(&adj, v, w);
+                for &node in nodes.iter().flatten() {
+                    weights[node] += delta;
+                }
+                assert_eq!(lctree.path_add(v, w, delta), nodes.is_some());
+            }
+            6 => {
+                let weight = f64::from(rng.gen_range(0..100));
+                let nodes = path(&adj, v, w);
+                for &node in nodes.iter().flatten() {
+                    weights[node] = weight;
+                }
+                assert_eq!(lctree.path_assign(v, w, weight), nodes.is_some());
+            }
+            _ => {
+                let expected = path(&adj, v, w).map(|path| path.iter().map(|&x| weights[x]).sum());
+                let actual = lctree.path(v, w).sum;
+                assert_eq!(Some(actual).filter(|sum| sum.is_finite()), expected);
+            }
+        }
+    }
+
+    // The snapshots read the pending additions without applying them:
+    let snapshot = lctree.snapshot();
+    let pending: Vec<f64> = (0..num_nodes)
+        .map(|v| snapshot.weight(v).unwrap())
+        .collect();
+    assert_eq!(pending, weights);
+    for (v, &weight) in weights.iter().enumerate() {
+        assert_eq!(lctree.node(v).unwrap().weight, weight);
+    }
+
+    // The pending additions are applied before the weights are rewritten:
+    let mut actual = vec![0.0; num_nodes];
+    lctree.map_weights(|idx, weight| {
+        actual[idx] = weight;
+        weight
+    });
+    assert_eq!(actual, weights);
+}
+
+// Lists the nodes of the subtree of `v` when its tree is rooted at `root`.
+fn subtree(adj: &[HashSet<usize>], root: usize, v: usize) -> Vec<usize> {
+    // the path is listed from `v` to `root`:
+    let parent = path(adj, root, v)
+        .unwrap()
+        .get(1)
+        .copied()
+        .unwrap_or(usize::MAX);
+    let mut nodes = Vec::new();
+    let mut stack = vec![(v, parent)];
+    while let Some((cur, prev)) = stack.pop() {
+        nodes.push(cur);
+        for &next in &adj[cur] {
+            if next != prev {
+                stack.push((next, cur));
+            }
+        }
+    }
+    nodes
+}
+
+// Finds the nodes on the path from `src` to `dest` (if they are connected).
+fn path(adj: &[HashSet<usize>], src: usize, dest: usize) -> Option<Vec<usize>> {
+    let mut parent = vec![usize::MAX; adj.len()];
+    let mut stack = vec![src];
+    parent[src] = src;
+    while let Some(cur) = stack.pop() {
+        for &next in &adj[cur] {
+            if parent[next] == usize::MAX {
+                parent[next] = cur;
+                stack.push(next);
+            }
+        }
+    }
+    if parent[dest] == usize::MAX {
+        return None;
+    }
+    let mut path = vec![dest];
+    while *path.last().unwrap() != src {
+        path.push(parent[*path.last().unwrap()]);
+    }
+    Some(path)
+}
@@ -0,0 +1,49 @@
+use lctree::SlidingWindowConnectivity;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[test]
+pub fn sliding_window() {
+    let num_vertices = 30;
+    let window = 20;
+    let seed = rand::thread_rng().gen();
+    println!("Seed: {}", seed); // print seed so we can reproduce the test (if it fails).
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut connectivity = SlidingWindowConnectivity::new(num_vertices, window);
+    let mut edges: Vec<(usize, usize, u64)> = Vec::new();
+    let mut time = 0;
+    for _ in 0..2000 {
+        time += rng.gen_range(0..3);
+        let u = rng.gen_range(0..num_vertices);
+        let v = rng.gen_range(0..num_vertices);
+        if rng.gen_bool(0.5) {
+            connectivity.insert_edge(u, v, time);
+            edges.push((u, v, time));
+        } else {
+            connectivity.advance(time);
+            let active: Vec<(usize, usize)> = edges
+                .iter()
+                .filter(|&&(_, _, inserted)| inserted + window > time)
+                .map(|&(a, b, _)| (a, b))
+                .collect();
+            let expected = brute_connected(num_vertices, &active, u, v);
+            assert_eq!(connectivity.connected(u, v), expected);
+        }
+    }
+}
+
+// Checks the connectivity with a union-find over the active edges:
+fn brute_connected(n: usize, edges: &[(usize, usize)], u: usize, v: usize) -> bool {
+    fn find(parent: &mut [usize], x: usize) -> usize {
+        if parent[x] != x {
+            parent[x] = find(parent, parent[x]);
+        }
+        parent[x]
+    }
+    let mut parent: Vec<usize> = (0..n).collect();
+    for &(a, b) in edges {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        parent[ra] = rb;
+    }
+    find(&mut parent, u) == find(&mut parent, v)
+}